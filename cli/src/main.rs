@@ -0,0 +1,1780 @@
+//! Command-line tool for operating the token program.
+//!
+//! Every command builds its transaction from the `solana-token` instruction
+//! builders. With `--sign-only` the transaction is signed with whatever
+//! keypairs are available and printed in base58 and base64 instead of being
+//! sent, so that other signers can review and co-sign it offline. Missing
+//! signatures are attached with `--signer PUBKEY=SIGNATURE` and the final
+//! transaction is submitted with `broadcast`.
+
+use std::error::Error;
+use std::str::FromStr;
+
+use base64::Engine;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{read_keypair_file, Signature, Signer};
+use solana_sdk::signer::null_signer::NullSigner;
+use solana_sdk::signer::presigner::Presigner;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use solana_token::instruction::{self, AllocationType};
+use solana_token::state::{
+    find_liquid_pool_address, find_stake_address, find_staking_config_address, AccountHeader, AccountType,
+    EmissionSchedule, Farm, FarmPosition, LiquidPool, LockTier, Locker, Operator, PenaltyCurve, PenaltyDestination,
+    StakeInfo, StakingConfig, StakingParams, StakingPool, UnbondingEntry, VestingSchedule, VoteLock,
+};
+use solana_token::token_info::{TokenInfo, DECIMALS};
+use spl_token::state::Mint;
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(name = "solana-token", version, about = "Operate the token program")]
+struct Cli {
+    /// JSON RPC URL of the cluster.
+    #[arg(long, short = 'u', global = true, env = "SOLANA_URL", default_value = "http://localhost:8899")]
+    url: String,
+
+    /// Address of the deployed token program.
+    #[arg(long, global = true, env = "TOKEN_PROGRAM_ID")]
+    program_id: Option<Pubkey>,
+
+    /// Default signer, used for the fee payer and every authority not given
+    /// explicitly.
+    #[arg(long, short = 'k', global = true, default_value = "~/.config/solana/id.json")]
+    keypair: String,
+
+    /// Fee payer, as a keypair path or a pubkey. Defaults to `--keypair`.
+    #[arg(long, global = true)]
+    fee_payer: Option<String>,
+
+    /// Sign with the available keypairs and print the transaction instead
+    /// of sending it. Signers given as a pubkey are left unsigned.
+    #[arg(long, global = true, requires = "blockhash")]
+    sign_only: bool,
+
+    /// Blockhash to sign with instead of fetching the latest one.
+    #[arg(long, global = true)]
+    blockhash: Option<Hash>,
+
+    /// Signature collected offline, attached to the signer given as a
+    /// pubkey.
+    #[arg(long = "signer", global = true, value_name = "PUBKEY=SIGNATURE")]
+    signers: Vec<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create and initialize a mint.
+    InitializeMint {
+        /// Keypair of the new mint account.
+        #[arg(long)]
+        mint: String,
+        /// Mint authority. Defaults to the default signer's pubkey.
+        #[arg(long)]
+        mint_authority: Option<Pubkey>,
+        #[arg(long)]
+        freeze_authority: Option<Pubkey>,
+        #[arg(long, default_value_t = DECIMALS)]
+        decimals: u8,
+    },
+    /// Create the token info account recording the supply allocations.
+    InitializeTokenInfo {
+        /// Keypair of the new token info account.
+        #[arg(long)]
+        token_info: String,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        mint_authority: Option<String>,
+    },
+    /// Create a vesting schedule funded from one of the allocations.
+    CreateVesting {
+        /// Keypair of the new vesting account.
+        #[arg(long)]
+        vesting: String,
+        #[arg(long)]
+        token_info: Pubkey,
+        #[arg(long)]
+        beneficiary: Pubkey,
+        #[arg(long, value_enum)]
+        allocation: Allocation,
+        /// Amount in base units.
+        #[arg(long)]
+        amount: u64,
+        /// Unix timestamp the schedule starts vesting at.
+        #[arg(long)]
+        start: i64,
+        /// Unix timestamp before which nothing is released, when what vested
+        /// since the start unlocks at once. Defaults to the start.
+        #[arg(long)]
+        cliff: Option<i64>,
+        /// Unix timestamp the schedule is fully vested at.
+        #[arg(long)]
+        end: i64,
+        #[arg(long)]
+        mint_authority: Option<String>,
+    },
+    /// Release the tokens vested so far.
+    ReleaseVested {
+        #[arg(long)]
+        vesting: Pubkey,
+        #[arg(long)]
+        beneficiary_token_account: Pubkey,
+        #[arg(long)]
+        source: Pubkey,
+        #[arg(long)]
+        source_authority: Option<String>,
+    },
+    /// Create the vault holding the tokens staked with a mint.
+    InitializeVault {
+        #[arg(long)]
+        mint: Pubkey,
+    },
+    /// Create the stake account of an owner for a mint.
+    InitializeStake {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long, value_enum)]
+        lock: Lock,
+        /// Owner of the stake, paying for the account. Defaults to the
+        /// default signer.
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Move a stake position to a longer lock.
+    ExtendLock {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long, value_enum)]
+        lock: Lock,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Stake tokens.
+    Stake {
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Amount in base units.
+        amount: u64,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Unstake tokens.
+    Unstake {
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Amount in base units.
+        amount: u64,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Move staked tokens into a new unbonding entry.
+    RequestUnstake {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Amount in base units.
+        amount: u64,
+        /// Id of the new entry. Read from the stake account by default.
+        #[arg(long)]
+        id: Option<u64>,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Withdraw an unbonding entry once its unbonding period has ended.
+    WithdrawUnbonded {
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        id: u64,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Put the tokens of an unbonding entry back into the stake.
+    CancelUnbonding {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        id: u64,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Unstake at once, paying the early unstake penalty of the staking
+    /// config on locked tokens.
+    EmergencyUnstake {
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Amount in base units, the penalty included.
+        amount: u64,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Create the liquid staking pool of a mint and its receipt mint.
+    InitializeLiquidPool {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+        /// The mint authority recorded in the token info.
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Stake through the liquid pool for receipt tokens.
+    LiquidStake {
+        #[arg(long)]
+        token_account: Pubkey,
+        /// Receipt token account to mint to.
+        #[arg(long)]
+        receipt_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Amount in base units.
+        amount: u64,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Redeem receipt tokens of the liquid pool for the tokens they are
+    /// worth.
+    LiquidUnstake {
+        #[arg(long)]
+        token_account: Pubkey,
+        /// Receipt token account to burn from.
+        #[arg(long)]
+        receipt_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Receipt amount in base units.
+        receipt_amount: u64,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Redeem receipt tokens of the liquid pool through a new unbonding
+    /// entry of the pool.
+    RequestLiquidUnstake {
+        /// Receipt token account to burn from.
+        #[arg(long)]
+        receipt_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Receipt amount in base units.
+        receipt_amount: u64,
+        /// Id of the new entry. Read from the stake account of the pool by
+        /// default.
+        #[arg(long)]
+        id: Option<u64>,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Withdraw an unbonding entry of the liquid pool once its unbonding
+    /// period has ended.
+    WithdrawLiquidUnbonded {
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        id: u64,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Compound the pending rewards of a stake position into it.
+    CompoundRewards {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Owner of a position that opted into auto-compound, to compound
+        /// it without their signature.
+        #[arg(long, conflicts_with = "staker")]
+        owner: Option<Pubkey>,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Opt a stake position in or out of auto-compound.
+    SetAutoCompound {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Opt out instead of in.
+        #[arg(long)]
+        disable: bool,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Create the vote-escrow locker of a mint and its vault.
+    InitializeLocker {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+        /// Longest lock in seconds, a whole number of weeks.
+        #[arg(long)]
+        max_lock_duration: i64,
+        /// The mint authority recorded in the token info.
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Lock tokens in the locker for voting power.
+    CreateVoteLock {
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Amount in base units.
+        amount: u64,
+        /// Unix timestamp of the unlock, rounded down to a week.
+        #[arg(long)]
+        unlock_time: i64,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Add tokens to a vote lock.
+    IncreaseVoteLockAmount {
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        /// Amount in base units.
+        amount: u64,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Move the unlock time of a vote lock later.
+    ExtendVoteLock {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Unix timestamp of the new unlock, rounded down to a week.
+        #[arg(long)]
+        unlock_time: i64,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Withdraw the tokens of an expired vote lock.
+    WithdrawVoteLock {
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Register as a staking operator of a mint.
+    RegisterOperator {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Share of the rewards of delegated positions, in basis points.
+        #[arg(long)]
+        commission_bps: u16,
+        #[arg(long)]
+        authority: Option<String>,
+    },
+    /// Delegate a stake position to an operator.
+    DelegateStake {
+        #[arg(long)]
+        mint: Pubkey,
+        /// Address of the operator account.
+        #[arg(long)]
+        operator: Pubkey,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Take a stake position away from its operator.
+    UndelegateStake {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Claim the commission earned on delegated positions.
+    ClaimCommission {
+        #[arg(long)]
+        destination: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        authority: Option<String>,
+    },
+    /// Create a farm rewarding stakers of any mint, and its vaults.
+    CreateFarm {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+        /// Id of the farm among the farms of the mint.
+        #[arg(long)]
+        id: u64,
+        #[arg(long)]
+        staked_mint: Pubkey,
+        #[arg(long)]
+        reward_mint: Pubkey,
+        /// Rewards emitted per second, in base units.
+        #[arg(long)]
+        emission_rate: u64,
+        /// Unix timestamp emission starts at.
+        #[arg(long)]
+        start_time: i64,
+        /// Unix timestamp emission ends at.
+        #[arg(long)]
+        end_time: i64,
+        /// The mint authority recorded in the token info.
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Add rewards to a farm.
+    FundFarm {
+        #[arg(long)]
+        farm: Pubkey,
+        #[arg(long)]
+        source: Pubkey,
+        /// Amount in base units of the reward mint.
+        amount: u64,
+        #[arg(long)]
+        funder: Option<String>,
+    },
+    /// Change the emission rate and end time of a farm.
+    UpdateFarm {
+        #[arg(long)]
+        farm: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+        /// Rewards emitted per second from now on, in base units.
+        #[arg(long)]
+        emission_rate: u64,
+        /// Unix timestamp emission ends at.
+        #[arg(long)]
+        end_time: i64,
+        /// The mint authority recorded in the token info.
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Stake tokens in a farm.
+    FarmStake {
+        #[arg(long)]
+        farm: Pubkey,
+        #[arg(long)]
+        token_account: Pubkey,
+        /// Amount in base units of the staked mint.
+        amount: u64,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Unstake tokens from a farm.
+    FarmUnstake {
+        #[arg(long)]
+        farm: Pubkey,
+        #[arg(long)]
+        token_account: Pubkey,
+        /// Amount in base units of the staked mint.
+        amount: u64,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Claim the rewards of a farm position.
+    ClaimFarmRewards {
+        #[arg(long)]
+        farm: Pubkey,
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        owner: Option<String>,
+    },
+    /// Put the staking pool of a mint on an epoch-based emission schedule,
+    /// starting now.
+    InitializeEmissionSchedule {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+        /// Length of an epoch in seconds, at least an hour.
+        #[arg(long)]
+        epoch_duration: i64,
+        /// Rewards emitted by the first epoch, in base units.
+        #[arg(long)]
+        initial_emission: u64,
+        /// Drop in emission at every epoch, in basis points. 5000 halves it.
+        #[arg(long)]
+        decay_bps: u16,
+        /// The mint authority recorded in the token info.
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Start the next epoch of the emission schedule of a mint.
+    AdvanceEpoch {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+    },
+    /// Create the staking pool of a mint and its reward vault.
+    InitializeStakingPool {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+        /// Rewards emitted per second, in base units.
+        #[arg(long)]
+        reward_rate: u64,
+        /// The mint authority recorded in the token info.
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Create the staking config of a mint.
+    InitializeStakingConfig {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+        #[command(flatten)]
+        params: StakingParamsArgs,
+        /// The mint authority recorded in the token info.
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Replace the staking rules of a mint.
+    UpdateStakingConfig {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+        #[command(flatten)]
+        params: StakingParamsArgs,
+        /// The mint authority recorded in the token info.
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Move tokens from the community rewards allocation into the reward
+    /// vault.
+    FundRewards {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        token_info: Pubkey,
+        /// Token account of the admin the rewards are taken from.
+        #[arg(long)]
+        source: Pubkey,
+        /// Amount in base units.
+        amount: u64,
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Claim staking rewards.
+    ClaimRewards {
+        #[arg(long)]
+        token_account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        staker: Option<String>,
+    },
+    /// Freeze a token account.
+    Freeze {
+        account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        freeze_authority: Option<String>,
+    },
+    /// Thaw a frozen token account.
+    Thaw {
+        account: Pubkey,
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long)]
+        freeze_authority: Option<String>,
+    },
+    /// Change or remove an authority of a mint or token account.
+    SetAuthority {
+        account: Pubkey,
+        #[arg(long, value_enum)]
+        authority_type: AuthorityType,
+        #[arg(long, required_unless_present = "disable")]
+        new_authority: Option<Pubkey>,
+        /// Remove the authority instead of replacing it.
+        #[arg(long, conflicts_with = "new_authority")]
+        disable: bool,
+        #[arg(long)]
+        current_authority: Option<String>,
+    },
+    /// Rewrite an account stored in an older layout in the current one.
+    Migrate {
+        account: Pubkey,
+        #[arg(long, value_enum)]
+        account_type: MigratedAccount,
+        #[arg(long)]
+        token_info: Pubkey,
+        /// The mint authority recorded in the token info.
+        #[arg(long)]
+        admin: Option<String>,
+    },
+    /// Decode and print a program account.
+    Show { address: Pubkey },
+    /// Send a transaction produced with `--sign-only` once fully signed.
+    Broadcast {
+        transaction: String,
+        #[arg(long, value_enum, default_value_t = Encoding::Base64)]
+        encoding: Encoding,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Allocation {
+    Team,
+    Investors,
+    Liquidity,
+    Development,
+    Community,
+    Strategic,
+}
+
+impl From<Allocation> for AllocationType {
+    fn from(allocation: Allocation) -> Self {
+        match allocation {
+            Allocation::Team => AllocationType::Team,
+            Allocation::Investors => AllocationType::Investors,
+            Allocation::Liquidity => AllocationType::Liquidity,
+            Allocation::Development => AllocationType::Development,
+            Allocation::Community => AllocationType::Community,
+            Allocation::Strategic => AllocationType::Strategic,
+        }
+    }
+}
+
+#[derive(Args)]
+struct StakingParamsArgs {
+    /// Smallest position a staker may hold, in base units.
+    #[arg(long, default_value_t = 0)]
+    min_stake: u64,
+    /// Seconds a stake stays locked after its last deposit.
+    #[arg(long)]
+    lock_duration: i64,
+    /// Rewards emitted per second, in base units.
+    #[arg(long)]
+    reward_rate: u64,
+    /// Cap on the total staked across all positions, in base units.
+    #[arg(long, default_value_t = u64::MAX)]
+    max_total_stake: u64,
+    /// Reject stakes and unstakes until unpaused.
+    #[arg(long)]
+    paused: bool,
+    /// Seconds between requesting an unstake and withdrawing it. Zero
+    /// unstakes at once.
+    #[arg(long, default_value_t = 0)]
+    unbonding_period: i64,
+    /// How locked tokens are charged for unstaking early.
+    #[arg(long, value_enum, default_value_t = Curve::Disabled)]
+    penalty_curve: Curve,
+    /// Early unstake penalty at the start of the lock, in basis points.
+    #[arg(long, default_value_t = 0)]
+    penalty_bps: u16,
+    /// Where early unstake penalties go.
+    #[arg(long, value_enum, default_value_t = Destination::Burn)]
+    penalty_destination: Destination,
+    /// Token account receiving penalties sent to the treasury.
+    #[arg(long, default_value_t = Pubkey::default())]
+    treasury: Pubkey,
+}
+
+impl From<StakingParamsArgs> for StakingParams {
+    fn from(args: StakingParamsArgs) -> Self {
+        StakingParams {
+            min_stake: args.min_stake,
+            lock_duration: args.lock_duration,
+            reward_rate: args.reward_rate,
+            max_total_stake: args.max_total_stake,
+            paused: args.paused,
+            unbonding_period: args.unbonding_period,
+            penalty_curve: args.penalty_curve.into(),
+            penalty_bps: args.penalty_bps,
+            penalty_destination: args.penalty_destination.into(),
+            treasury: args.treasury,
+        }
+    }
+}
+
+/// Early unstake penalty curve of a staking config.
+#[derive(Clone, Copy, ValueEnum)]
+enum Curve {
+    Disabled,
+    LinearDecay,
+    Flat,
+}
+
+impl From<Curve> for PenaltyCurve {
+    fn from(curve: Curve) -> Self {
+        match curve {
+            Curve::Disabled => PenaltyCurve::Disabled,
+            Curve::LinearDecay => PenaltyCurve::LinearDecay,
+            Curve::Flat => PenaltyCurve::Flat,
+        }
+    }
+}
+
+/// Destination of early unstake penalties.
+#[derive(Clone, Copy, ValueEnum)]
+enum Destination {
+    Burn,
+    Treasury,
+    RewardPool,
+}
+
+impl From<Destination> for PenaltyDestination {
+    fn from(destination: Destination) -> Self {
+        match destination {
+            Destination::Burn => PenaltyDestination::Burn,
+            Destination::Treasury => PenaltyDestination::Treasury,
+            Destination::RewardPool => PenaltyDestination::RewardPool,
+        }
+    }
+}
+
+/// Lock tier of a stake position.
+#[derive(Clone, Copy, ValueEnum)]
+enum Lock {
+    #[value(name = "30d")]
+    Days30,
+    #[value(name = "90d")]
+    Days90,
+    #[value(name = "180d")]
+    Days180,
+    #[value(name = "365d")]
+    Days365,
+}
+
+impl From<Lock> for LockTier {
+    fn from(lock: Lock) -> Self {
+        match lock {
+            Lock::Days30 => LockTier::Days30,
+            Lock::Days90 => LockTier::Days90,
+            Lock::Days180 => LockTier::Days180,
+            Lock::Days365 => LockTier::Days365,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum MigratedAccount {
+    TokenInfo,
+    Vesting,
+    Stake,
+    StakingPool,
+    StakingConfig,
+}
+
+impl From<MigratedAccount> for AccountType {
+    fn from(account: MigratedAccount) -> Self {
+        match account {
+            MigratedAccount::TokenInfo => AccountType::TokenInfo,
+            MigratedAccount::Vesting => AccountType::VestingSchedule,
+            MigratedAccount::Stake => AccountType::StakeInfo,
+            MigratedAccount::StakingPool => AccountType::StakingPool,
+            MigratedAccount::StakingConfig => AccountType::StakingConfig,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum AuthorityType {
+    MintTokens,
+    FreezeAccount,
+    AccountOwner,
+    CloseAccount,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Encoding {
+    Base58,
+    Base64,
+}
+
+struct Context {
+    rpc: RpcClient,
+    program_id: Option<Pubkey>,
+    default_signer: String,
+    fee_payer: Box<dyn Signer>,
+    sign_only: bool,
+    blockhash: Option<Hash>,
+    presigned: Vec<(Pubkey, Signature)>,
+}
+
+impl Context {
+    fn program_id(&self) -> CliResult<Pubkey> {
+        self.program_id
+            .ok_or_else(|| "--program-id or TOKEN_PROGRAM_ID is required".into())
+    }
+
+    /// Resolves a keypair path or a pubkey into a signer. A pubkey resolves
+    /// to its `--signer` signature if one was given, and to an empty
+    /// signature when signing offline.
+    fn signer(&self, spec: Option<&str>) -> CliResult<Box<dyn Signer>> {
+        resolve_signer(
+            spec.unwrap_or(&self.default_signer),
+            &self.presigned,
+            self.sign_only,
+        )
+    }
+
+    async fn rent_exempt_lamports(&self, space: usize) -> CliResult<u64> {
+        if self.sign_only {
+            return Ok(Rent::default().minimum_balance(space));
+        }
+        Ok(self.rpc.get_minimum_balance_for_rent_exemption(space).await?)
+    }
+
+    /// The operator the position of `owner` is delegated to, which
+    /// instructions on the position have to pass along.
+    async fn delegated_operator(&self, mint: &Pubkey, owner: &Pubkey) -> CliResult<Option<Pubkey>> {
+        let (stake_account, _) = find_stake_address(&self.program_id()?, mint, owner);
+        let account = self
+            .rpc
+            .get_account_with_commitment(&stake_account, self.rpc.commitment())
+            .await?
+            .value;
+        match account {
+            Some(account) => {
+                let stake_info = StakeInfo::unpack(&account.data)?;
+                Ok(stake_info.is_delegated().then_some(stake_info.operator))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn create_account(
+        &self,
+        account: &Pubkey,
+        space: usize,
+        owner: &Pubkey,
+    ) -> CliResult<Instruction> {
+        Ok(system_instruction::create_account(
+            &self.fee_payer.pubkey(),
+            account,
+            self.rent_exempt_lamports(space).await?,
+            space as u64,
+            owner,
+        ))
+    }
+
+    async fn process(&self, instructions: &[Instruction], signers: &[&dyn Signer]) -> CliResult<()> {
+        let blockhash = match self.blockhash {
+            Some(blockhash) => blockhash,
+            None => self.rpc.get_latest_blockhash().await?,
+        };
+
+        let mut all_signers: Vec<&dyn Signer> = vec![self.fee_payer.as_ref()];
+        for signer in signers {
+            if !all_signers.iter().any(|s| s.pubkey() == signer.pubkey()) {
+                all_signers.push(*signer);
+            }
+        }
+
+        let mut transaction =
+            Transaction::new_with_payer(instructions, Some(&self.fee_payer.pubkey()));
+        transaction.try_partial_sign(&all_signers, blockhash)?;
+
+        if self.sign_only {
+            print_transaction(&transaction);
+            return Ok(());
+        }
+        send(&self.rpc, &transaction).await
+    }
+}
+
+fn resolve_signer(
+    spec: &str,
+    presigned: &[(Pubkey, Signature)],
+    sign_only: bool,
+) -> CliResult<Box<dyn Signer>> {
+    if let Ok(pubkey) = Pubkey::from_str(spec) {
+        if let Some((_, signature)) = presigned.iter().find(|(key, _)| *key == pubkey) {
+            return Ok(Box::new(Presigner::new(&pubkey, signature)));
+        }
+        if sign_only {
+            return Ok(Box::new(NullSigner::new(&pubkey)));
+        }
+        return Err(format!("no signature for {}, pass a keypair or --signer", pubkey).into());
+    }
+    let path = match spec.strip_prefix("~/") {
+        Some(rest) => format!("{}/{}", std::env::var("HOME")?, rest),
+        None => spec.to_string(),
+    };
+    let keypair = read_keypair_file(&path).map_err(|err| format!("{}: {}", path, err))?;
+    Ok(Box::new(keypair))
+}
+
+fn parse_presigned(signer: &str) -> CliResult<(Pubkey, Signature)> {
+    let (pubkey, signature) = signer
+        .split_once('=')
+        .ok_or_else(|| format!("expected PUBKEY=SIGNATURE, got {}", signer))?;
+    Ok((Pubkey::from_str(pubkey)?, Signature::from_str(signature)?))
+}
+
+fn print_transaction(transaction: &Transaction) {
+    let message_signers = &transaction.message.account_keys
+        [..transaction.message.header.num_required_signatures as usize];
+    println!("Blockhash: {}", transaction.message.recent_blockhash);
+    println!("Signers (Pubkey=Signature):");
+    for (pubkey, signature) in message_signers.iter().zip(&transaction.signatures) {
+        if *signature != Signature::default() {
+            println!("  {}={}", pubkey, signature);
+        }
+    }
+    let absent: Vec<_> = message_signers
+        .iter()
+        .zip(&transaction.signatures)
+        .filter(|(_, signature)| **signature == Signature::default())
+        .collect();
+    if !absent.is_empty() {
+        println!("Absent Signers:");
+        for (pubkey, _) in absent {
+            println!("  {}", pubkey);
+        }
+    }
+    let serialized = bincode::serialize(transaction).expect("transaction serializes");
+    println!("Transaction (base58): {}", bs58::encode(&serialized).into_string());
+    println!(
+        "Transaction (base64): {}",
+        base64::engine::general_purpose::STANDARD.encode(&serialized)
+    );
+}
+
+async fn send(rpc: &RpcClient, transaction: &Transaction) -> CliResult<()> {
+    if !transaction.is_signed() {
+        return Err("transaction is missing signatures".into());
+    }
+    let signature = rpc.send_and_confirm_transaction(transaction).await?;
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+async fn show(context: &Context, address: &Pubkey) -> CliResult<()> {
+    let program_id = context.program_id()?;
+    let account = context.rpc.get_account(address).await?;
+    if account.owner != program_id {
+        return Err(format!("{} is not owned by {}", address, program_id).into());
+    }
+    match AccountHeader::unpack(&account.data)?.account_type {
+        AccountType::TokenInfo => println!("{:#?}", TokenInfo::unpack(&account.data)?),
+        AccountType::VestingSchedule => {
+            println!("{:#?}", VestingSchedule::unpack(&account.data)?)
+        }
+        AccountType::StakeInfo => println!("{:#?}", StakeInfo::unpack(&account.data)?),
+        AccountType::StakingPool => println!("{:#?}", StakingPool::unpack(&account.data)?),
+        AccountType::StakingConfig => println!("{:#?}", StakingConfig::unpack(&account.data)?),
+        AccountType::UnbondingEntry => println!("{:#?}", UnbondingEntry::unpack(&account.data)?),
+        AccountType::LiquidPool => println!("{:#?}", LiquidPool::unpack(&account.data)?),
+        AccountType::Locker => println!("{:#?}", Locker::unpack(&account.data)?),
+        AccountType::VoteLock => println!("{:#?}", VoteLock::unpack(&account.data)?),
+        AccountType::Operator => println!("{:#?}", Operator::unpack(&account.data)?),
+        AccountType::Farm => println!("{:#?}", Farm::unpack(&account.data)?),
+        AccountType::FarmPosition => println!("{:#?}", FarmPosition::unpack(&account.data)?),
+        AccountType::EmissionSchedule => println!("{:#?}", EmissionSchedule::unpack(&account.data)?),
+        AccountType::Uninitialized => return Err(format!("{} is not initialized", address).into()),
+    }
+    Ok(())
+}
+
+async fn broadcast(context: &Context, encoded: &str, encoding: Encoding) -> CliResult<()> {
+    let bytes = match encoding {
+        Encoding::Base58 => bs58::decode(encoded).into_vec()?,
+        Encoding::Base64 => base64::engine::general_purpose::STANDARD.decode(encoded)?,
+    };
+    let transaction: Transaction = bincode::deserialize(&bytes)?;
+    transaction.verify()?;
+    send(&context.rpc, &transaction).await
+}
+
+async fn run(cli: Cli) -> CliResult<()> {
+    let presigned = cli
+        .signers
+        .iter()
+        .map(|signer| parse_presigned(signer))
+        .collect::<CliResult<Vec<_>>>()?;
+    let fee_payer = resolve_signer(
+        cli.fee_payer.as_deref().unwrap_or(&cli.keypair),
+        &presigned,
+        cli.sign_only,
+    )?;
+    let context = Context {
+        rpc: RpcClient::new(cli.url),
+        program_id: cli.program_id,
+        default_signer: cli.keypair,
+        fee_payer,
+        sign_only: cli.sign_only,
+        blockhash: cli.blockhash,
+        presigned,
+    };
+
+    match cli.command {
+        Command::InitializeMint {
+            mint,
+            mint_authority,
+            freeze_authority,
+            decimals,
+        } => {
+            let program_id = context.program_id()?;
+            let mint = context.signer(Some(&mint))?;
+            let mint_authority = match mint_authority {
+                Some(mint_authority) => mint_authority,
+                None => context.signer(None)?.pubkey(),
+            };
+            let instructions = [
+                context
+                    .create_account(&mint.pubkey(), Mint::LEN, &spl_token::id())
+                    .await?,
+                instruction::initialize_mint(
+                    &program_id,
+                    &mint.pubkey(),
+                    &mint_authority,
+                    freeze_authority.as_ref(),
+                    decimals,
+                ),
+            ];
+            context.process(&instructions, &[mint.as_ref()]).await
+        }
+        Command::InitializeTokenInfo {
+            token_info,
+            mint,
+            mint_authority,
+        } => {
+            let program_id = context.program_id()?;
+            let token_info = context.signer(Some(&token_info))?;
+            let mint_authority = context.signer(mint_authority.as_deref())?;
+            let instructions = [
+                context
+                    .create_account(&token_info.pubkey(), TokenInfo::LEN, &program_id)
+                    .await?,
+                instruction::initialize_token_info(
+                    &program_id,
+                    &token_info.pubkey(),
+                    &mint_authority.pubkey(),
+                    &mint,
+                ),
+            ];
+            context
+                .process(&instructions, &[token_info.as_ref(), mint_authority.as_ref()])
+                .await
+        }
+        Command::CreateVesting {
+            vesting,
+            token_info,
+            beneficiary,
+            allocation,
+            amount,
+            start,
+            cliff,
+            end,
+            mint_authority,
+        } => {
+            let program_id = context.program_id()?;
+            let vesting = context.signer(Some(&vesting))?;
+            let mint_authority = context.signer(mint_authority.as_deref())?;
+            let instructions = [
+                context
+                    .create_account(&vesting.pubkey(), VestingSchedule::LEN, &program_id)
+                    .await?,
+                instruction::create_vesting_schedule(
+                    &program_id,
+                    &vesting.pubkey(),
+                    &token_info,
+                    &mint_authority.pubkey(),
+                    &beneficiary,
+                    allocation.into(),
+                    amount,
+                    start,
+                    cliff.unwrap_or(start),
+                    end,
+                ),
+            ];
+            context
+                .process(&instructions, &[vesting.as_ref(), mint_authority.as_ref()])
+                .await
+        }
+        Command::ReleaseVested {
+            vesting,
+            beneficiary_token_account,
+            source,
+            source_authority,
+        } => {
+            let source_authority = context.signer(source_authority.as_deref())?;
+            let instruction = instruction::release_vested_tokens(
+                &context.program_id()?,
+                &vesting,
+                &beneficiary_token_account,
+                &source,
+                &source_authority.pubkey(),
+            );
+            context
+                .process(&[instruction], &[source_authority.as_ref()])
+                .await
+        }
+        Command::InitializeVault { mint } => {
+            let instruction = instruction::initialize_vault(
+                &context.program_id()?,
+                &mint,
+                &context.fee_payer.pubkey(),
+            );
+            context.process(&[instruction], &[]).await
+        }
+        Command::InitializeStake { mint, lock, owner } => {
+            let owner = context.signer(owner.as_deref())?;
+            let instruction =
+                instruction::initialize_stake(&context.program_id()?, &mint, &owner.pubkey(), lock.into());
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::ExtendLock { mint, lock, staker } => {
+            let staker = context.signer(staker.as_deref())?;
+            let operator = context.delegated_operator(&mint, &staker.pubkey()).await?;
+            let instruction = instruction::extend_lock(
+                &context.program_id()?,
+                &mint,
+                &staker.pubkey(),
+                lock.into(),
+                operator.as_ref(),
+            );
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::Stake {
+            token_account,
+            mint,
+            amount,
+            staker,
+        } => {
+            let staker = context.signer(staker.as_deref())?;
+            let operator = context.delegated_operator(&mint, &staker.pubkey()).await?;
+            let instruction = instruction::stake(
+                &context.program_id()?,
+                &token_account,
+                &mint,
+                &staker.pubkey(),
+                amount,
+                operator.as_ref(),
+            );
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::Unstake {
+            token_account,
+            mint,
+            amount,
+            staker,
+        } => {
+            let staker = context.signer(staker.as_deref())?;
+            let operator = context.delegated_operator(&mint, &staker.pubkey()).await?;
+            let instruction = instruction::unstake(
+                &context.program_id()?,
+                &token_account,
+                &mint,
+                &staker.pubkey(),
+                amount,
+                operator.as_ref(),
+            );
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::RequestUnstake {
+            mint,
+            amount,
+            id,
+            staker,
+        } => {
+            let program_id = context.program_id()?;
+            let staker = context.signer(staker.as_deref())?;
+            let id = match id {
+                Some(id) => id,
+                None => {
+                    let (stake_account, _) = find_stake_address(&program_id, &mint, &staker.pubkey());
+                    let account = context.rpc.get_account(&stake_account).await?;
+                    StakeInfo::unpack(&account.data)?.next_unbonding_id
+                }
+            };
+            println!("Unbonding id: {}", id);
+            let operator = context.delegated_operator(&mint, &staker.pubkey()).await?;
+            let instruction =
+                instruction::request_unstake(&program_id, &mint, &staker.pubkey(), id, amount, operator.as_ref());
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::WithdrawUnbonded {
+            token_account,
+            mint,
+            id,
+            staker,
+        } => {
+            let staker = context.signer(staker.as_deref())?;
+            let instruction = instruction::withdraw_unbonded(
+                &context.program_id()?,
+                &token_account,
+                &mint,
+                &staker.pubkey(),
+                id,
+            );
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::CancelUnbonding { mint, id, staker } => {
+            let staker = context.signer(staker.as_deref())?;
+            let operator = context.delegated_operator(&mint, &staker.pubkey()).await?;
+            let instruction =
+                instruction::cancel_unbonding(&context.program_id()?, &mint, &staker.pubkey(), id, operator.as_ref());
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::EmergencyUnstake {
+            token_account,
+            mint,
+            amount,
+            staker,
+        } => {
+            let program_id = context.program_id()?;
+            let staker = context.signer(staker.as_deref())?;
+            let (staking_config, _) = find_staking_config_address(&program_id, &mint);
+            let account = context.rpc.get_account(&staking_config).await?;
+            let penalty_account = StakingConfig::unpack(&account.data)?.params.penalty_account(&program_id, &mint);
+            let operator = context.delegated_operator(&mint, &staker.pubkey()).await?;
+            let instruction = instruction::emergency_unstake(
+                &program_id,
+                &token_account,
+                &mint,
+                &penalty_account,
+                &staker.pubkey(),
+                amount,
+                operator.as_ref(),
+            );
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::InitializeLiquidPool { mint, token_info, admin } => {
+            let admin = context.signer(admin.as_deref())?;
+            let instruction =
+                instruction::initialize_liquid_pool(&context.program_id()?, &mint, &token_info, &admin.pubkey());
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::LiquidStake {
+            token_account,
+            receipt_account,
+            mint,
+            amount,
+            owner,
+        } => {
+            let owner = context.signer(owner.as_deref())?;
+            let instruction = instruction::liquid_stake(
+                &context.program_id()?,
+                &token_account,
+                &receipt_account,
+                &mint,
+                &owner.pubkey(),
+                amount,
+            );
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::LiquidUnstake {
+            token_account,
+            receipt_account,
+            mint,
+            receipt_amount,
+            owner,
+        } => {
+            let owner = context.signer(owner.as_deref())?;
+            let instruction = instruction::liquid_unstake(
+                &context.program_id()?,
+                &token_account,
+                &receipt_account,
+                &mint,
+                &owner.pubkey(),
+                receipt_amount,
+            );
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::RequestLiquidUnstake {
+            receipt_account,
+            mint,
+            receipt_amount,
+            id,
+            owner,
+        } => {
+            let program_id = context.program_id()?;
+            let owner = context.signer(owner.as_deref())?;
+            let id = match id {
+                Some(id) => id,
+                None => {
+                    let (liquid_pool, _) = find_liquid_pool_address(&program_id, &mint);
+                    let (stake_account, _) = find_stake_address(&program_id, &mint, &liquid_pool);
+                    let account = context.rpc.get_account(&stake_account).await?;
+                    StakeInfo::unpack(&account.data)?.next_unbonding_id
+                }
+            };
+            println!("Unbonding id: {}", id);
+            let instruction = instruction::request_liquid_unstake(
+                &program_id,
+                &receipt_account,
+                &mint,
+                &owner.pubkey(),
+                id,
+                receipt_amount,
+            );
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::WithdrawLiquidUnbonded {
+            token_account,
+            mint,
+            id,
+            owner,
+        } => {
+            let owner = context.signer(owner.as_deref())?;
+            let instruction = instruction::withdraw_liquid_unbonded(
+                &context.program_id()?,
+                &token_account,
+                &mint,
+                &owner.pubkey(),
+                id,
+            );
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::CompoundRewards { mint, owner, staker } => {
+            if let Some(owner) = owner {
+                let operator = context.delegated_operator(&mint, &owner).await?;
+                let instruction =
+                    instruction::compound_rewards(&context.program_id()?, &mint, &owner, false, operator.as_ref());
+                return context.process(&[instruction], &[]).await;
+            }
+            let staker = context.signer(staker.as_deref())?;
+            let operator = context.delegated_operator(&mint, &staker.pubkey()).await?;
+            let instruction =
+                instruction::compound_rewards(&context.program_id()?, &mint, &staker.pubkey(), true, operator.as_ref());
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::SetAutoCompound { mint, disable, staker } => {
+            let staker = context.signer(staker.as_deref())?;
+            let instruction = instruction::set_auto_compound(&context.program_id()?, &mint, &staker.pubkey(), !disable);
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::InitializeLocker {
+            mint,
+            token_info,
+            max_lock_duration,
+            admin,
+        } => {
+            let admin = context.signer(admin.as_deref())?;
+            let instruction = instruction::initialize_locker(
+                &context.program_id()?,
+                &mint,
+                &token_info,
+                &admin.pubkey(),
+                max_lock_duration,
+            );
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::CreateVoteLock {
+            token_account,
+            mint,
+            amount,
+            unlock_time,
+            owner,
+        } => {
+            let owner = context.signer(owner.as_deref())?;
+            let instruction = instruction::create_vote_lock(
+                &context.program_id()?,
+                &token_account,
+                &mint,
+                &owner.pubkey(),
+                amount,
+                unlock_time,
+            );
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::IncreaseVoteLockAmount {
+            token_account,
+            mint,
+            amount,
+            owner,
+        } => {
+            let owner = context.signer(owner.as_deref())?;
+            let instruction = instruction::increase_vote_lock_amount(
+                &context.program_id()?,
+                &token_account,
+                &mint,
+                &owner.pubkey(),
+                amount,
+            );
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::ExtendVoteLock { mint, unlock_time, owner } => {
+            let owner = context.signer(owner.as_deref())?;
+            let instruction = instruction::extend_vote_lock(&context.program_id()?, &mint, &owner.pubkey(), unlock_time);
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::WithdrawVoteLock { token_account, mint, owner } => {
+            let owner = context.signer(owner.as_deref())?;
+            let instruction =
+                instruction::withdraw_vote_lock(&context.program_id()?, &token_account, &mint, &owner.pubkey());
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::RegisterOperator {
+            mint,
+            commission_bps,
+            authority,
+        } => {
+            let authority = context.signer(authority.as_deref())?;
+            let instruction =
+                instruction::register_operator(&context.program_id()?, &mint, &authority.pubkey(), commission_bps);
+            context.process(&[instruction], &[authority.as_ref()]).await
+        }
+        Command::DelegateStake { mint, operator, staker } => {
+            let staker = context.signer(staker.as_deref())?;
+            let current_operator = context.delegated_operator(&mint, &staker.pubkey()).await?;
+            let instruction = instruction::delegate_stake(
+                &context.program_id()?,
+                &mint,
+                &staker.pubkey(),
+                &operator,
+                current_operator.as_ref(),
+            );
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::UndelegateStake { mint, staker } => {
+            let staker = context.signer(staker.as_deref())?;
+            let operator = context
+                .delegated_operator(&mint, &staker.pubkey())
+                .await?
+                .ok_or("the stake position is not delegated")?;
+            let instruction = instruction::undelegate_stake(&context.program_id()?, &mint, &staker.pubkey(), &operator);
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::ClaimCommission {
+            destination,
+            mint,
+            authority,
+        } => {
+            let authority = context.signer(authority.as_deref())?;
+            let instruction =
+                instruction::claim_commission(&context.program_id()?, &destination, &mint, &authority.pubkey());
+            context.process(&[instruction], &[authority.as_ref()]).await
+        }
+        Command::CreateFarm {
+            mint,
+            token_info,
+            id,
+            staked_mint,
+            reward_mint,
+            emission_rate,
+            start_time,
+            end_time,
+            admin,
+        } => {
+            let admin = context.signer(admin.as_deref())?;
+            let instruction = instruction::create_farm(
+                &context.program_id()?,
+                &mint,
+                &token_info,
+                &staked_mint,
+                &reward_mint,
+                &admin.pubkey(),
+                id,
+                emission_rate,
+                start_time,
+                end_time,
+            );
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::FundFarm {
+            farm,
+            source,
+            amount,
+            funder,
+        } => {
+            let funder = context.signer(funder.as_deref())?;
+            let reward_mint = Farm::unpack(&context.rpc.get_account(&farm).await?.data)?.reward_mint;
+            let instruction =
+                instruction::fund_farm(&context.program_id()?, &farm, &source, &reward_mint, &funder.pubkey(), amount);
+            context.process(&[instruction], &[funder.as_ref()]).await
+        }
+        Command::UpdateFarm {
+            farm,
+            token_info,
+            emission_rate,
+            end_time,
+            admin,
+        } => {
+            let admin = context.signer(admin.as_deref())?;
+            let mint = Farm::unpack(&context.rpc.get_account(&farm).await?.data)?.mint;
+            let instruction = instruction::update_farm(
+                &context.program_id()?,
+                &farm,
+                &mint,
+                &token_info,
+                &admin.pubkey(),
+                emission_rate,
+                end_time,
+            );
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::FarmStake {
+            farm,
+            token_account,
+            amount,
+            owner,
+        } => {
+            let owner = context.signer(owner.as_deref())?;
+            let staked_mint = Farm::unpack(&context.rpc.get_account(&farm).await?.data)?.staked_mint;
+            let instruction = instruction::farm_stake(
+                &context.program_id()?,
+                &farm,
+                &token_account,
+                &staked_mint,
+                &owner.pubkey(),
+                amount,
+            );
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::FarmUnstake {
+            farm,
+            token_account,
+            amount,
+            owner,
+        } => {
+            let owner = context.signer(owner.as_deref())?;
+            let staked_mint = Farm::unpack(&context.rpc.get_account(&farm).await?.data)?.staked_mint;
+            let instruction = instruction::farm_unstake(
+                &context.program_id()?,
+                &farm,
+                &token_account,
+                &staked_mint,
+                &owner.pubkey(),
+                amount,
+            );
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::ClaimFarmRewards {
+            farm,
+            token_account,
+            owner,
+        } => {
+            let owner = context.signer(owner.as_deref())?;
+            let reward_mint = Farm::unpack(&context.rpc.get_account(&farm).await?.data)?.reward_mint;
+            let instruction = instruction::claim_farm_rewards(
+                &context.program_id()?,
+                &farm,
+                &token_account,
+                &reward_mint,
+                &owner.pubkey(),
+            );
+            context.process(&[instruction], &[owner.as_ref()]).await
+        }
+        Command::InitializeEmissionSchedule {
+            mint,
+            token_info,
+            epoch_duration,
+            initial_emission,
+            decay_bps,
+            admin,
+        } => {
+            let admin = context.signer(admin.as_deref())?;
+            let instruction = instruction::initialize_emission_schedule(
+                &context.program_id()?,
+                &mint,
+                &token_info,
+                &admin.pubkey(),
+                epoch_duration,
+                initial_emission,
+                decay_bps,
+            );
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::AdvanceEpoch { mint, token_info } => {
+            let instruction = instruction::advance_epoch(&context.program_id()?, &mint, &token_info);
+            context.process(&[instruction], &[]).await
+        }
+        Command::InitializeStakingPool {
+            mint,
+            token_info,
+            reward_rate,
+            admin,
+        } => {
+            let admin = context.signer(admin.as_deref())?;
+            let instruction = instruction::initialize_staking_pool(
+                &context.program_id()?,
+                &mint,
+                &token_info,
+                &admin.pubkey(),
+                reward_rate,
+            );
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::InitializeStakingConfig {
+            mint,
+            token_info,
+            params,
+            admin,
+        } => {
+            let admin = context.signer(admin.as_deref())?;
+            let instruction = instruction::initialize_staking_config(
+                &context.program_id()?,
+                &mint,
+                &token_info,
+                &admin.pubkey(),
+                params.into(),
+            );
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::UpdateStakingConfig {
+            mint,
+            token_info,
+            params,
+            admin,
+        } => {
+            let admin = context.signer(admin.as_deref())?;
+            let instruction = instruction::update_staking_config(
+                &context.program_id()?,
+                &mint,
+                &token_info,
+                &admin.pubkey(),
+                params.into(),
+            );
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::FundRewards {
+            mint,
+            token_info,
+            source,
+            amount,
+            admin,
+        } => {
+            let admin = context.signer(admin.as_deref())?;
+            let instruction = instruction::fund_rewards(
+                &context.program_id()?,
+                &mint,
+                &token_info,
+                &source,
+                &admin.pubkey(),
+                amount,
+            );
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::ClaimRewards {
+            token_account,
+            mint,
+            staker,
+        } => {
+            let staker = context.signer(staker.as_deref())?;
+            let operator = context.delegated_operator(&mint, &staker.pubkey()).await?;
+            let instruction = instruction::claim_rewards(
+                &context.program_id()?,
+                &token_account,
+                &mint,
+                &staker.pubkey(),
+                operator.as_ref(),
+            );
+            context.process(&[instruction], &[staker.as_ref()]).await
+        }
+        Command::Freeze {
+            account,
+            mint,
+            freeze_authority,
+        } => {
+            let freeze_authority = context.signer(freeze_authority.as_deref())?;
+            let instruction = instruction::freeze(
+                &context.program_id()?,
+                &account,
+                &mint,
+                &freeze_authority.pubkey(),
+            );
+            context
+                .process(&[instruction], &[freeze_authority.as_ref()])
+                .await
+        }
+        Command::Thaw {
+            account,
+            mint,
+            freeze_authority,
+        } => {
+            let freeze_authority = context.signer(freeze_authority.as_deref())?;
+            let instruction = instruction::thaw(
+                &context.program_id()?,
+                &account,
+                &mint,
+                &freeze_authority.pubkey(),
+            );
+            context
+                .process(&[instruction], &[freeze_authority.as_ref()])
+                .await
+        }
+        Command::SetAuthority {
+            account,
+            authority_type,
+            new_authority,
+            disable: _,
+            current_authority,
+        } => {
+            let current_authority = context.signer(current_authority.as_deref())?;
+            let instruction = instruction::set_authority(
+                &context.program_id()?,
+                &account,
+                new_authority.as_ref(),
+                authority_type as u8,
+                &current_authority.pubkey(),
+            );
+            context
+                .process(&[instruction], &[current_authority.as_ref()])
+                .await
+        }
+        Command::Migrate {
+            account,
+            account_type,
+            token_info,
+            admin,
+        } => {
+            let admin = context.signer(admin.as_deref())?;
+            let instruction = instruction::migrate_account(
+                &context.program_id()?,
+                &account,
+                &token_info,
+                &admin.pubkey(),
+                account_type.into(),
+            );
+            context.process(&[instruction], &[admin.as_ref()]).await
+        }
+        Command::Show { address } => show(&context, &address).await,
+        Command::Broadcast {
+            transaction,
+            encoding,
+        } => broadcast(&context, &transaction, encoding).await,
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(err) = run(Cli::parse()).await {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_offline_signers() {
+        let pubkey = Pubkey::new_unique();
+        let signature = Signature::from([7; 64]);
+        let presigned = parse_presigned(&format!("{}={}", pubkey, signature)).unwrap();
+        assert_eq!(presigned, (pubkey, signature));
+
+        let signer = resolve_signer(&pubkey.to_string(), &[], true).unwrap();
+        assert_eq!(signer.pubkey(), pubkey);
+        assert!(resolve_signer(&pubkey.to_string(), &[], false).is_err());
+    }
+}
@@ -0,0 +1,905 @@
+//! Typed async client for the token program.
+//!
+//! [`TokenClient`] wraps the instruction builders and account decoders of
+//! the `solana-token` crate. It talks to the cluster through a
+//! [`Transport`], so the same code runs against `solana-program-test` and a
+//! real RPC node.
+
+mod transport;
+
+pub use solana_token;
+pub use transport::Transport;
+
+use borsh::BorshDeserialize;
+use solana_program::program_pack::{IsInitialized, Pack};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::program_error::ProgramError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::signer::signers::Signers;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use solana_token::instruction::{self, AllocationType};
+use solana_token::state::{
+    find_farm_address, find_liquid_pool_address, find_stake_address, find_staking_config_address, AccountType,
+    EmissionSchedule, EpochEmission, Farm, FarmPosition, LiquidPool, LockTier, Locker, Operator, StakeInfo,
+    StakingConfig, StakingParams, StakingPool, UnbondingEntry, VestingSchedule, VoteLock,
+};
+use solana_token::token_info::TokenInfo;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("transport error: {0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    #[error("account {0} not found")]
+    AccountNotFound(Pubkey),
+    #[error("account {0} is not owned by the token program")]
+    InvalidOwner(Pubkey),
+    #[error("invalid account data: {0}")]
+    InvalidAccountData(#[from] ProgramError),
+    #[error("the query returned no data")]
+    MissingReturnData,
+}
+
+impl ClientError {
+    fn transport<E: std::error::Error + Send + Sync + 'static>(error: E) -> Self {
+        ClientError::Transport(Box::new(error))
+    }
+}
+
+pub struct TokenClient<T> {
+    transport: T,
+    program_id: Pubkey,
+    payer: Box<dyn Signer>,
+}
+
+impl<T: Transport> TokenClient<T> {
+    pub fn new(transport: T, program_id: Pubkey, payer: Box<dyn Signer>) -> Self {
+        Self {
+            transport,
+            program_id,
+            payer,
+        }
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Signs `instructions` with the payer and `signers` and sends them in
+    /// one transaction.
+    pub async fn send<S: Signers + ?Sized>(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &S,
+    ) -> Result<Signature, ClientError> {
+        let blockhash = self.transport.get_latest_blockhash().await?;
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+        transaction.partial_sign(&[self.payer.as_ref()], blockhash);
+        transaction.partial_sign(signers, blockhash);
+        self.transport.send_transaction(&transaction).await
+    }
+
+    /// Instruction allocating a rent exempt account of `space` bytes owned
+    /// by the program, funded by the payer.
+    pub async fn create_program_account(
+        &mut self,
+        account: &Pubkey,
+        space: usize,
+    ) -> Result<Instruction, ClientError> {
+        let lamports = self
+            .transport
+            .get_minimum_balance_for_rent_exemption(space)
+            .await?;
+        Ok(system_instruction::create_account(
+            &self.payer.pubkey(),
+            account,
+            lamports,
+            space as u64,
+            &self.program_id,
+        ))
+    }
+
+    pub async fn initialize_token_info(
+        &mut self,
+        token_info: &dyn Signer,
+        mint_authority: &dyn Signer,
+        mint: &Pubkey,
+    ) -> Result<Signature, ClientError> {
+        let instructions = [
+            self.create_program_account(&token_info.pubkey(), TokenInfo::LEN)
+                .await?,
+            instruction::initialize_token_info(
+                &self.program_id,
+                &token_info.pubkey(),
+                &mint_authority.pubkey(),
+                mint,
+            ),
+        ];
+        self.send(&instructions, &[token_info, mint_authority]).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_vesting(
+        &mut self,
+        vesting_account: &dyn Signer,
+        token_info: &Pubkey,
+        mint_authority: &dyn Signer,
+        beneficiary: &Pubkey,
+        allocation_type: AllocationType,
+        amount: u64,
+        start_time: i64,
+        cliff_time: i64,
+        end_time: i64,
+    ) -> Result<Signature, ClientError> {
+        let instructions = [
+            self.create_program_account(&vesting_account.pubkey(), VestingSchedule::LEN)
+                .await?,
+            instruction::create_vesting_schedule(
+                &self.program_id,
+                &vesting_account.pubkey(),
+                token_info,
+                &mint_authority.pubkey(),
+                beneficiary,
+                allocation_type,
+                amount,
+                start_time,
+                cliff_time,
+                end_time,
+            ),
+        ];
+        self.send(&instructions, &[vesting_account, mint_authority]).await
+    }
+
+    pub async fn release_vested_tokens(
+        &mut self,
+        vesting_account: &Pubkey,
+        beneficiary_token_account: &Pubkey,
+        source_token_account: &Pubkey,
+        source_authority: &dyn Signer,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::release_vested_tokens(
+            &self.program_id,
+            vesting_account,
+            beneficiary_token_account,
+            source_token_account,
+            &source_authority.pubkey(),
+        );
+        self.send(&[instruction], &[source_authority]).await
+    }
+
+    /// Creates the vault holding the tokens staked with `mint`, paid by the
+    /// payer.
+    pub async fn initialize_vault(&mut self, mint: &Pubkey) -> Result<Signature, ClientError> {
+        let instruction = instruction::initialize_vault(&self.program_id, mint, &self.payer.pubkey());
+        self.send(&[instruction], &[] as &[&dyn Signer]).await
+    }
+
+    /// Creates the stake account of `owner` for `mint`, paid by `owner`.
+    pub async fn initialize_stake(
+        &mut self,
+        mint: &Pubkey,
+        owner: &dyn Signer,
+        lock_tier: LockTier,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::initialize_stake(&self.program_id, mint, &owner.pubkey(), lock_tier);
+        self.send(&[instruction], &[owner]).await
+    }
+
+    pub async fn extend_lock(
+        &mut self,
+        mint: &Pubkey,
+        staker: &dyn Signer,
+        lock_tier: LockTier,
+    ) -> Result<Signature, ClientError> {
+        let operator = self.delegated_operator(mint, &staker.pubkey()).await?;
+        let instruction =
+            instruction::extend_lock(&self.program_id, mint, &staker.pubkey(), lock_tier, operator.as_ref());
+        self.send(&[instruction], &[staker]).await
+    }
+
+    pub async fn stake(
+        &mut self,
+        token_account: &Pubkey,
+        mint: &Pubkey,
+        staker: &dyn Signer,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let operator = self.delegated_operator(mint, &staker.pubkey()).await?;
+        let instruction = instruction::stake(
+            &self.program_id,
+            token_account,
+            mint,
+            &staker.pubkey(),
+            amount,
+            operator.as_ref(),
+        );
+        self.send(&[instruction], &[staker]).await
+    }
+
+    pub async fn unstake(
+        &mut self,
+        token_account: &Pubkey,
+        mint: &Pubkey,
+        staker: &dyn Signer,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let operator = self.delegated_operator(mint, &staker.pubkey()).await?;
+        let instruction = instruction::unstake(
+            &self.program_id,
+            token_account,
+            mint,
+            &staker.pubkey(),
+            amount,
+            operator.as_ref(),
+        );
+        self.send(&[instruction], &[staker]).await
+    }
+
+    /// Moves `amount` out of the stake of `staker` into the next unbonding
+    /// entry of the position, and returns the id of that entry.
+    pub async fn request_unstake(&mut self, mint: &Pubkey, staker: &dyn Signer, amount: u64) -> Result<u64, ClientError> {
+        let (stake_account, _) = find_stake_address(&self.program_id, mint, &staker.pubkey());
+        let stake_info = self.fetch_stake_info(&stake_account).await?;
+        let id = stake_info.next_unbonding_id;
+        let operator = stake_info.is_delegated().then_some(stake_info.operator);
+        let instruction =
+            instruction::request_unstake(&self.program_id, mint, &staker.pubkey(), id, amount, operator.as_ref());
+        self.send(&[instruction], &[staker]).await?;
+        Ok(id)
+    }
+
+    pub async fn withdraw_unbonded(
+        &mut self,
+        token_account: &Pubkey,
+        mint: &Pubkey,
+        staker: &dyn Signer,
+        unbonding_id: u64,
+    ) -> Result<Signature, ClientError> {
+        let instruction =
+            instruction::withdraw_unbonded(&self.program_id, token_account, mint, &staker.pubkey(), unbonding_id);
+        self.send(&[instruction], &[staker]).await
+    }
+
+    pub async fn cancel_unbonding(
+        &mut self,
+        mint: &Pubkey,
+        staker: &dyn Signer,
+        unbonding_id: u64,
+    ) -> Result<Signature, ClientError> {
+        let operator = self.delegated_operator(mint, &staker.pubkey()).await?;
+        let instruction =
+            instruction::cancel_unbonding(&self.program_id, mint, &staker.pubkey(), unbonding_id, operator.as_ref());
+        self.send(&[instruction], &[staker]).await
+    }
+
+    /// Takes `amount` out of the stake of `staker` at once, paying the
+    /// penalty where the staking config of `mint` sends it.
+    pub async fn emergency_unstake(
+        &mut self,
+        token_account: &Pubkey,
+        mint: &Pubkey,
+        staker: &dyn Signer,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let (staking_config, _) = find_staking_config_address(&self.program_id, mint);
+        let params = self.fetch_staking_config(&staking_config).await?.params;
+        let penalty_account = params.penalty_account(&self.program_id, mint);
+        let operator = self.delegated_operator(mint, &staker.pubkey()).await?;
+        let instruction = instruction::emergency_unstake(
+            &self.program_id,
+            token_account,
+            mint,
+            &penalty_account,
+            &staker.pubkey(),
+            amount,
+            operator.as_ref(),
+        );
+        self.send(&[instruction], &[staker]).await
+    }
+
+    /// Creates the liquid pool of `mint` and its receipt mint, with `admin`
+    /// paying for them.
+    pub async fn initialize_liquid_pool(
+        &mut self,
+        mint: &Pubkey,
+        token_info: &Pubkey,
+        admin: &dyn Signer,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::initialize_liquid_pool(&self.program_id, mint, token_info, &admin.pubkey());
+        self.send(&[instruction], &[admin]).await
+    }
+
+    pub async fn liquid_stake(
+        &mut self,
+        token_account: &Pubkey,
+        receipt_account: &Pubkey,
+        mint: &Pubkey,
+        owner: &dyn Signer,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let instruction =
+            instruction::liquid_stake(&self.program_id, token_account, receipt_account, mint, &owner.pubkey(), amount);
+        self.send(&[instruction], &[owner]).await
+    }
+
+    pub async fn liquid_unstake(
+        &mut self,
+        token_account: &Pubkey,
+        receipt_account: &Pubkey,
+        mint: &Pubkey,
+        owner: &dyn Signer,
+        receipt_amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::liquid_unstake(
+            &self.program_id,
+            token_account,
+            receipt_account,
+            mint,
+            &owner.pubkey(),
+            receipt_amount,
+        );
+        self.send(&[instruction], &[owner]).await
+    }
+
+    /// Redeems `receipt_amount` receipt tokens through a new unbonding
+    /// entry of the liquid pool. Returns the id of the entry.
+    pub async fn request_liquid_unstake(
+        &mut self,
+        receipt_account: &Pubkey,
+        mint: &Pubkey,
+        owner: &dyn Signer,
+        receipt_amount: u64,
+    ) -> Result<u64, ClientError> {
+        let (liquid_pool, _) = find_liquid_pool_address(&self.program_id, mint);
+        let (stake_account, _) = find_stake_address(&self.program_id, mint, &liquid_pool);
+        let id = self.fetch_stake_info(&stake_account).await?.next_unbonding_id;
+        let instruction = instruction::request_liquid_unstake(
+            &self.program_id,
+            receipt_account,
+            mint,
+            &owner.pubkey(),
+            id,
+            receipt_amount,
+        );
+        self.send(&[instruction], &[owner]).await?;
+        Ok(id)
+    }
+
+    pub async fn withdraw_liquid_unbonded(
+        &mut self,
+        token_account: &Pubkey,
+        mint: &Pubkey,
+        owner: &dyn Signer,
+        unbonding_id: u64,
+    ) -> Result<Signature, ClientError> {
+        let instruction =
+            instruction::withdraw_liquid_unbonded(&self.program_id, token_account, mint, &owner.pubkey(), unbonding_id);
+        self.send(&[instruction], &[owner]).await
+    }
+
+    /// Compounds the rewards of the position of `owner` into it.
+    pub async fn compound_rewards(&mut self, mint: &Pubkey, owner: &dyn Signer) -> Result<Signature, ClientError> {
+        let operator = self.delegated_operator(mint, &owner.pubkey()).await?;
+        let instruction = instruction::compound_rewards(&self.program_id, mint, &owner.pubkey(), true, operator.as_ref());
+        self.send(&[instruction], &[owner]).await
+    }
+
+    /// Compounds the rewards of a position that opted into auto-compound,
+    /// with only the payer signing.
+    pub async fn crank_compound(&mut self, mint: &Pubkey, owner: &Pubkey) -> Result<Signature, ClientError> {
+        let operator = self.delegated_operator(mint, owner).await?;
+        let instruction = instruction::compound_rewards(&self.program_id, mint, owner, false, operator.as_ref());
+        self.send(&[instruction], &[] as &[&dyn Signer]).await
+    }
+
+    pub async fn set_auto_compound(
+        &mut self,
+        mint: &Pubkey,
+        staker: &dyn Signer,
+        enabled: bool,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::set_auto_compound(&self.program_id, mint, &staker.pubkey(), enabled);
+        self.send(&[instruction], &[staker]).await
+    }
+
+    /// Creates the vote-escrow locker of `mint`, with `admin` paying for it.
+    pub async fn initialize_locker(
+        &mut self,
+        mint: &Pubkey,
+        token_info: &Pubkey,
+        admin: &dyn Signer,
+        max_lock_duration: i64,
+    ) -> Result<Signature, ClientError> {
+        let instruction =
+            instruction::initialize_locker(&self.program_id, mint, token_info, &admin.pubkey(), max_lock_duration);
+        self.send(&[instruction], &[admin]).await
+    }
+
+    pub async fn create_vote_lock(
+        &mut self,
+        token_account: &Pubkey,
+        mint: &Pubkey,
+        owner: &dyn Signer,
+        amount: u64,
+        unlock_time: i64,
+    ) -> Result<Signature, ClientError> {
+        let instruction =
+            instruction::create_vote_lock(&self.program_id, token_account, mint, &owner.pubkey(), amount, unlock_time);
+        self.send(&[instruction], &[owner]).await
+    }
+
+    pub async fn increase_vote_lock_amount(
+        &mut self,
+        token_account: &Pubkey,
+        mint: &Pubkey,
+        owner: &dyn Signer,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let instruction =
+            instruction::increase_vote_lock_amount(&self.program_id, token_account, mint, &owner.pubkey(), amount);
+        self.send(&[instruction], &[owner]).await
+    }
+
+    pub async fn extend_vote_lock(
+        &mut self,
+        mint: &Pubkey,
+        owner: &dyn Signer,
+        unlock_time: i64,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::extend_vote_lock(&self.program_id, mint, &owner.pubkey(), unlock_time);
+        self.send(&[instruction], &[owner]).await
+    }
+
+    pub async fn withdraw_vote_lock(
+        &mut self,
+        token_account: &Pubkey,
+        mint: &Pubkey,
+        owner: &dyn Signer,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::withdraw_vote_lock(&self.program_id, token_account, mint, &owner.pubkey());
+        self.send(&[instruction], &[owner]).await
+    }
+
+    /// Registers `authority` as a staking operator of `mint`.
+    pub async fn register_operator(
+        &mut self,
+        mint: &Pubkey,
+        authority: &dyn Signer,
+        commission_bps: u16,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::register_operator(&self.program_id, mint, &authority.pubkey(), commission_bps);
+        self.send(&[instruction], &[authority]).await
+    }
+
+    /// Delegates the position of `staker` to `operator`, passing along the
+    /// operator it is currently delegated to, if any.
+    pub async fn delegate_stake(
+        &mut self,
+        mint: &Pubkey,
+        staker: &dyn Signer,
+        operator: &Pubkey,
+    ) -> Result<Signature, ClientError> {
+        let current_operator = self.delegated_operator(mint, &staker.pubkey()).await?;
+        let instruction = instruction::delegate_stake(
+            &self.program_id,
+            mint,
+            &staker.pubkey(),
+            operator,
+            current_operator.as_ref(),
+        );
+        self.send(&[instruction], &[staker]).await
+    }
+
+    /// Takes the position of `staker` away from the operator it is
+    /// delegated to.
+    pub async fn undelegate_stake(&mut self, mint: &Pubkey, staker: &dyn Signer) -> Result<Signature, ClientError> {
+        let (stake_account, _) = find_stake_address(&self.program_id, mint, &staker.pubkey());
+        let operator = self.fetch_stake_info(&stake_account).await?.operator;
+        let instruction = instruction::undelegate_stake(&self.program_id, mint, &staker.pubkey(), &operator);
+        self.send(&[instruction], &[staker]).await
+    }
+
+    /// Pays the commission passed on to the operator of `authority` to
+    /// `destination`.
+    pub async fn claim_commission(
+        &mut self,
+        destination: &Pubkey,
+        mint: &Pubkey,
+        authority: &dyn Signer,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::claim_commission(&self.program_id, destination, mint, &authority.pubkey());
+        self.send(&[instruction], &[authority]).await
+    }
+
+    /// Creates farm `id` of `mint`, with `admin` paying for it, and returns
+    /// its address.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_farm(
+        &mut self,
+        mint: &Pubkey,
+        token_info: &Pubkey,
+        staked_mint: &Pubkey,
+        reward_mint: &Pubkey,
+        admin: &dyn Signer,
+        id: u64,
+        emission_rate: u64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<Pubkey, ClientError> {
+        let instruction = instruction::create_farm(
+            &self.program_id,
+            mint,
+            token_info,
+            staked_mint,
+            reward_mint,
+            &admin.pubkey(),
+            id,
+            emission_rate,
+            start_time,
+            end_time,
+        );
+        self.send(&[instruction], &[admin]).await?;
+        Ok(find_farm_address(&self.program_id, mint, id).0)
+    }
+
+    pub async fn fund_farm(
+        &mut self,
+        farm: &Pubkey,
+        source_token_account: &Pubkey,
+        funder: &dyn Signer,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let reward_mint = self.fetch_farm(farm).await?.reward_mint;
+        let instruction =
+            instruction::fund_farm(&self.program_id, farm, source_token_account, &reward_mint, &funder.pubkey(), amount);
+        self.send(&[instruction], &[funder]).await
+    }
+
+    /// Changes the emission rate and end time of a farm. Only the mint
+    /// authority recorded in `token_info` can.
+    pub async fn update_farm(
+        &mut self,
+        farm: &Pubkey,
+        token_info: &Pubkey,
+        admin: &dyn Signer,
+        emission_rate: u64,
+        end_time: i64,
+    ) -> Result<Signature, ClientError> {
+        let mint = self.fetch_farm(farm).await?.mint;
+        let instruction = instruction::update_farm(
+            &self.program_id,
+            farm,
+            &mint,
+            token_info,
+            &admin.pubkey(),
+            emission_rate,
+            end_time,
+        );
+        self.send(&[instruction], &[admin]).await
+    }
+
+    pub async fn farm_stake(
+        &mut self,
+        farm: &Pubkey,
+        token_account: &Pubkey,
+        owner: &dyn Signer,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let staked_mint = self.fetch_farm(farm).await?.staked_mint;
+        let instruction =
+            instruction::farm_stake(&self.program_id, farm, token_account, &staked_mint, &owner.pubkey(), amount);
+        self.send(&[instruction], &[owner]).await
+    }
+
+    pub async fn farm_unstake(
+        &mut self,
+        farm: &Pubkey,
+        token_account: &Pubkey,
+        owner: &dyn Signer,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let staked_mint = self.fetch_farm(farm).await?.staked_mint;
+        let instruction =
+            instruction::farm_unstake(&self.program_id, farm, token_account, &staked_mint, &owner.pubkey(), amount);
+        self.send(&[instruction], &[owner]).await
+    }
+
+    pub async fn claim_farm_rewards(
+        &mut self,
+        farm: &Pubkey,
+        destination: &Pubkey,
+        owner: &dyn Signer,
+    ) -> Result<Signature, ClientError> {
+        let reward_mint = self.fetch_farm(farm).await?.reward_mint;
+        let instruction =
+            instruction::claim_farm_rewards(&self.program_id, farm, destination, &reward_mint, &owner.pubkey());
+        self.send(&[instruction], &[owner]).await
+    }
+
+    /// Puts the staking pool of `mint` on an emission schedule starting now,
+    /// with `admin` paying for it.
+    pub async fn initialize_emission_schedule(
+        &mut self,
+        mint: &Pubkey,
+        token_info: &Pubkey,
+        admin: &dyn Signer,
+        epoch_duration: i64,
+        initial_emission: u64,
+        decay_bps: u16,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::initialize_emission_schedule(
+            &self.program_id,
+            mint,
+            token_info,
+            &admin.pubkey(),
+            epoch_duration,
+            initial_emission,
+            decay_bps,
+        );
+        self.send(&[instruction], &[admin]).await
+    }
+
+    /// Starts the next epoch of the emission schedule of `mint`, with only
+    /// the payer signing.
+    pub async fn advance_epoch(&mut self, mint: &Pubkey, token_info: &Pubkey) -> Result<Signature, ClientError> {
+        let instruction = instruction::advance_epoch(&self.program_id, mint, token_info);
+        self.send(&[instruction], &[] as &[&dyn Signer]).await
+    }
+
+    /// Creates the staking pool of `mint`, with `admin` paying for it.
+    pub async fn initialize_staking_pool(
+        &mut self,
+        mint: &Pubkey,
+        token_info: &Pubkey,
+        admin: &dyn Signer,
+        reward_rate: u64,
+    ) -> Result<Signature, ClientError> {
+        let instruction =
+            instruction::initialize_staking_pool(&self.program_id, mint, token_info, &admin.pubkey(), reward_rate);
+        self.send(&[instruction], &[admin]).await
+    }
+
+    pub async fn initialize_staking_config(
+        &mut self,
+        mint: &Pubkey,
+        token_info: &Pubkey,
+        admin: &dyn Signer,
+        params: StakingParams,
+    ) -> Result<Signature, ClientError> {
+        let instruction =
+            instruction::initialize_staking_config(&self.program_id, mint, token_info, &admin.pubkey(), params);
+        self.send(&[instruction], &[admin]).await
+    }
+
+    pub async fn update_staking_config(
+        &mut self,
+        mint: &Pubkey,
+        token_info: &Pubkey,
+        admin: &dyn Signer,
+        params: StakingParams,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::update_staking_config(&self.program_id, mint, token_info, &admin.pubkey(), params);
+        self.send(&[instruction], &[admin]).await
+    }
+
+    pub async fn fund_rewards(
+        &mut self,
+        mint: &Pubkey,
+        token_info: &Pubkey,
+        source_token_account: &Pubkey,
+        admin: &dyn Signer,
+        amount: u64,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::fund_rewards(
+            &self.program_id,
+            mint,
+            token_info,
+            source_token_account,
+            &admin.pubkey(),
+            amount,
+        );
+        self.send(&[instruction], &[admin]).await
+    }
+
+    pub async fn claim_rewards(
+        &mut self,
+        destination: &Pubkey,
+        mint: &Pubkey,
+        staker: &dyn Signer,
+    ) -> Result<Signature, ClientError> {
+        let operator = self.delegated_operator(mint, &staker.pubkey()).await?;
+        let instruction =
+            instruction::claim_rewards(&self.program_id, destination, mint, &staker.pubkey(), operator.as_ref());
+        self.send(&[instruction], &[staker]).await
+    }
+
+    /// Rewrites `account` in the current layout, with `admin` paying for
+    /// any extra rent.
+    pub async fn migrate_account(
+        &mut self,
+        account: &Pubkey,
+        token_info: &Pubkey,
+        admin: &dyn Signer,
+        account_type: AccountType,
+    ) -> Result<Signature, ClientError> {
+        let instruction = instruction::migrate_account(
+            &self.program_id,
+            account,
+            token_info,
+            &admin.pubkey(),
+            account_type,
+        );
+        self.send(&[instruction], &[admin]).await
+    }
+
+    /// Simulates a query instruction and decodes what it returns.
+    pub async fn query<R: BorshDeserialize>(&mut self, instruction: Instruction) -> Result<R, ClientError> {
+        let blockhash = self.transport.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.payer.pubkey()),
+            &[self.payer.as_ref()],
+            blockhash,
+        );
+        match self.transport.simulate_return_data(&transaction).await? {
+            Some((program_id, data)) if program_id == self.program_id => {
+                R::try_from_slice(&data).map_err(|_| ProgramError::InvalidAccountData.into())
+            }
+            _ => Err(ClientError::MissingReturnData),
+        }
+    }
+
+    pub async fn query_vested_amount(&mut self, vesting_account: &Pubkey) -> Result<u64, ClientError> {
+        self.query(instruction::query_vested_amount(&self.program_id, vesting_account))
+            .await
+    }
+
+    pub async fn query_releasable_amount(
+        &mut self,
+        vesting_account: &Pubkey,
+    ) -> Result<u64, ClientError> {
+        self.query(instruction::query_releasable_amount(&self.program_id, vesting_account))
+            .await
+    }
+
+    pub async fn query_stake_info(&mut self, stake_account: &Pubkey) -> Result<StakeInfo, ClientError> {
+        self.query(instruction::query_stake_info(&self.program_id, stake_account))
+            .await
+    }
+
+    /// Rewards `owner` could claim now from their stake of `mint`.
+    pub async fn query_pending_rewards(&mut self, mint: &Pubkey, owner: &Pubkey) -> Result<u64, ClientError> {
+        self.query(instruction::query_pending_rewards(&self.program_id, mint, owner))
+            .await
+    }
+
+    /// Voting power of the vote lock of `owner` at `timestamp`.
+    pub async fn query_voting_power(
+        &mut self,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        timestamp: i64,
+    ) -> Result<u64, ClientError> {
+        self.query(instruction::query_voting_power(&self.program_id, mint, owner, timestamp))
+            .await
+    }
+
+    /// Rewards the farm position of `owner` could claim now.
+    pub async fn query_farm_rewards(&mut self, farm: &Pubkey, owner: &Pubkey) -> Result<u64, ClientError> {
+        self.query(instruction::query_farm_rewards(&self.program_id, farm, owner)).await
+    }
+
+    /// Current epoch of the emission schedule of `mint`.
+    pub async fn query_emission_schedule(&mut self, mint: &Pubkey) -> Result<EpochEmission, ClientError> {
+        self.query(instruction::query_emission_schedule(&self.program_id, mint)).await
+    }
+
+    /// Total voting power of the locker of `mint` at `timestamp`.
+    pub async fn query_total_voting_power(&mut self, mint: &Pubkey, timestamp: i64) -> Result<u64, ClientError> {
+        self.query(instruction::query_total_voting_power(&self.program_id, mint, timestamp))
+            .await
+    }
+
+    pub async fn query_allocation_remaining(
+        &mut self,
+        token_info: &Pubkey,
+        allocation_type: AllocationType,
+    ) -> Result<u64, ClientError> {
+        self.query(instruction::query_allocation_remaining(
+            &self.program_id,
+            token_info,
+            allocation_type,
+        ))
+        .await
+    }
+
+    pub async fn fetch_token_info(&mut self, address: &Pubkey) -> Result<TokenInfo, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_vesting_schedule(
+        &mut self,
+        address: &Pubkey,
+    ) -> Result<VestingSchedule, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_stake_info(&mut self, address: &Pubkey) -> Result<StakeInfo, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    /// The operator the position of `owner` is delegated to, which
+    /// instructions on the position have to pass along. `None` as well when
+    /// there is no position, leaving the program to reject it.
+    pub async fn delegated_operator(&mut self, mint: &Pubkey, owner: &Pubkey) -> Result<Option<Pubkey>, ClientError> {
+        let (stake_account, _) = find_stake_address(&self.program_id, mint, owner);
+        match self.fetch_stake_info(&stake_account).await {
+            Ok(stake_info) => Ok(stake_info.is_delegated().then_some(stake_info.operator)),
+            Err(ClientError::AccountNotFound(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub async fn fetch_staking_pool(&mut self, address: &Pubkey) -> Result<StakingPool, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_staking_config(&mut self, address: &Pubkey) -> Result<StakingConfig, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_unbonding_entry(&mut self, address: &Pubkey) -> Result<UnbondingEntry, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_liquid_pool(&mut self, address: &Pubkey) -> Result<LiquidPool, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_locker(&mut self, address: &Pubkey) -> Result<Locker, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_vote_lock(&mut self, address: &Pubkey) -> Result<VoteLock, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_operator(&mut self, address: &Pubkey) -> Result<Operator, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_farm(&mut self, address: &Pubkey) -> Result<Farm, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_farm_position(&mut self, address: &Pubkey) -> Result<FarmPosition, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    pub async fn fetch_emission_schedule(&mut self, address: &Pubkey) -> Result<EmissionSchedule, ClientError> {
+        self.fetch_account(address).await
+    }
+
+    /// Reads and decodes a program account.
+    pub async fn fetch_account<A: Pack + IsInitialized>(&mut self, address: &Pubkey) -> Result<A, ClientError> {
+        let account = self
+            .transport
+            .get_account(address)
+            .await?
+            .ok_or(ClientError::AccountNotFound(*address))?;
+        if account.owner != self.program_id {
+            return Err(ClientError::InvalidOwner(*address));
+        }
+        Ok(A::unpack(&account.data)?)
+    }
+}
@@ -0,0 +1,146 @@
+use async_trait::async_trait;
+use base64::Engine;
+use solana_banks_client::BanksClient;
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::account::Account;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::UiReturnDataEncoding;
+
+use crate::ClientError;
+
+/// The handful of cluster operations the client needs.
+///
+/// Implemented for `solana-program-test`'s `BanksClient` and for the
+/// nonblocking `RpcClient`.
+#[async_trait]
+pub trait Transport {
+    async fn get_account(&mut self, address: &Pubkey) -> Result<Option<Account>, ClientError>;
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        data_len: usize,
+    ) -> Result<u64, ClientError>;
+
+    async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError>;
+
+    /// Sends the transaction and waits until it is processed.
+    async fn send_transaction(&mut self, transaction: &Transaction) -> Result<Signature, ClientError>;
+
+    /// Simulates the transaction and returns the program that set return
+    /// data last, along with the data.
+    async fn simulate_return_data(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<Option<(Pubkey, Vec<u8>)>, ClientError>;
+}
+
+#[async_trait]
+impl Transport for BanksClient {
+    async fn get_account(&mut self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        BanksClient::get_account(self, *address)
+            .await
+            .map_err(ClientError::transport)
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        data_len: usize,
+    ) -> Result<u64, ClientError> {
+        let rent = self.get_rent().await.map_err(ClientError::transport)?;
+        Ok(rent.minimum_balance(data_len))
+    }
+
+    async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError> {
+        BanksClient::get_latest_blockhash(self)
+            .await
+            .map_err(ClientError::transport)
+    }
+
+    async fn send_transaction(&mut self, transaction: &Transaction) -> Result<Signature, ClientError> {
+        self.process_transaction(transaction.clone())
+            .await
+            .map_err(ClientError::transport)?;
+        Ok(transaction.signatures[0])
+    }
+
+    async fn simulate_return_data(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<Option<(Pubkey, Vec<u8>)>, ClientError> {
+        let simulation = self
+            .simulate_transaction(transaction.clone())
+            .await
+            .map_err(ClientError::transport)?;
+        if let Some(Err(err)) = simulation.result {
+            return Err(ClientError::transport(err));
+        }
+        Ok(simulation
+            .simulation_details
+            .and_then(|details| details.return_data)
+            .map(|return_data| (return_data.program_id, return_data.data)))
+    }
+}
+
+#[async_trait]
+impl Transport for RpcClient {
+    async fn get_account(&mut self, address: &Pubkey) -> Result<Option<Account>, ClientError> {
+        let response = self
+            .get_account_with_commitment(address, self.commitment())
+            .await
+            .map_err(ClientError::transport)?;
+        Ok(response.value)
+    }
+
+    async fn get_minimum_balance_for_rent_exemption(
+        &mut self,
+        data_len: usize,
+    ) -> Result<u64, ClientError> {
+        RpcClient::get_minimum_balance_for_rent_exemption(self, data_len)
+            .await
+            .map_err(ClientError::transport)
+    }
+
+    async fn get_latest_blockhash(&mut self) -> Result<Hash, ClientError> {
+        RpcClient::get_latest_blockhash(self)
+            .await
+            .map_err(ClientError::transport)
+    }
+
+    async fn send_transaction(&mut self, transaction: &Transaction) -> Result<Signature, ClientError> {
+        self.send_and_confirm_transaction(transaction)
+            .await
+            .map_err(ClientError::transport)
+    }
+
+    async fn simulate_return_data(
+        &mut self,
+        transaction: &Transaction,
+    ) -> Result<Option<(Pubkey, Vec<u8>)>, ClientError> {
+        let simulation = self
+            .simulate_transaction(transaction)
+            .await
+            .map_err(ClientError::transport)?
+            .value;
+        if let Some(err) = simulation.err {
+            return Err(ClientError::transport(err));
+        }
+        let return_data = match simulation.return_data {
+            Some(return_data) => return_data,
+            None => return Ok(None),
+        };
+        let program_id = return_data
+            .program_id
+            .parse()
+            .map_err(ClientError::transport)?;
+        let (data, encoding) = return_data.data;
+        let data = match encoding {
+            UiReturnDataEncoding::Base64 => base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(ClientError::transport)?,
+        };
+        Ok(Some((program_id, data)))
+    }
+}
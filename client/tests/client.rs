@@ -0,0 +1,1683 @@
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program_test::{processor, BanksClient, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::TransactionError;
+use solana_token::error::TokenError;
+use solana_token::instruction::AllocationType;
+use solana_token::processor::Processor;
+use solana_token::state::{LockTier, StakingParams};
+use solana_token_client::{ClientError, TokenClient};
+use spl_token::state::Mint;
+
+#[tokio::test]
+async fn test_create_and_fetch_vesting() {
+    let program_id = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let mint_authority = Keypair::new();
+    let mut program_test = ProgramTest::new("solana_token", program_id, processor!(Processor::process));
+    let mut mint_data = vec![0; Mint::LEN];
+    Mint::pack(
+        Mint {
+            mint_authority: COption::Some(mint_authority.pubkey()),
+            decimals: 9,
+            is_initialized: true,
+            ..Mint::default()
+        },
+        &mut mint_data,
+    )
+    .unwrap();
+    program_test.add_account(
+        mint,
+        Account {
+            lamports: 1_000_000_000,
+            data: mint_data,
+            owner: spl_token::id(),
+            ..Account::default()
+        },
+    );
+    let (banks_client, payer, _) = program_test.start().await;
+    let mut client = TokenClient::new(banks_client, program_id, Box::new(payer));
+
+    // The mint authority must sign and match the mint.
+    let token_info = Keypair::new();
+    assert!(client
+        .initialize_token_info(&token_info, &Keypair::new(), &mint)
+        .await
+        .is_err());
+    client
+        .initialize_token_info(&token_info, &mint_authority, &mint)
+        .await
+        .unwrap();
+    let info = client.fetch_token_info(&token_info.pubkey()).await.unwrap();
+    assert_eq!(info.mint, mint);
+    assert_eq!(info.mint_authority, mint_authority.pubkey());
+
+    let vesting = Keypair::new();
+    let beneficiary = Pubkey::new_unique();
+    client
+        .create_vesting(
+            &vesting,
+            &token_info.pubkey(),
+            &mint_authority,
+            &beneficiary,
+            AllocationType::Team,
+            1_000,
+            0,
+            0,
+            100,
+        )
+        .await
+        .unwrap();
+
+    let schedule = client.fetch_vesting_schedule(&vesting.pubkey()).await.unwrap();
+    assert_eq!(schedule.beneficiary, beneficiary);
+    assert_eq!(schedule.total_amount, 1_000);
+    assert_eq!(schedule.released_amount, 0);
+    let info_after = client.fetch_token_info(&token_info.pubkey()).await.unwrap();
+    assert_eq!(info_after.team_allocation, info.team_allocation - 1_000);
+
+    // The schedule ended long before the test clock, so all of it is vested.
+    assert_eq!(client.query_vested_amount(&vesting.pubkey()).await.unwrap(), 1_000);
+    assert_eq!(client.query_releasable_amount(&vesting.pubkey()).await.unwrap(), 1_000);
+    assert_eq!(
+        client
+            .query_allocation_remaining(&token_info.pubkey(), AllocationType::Team)
+            .await
+            .unwrap(),
+        info_after.team_allocation
+    );
+    assert!(client.query_vested_amount(&token_info.pubkey()).await.is_err());
+
+    let missing = Pubkey::new_unique();
+    assert!(matches!(
+        client.fetch_vesting_schedule(&missing).await,
+        Err(ClientError::AccountNotFound(key)) if key == missing
+    ));
+}
+
+#[tokio::test]
+async fn test_migrate_version_0_accounts() {
+    use solana_sdk::rent::Rent;
+    use solana_token::migration::v0;
+    use solana_token::state::{AccountType, StakeInfo, VestingSchedule};
+    use solana_token::token_info::TokenInfo;
+
+    let program_id = Pubkey::new_unique();
+    let admin = Keypair::new();
+    let mut program_test = ProgramTest::new("solana_token", program_id, processor!(Processor::process));
+    program_test.add_account(
+        admin.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+
+    let legacy = [
+        (
+            Pubkey::new_unique(),
+            AccountType::TokenInfo,
+            borsh::to_vec(&v0::TokenInfo {
+                total_supply: 1_000,
+                team_allocation: 100,
+                investors_allocation: 200,
+                liquidity_reserve: 150,
+                development_reserve: 200,
+                community_rewards: 250,
+                strategic_reserve: 100,
+                mint_authority: admin.pubkey(),
+                mint: Pubkey::new_unique(),
+            })
+            .unwrap(),
+        ),
+        (
+            Pubkey::new_unique(),
+            AccountType::VestingSchedule,
+            borsh::to_vec(&v0::VestingSchedule {
+                beneficiary: Pubkey::new_unique(),
+                total_amount: 100,
+                released_amount: 40,
+                start_time: 0,
+                end_time: 100,
+                allocation_type: AllocationType::Investors,
+            })
+            .unwrap(),
+        ),
+        (
+            Pubkey::new_unique(),
+            AccountType::StakeInfo,
+            borsh::to_vec(&v0::StakeInfo {
+                amount: 7,
+                start_time: 3,
+            })
+            .unwrap(),
+        ),
+    ];
+    for (address, _, data) in &legacy {
+        program_test.add_account(
+            *address,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data: data.clone(),
+                owner: program_id,
+                ..Account::default()
+            },
+        );
+    }
+    let (banks_client, payer, _) = program_test.start().await;
+    let mut client = TokenClient::new(banks_client, program_id, Box::new(payer));
+    let token_info = legacy[0].0;
+
+    // Only the mint authority recorded in the token info may migrate.
+    let intruder = Keypair::new();
+    assert!(client
+        .migrate_account(&legacy[2].0, &token_info, &intruder, AccountType::StakeInfo)
+        .await
+        .is_err());
+
+    for (address, account_type, _) in &legacy {
+        client
+            .migrate_account(address, &token_info, &admin, *account_type)
+            .await
+            .unwrap();
+    }
+
+    let info = client.fetch_token_info(&token_info).await.unwrap();
+    assert_eq!(info.community_rewards, 250);
+    assert_eq!(info.mint_authority, admin.pubkey());
+    let schedule = client.fetch_vesting_schedule(&legacy[1].0).await.unwrap();
+    assert_eq!((schedule.total_amount, schedule.released_amount), (100, 40));
+    assert_eq!(schedule.allocation_type, AllocationType::Investors);
+    let stake = client.fetch_stake_info(&legacy[2].0).await.unwrap();
+    assert_eq!((stake.amount, stake.start_time), (7, 3));
+    assert_eq!(client.query_stake_info(&legacy[2].0).await.unwrap(), stake);
+
+    for ((address, _, _), len) in legacy
+        .iter()
+        .zip([TokenInfo::LEN, VestingSchedule::LEN, StakeInfo::LEN])
+    {
+        let account = client.transport_mut().get_account(*address).await.unwrap().unwrap();
+        assert_eq!(account.data.len(), len);
+        assert!(Rent::default().is_exempt(account.lamports, len));
+    }
+}
+
+/// A mint with a token info, stake vault, staking pool and staking config,
+/// and one funded token account per holder.
+struct Staking {
+    context: ProgramTestContext,
+    client: TokenClient<BanksClient>,
+    program_id: Pubkey,
+    mint: Pubkey,
+    admin: Keypair,
+    /// Token account of the admin, holding the rewards to fund.
+    admin_tokens: Pubkey,
+    token_info: Pubkey,
+}
+
+const WEEK: i64 = 7 * 24 * 60 * 60;
+const HOUR: i64 = 60 * 60;
+
+/// A week long lock and no other restriction.
+fn weekly(reward_rate: u64) -> StakingParams {
+    StakingParams {
+        min_stake: 0,
+        lock_duration: WEEK,
+        reward_rate,
+        max_total_stake: u64::MAX,
+        paused: false,
+        unbonding_period: 0,
+        ..StakingParams::default()
+    }
+}
+
+/// Starts a chain at `start_time` where each `(owner, token account,
+/// amount)` holder exists and the admin holds `rewards`, and creates the
+/// staking pool and config of the mint.
+async fn start_staking(
+    holders: &[(&Keypair, Pubkey, u64)],
+    rewards: u64,
+    params: StakingParams,
+    start_time: i64,
+) -> Staking {
+    use spl_token::state::{Account as TokenAccount, AccountState};
+
+    let program_id = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let admin = Keypair::new();
+    let admin_tokens = Pubkey::new_unique();
+    let mut holders = holders.to_vec();
+    holders.push((&admin, admin_tokens, rewards));
+    let mut program_test = ProgramTest::new("solana_token", program_id, processor!(Processor::process));
+
+    let mut mint_data = vec![0; Mint::LEN];
+    Mint::pack(
+        Mint {
+            mint_authority: COption::Some(admin.pubkey()),
+            supply: holders.iter().map(|(_, _, amount)| amount).sum(),
+            decimals: 9,
+            is_initialized: true,
+            ..Mint::default()
+        },
+        &mut mint_data,
+    )
+    .unwrap();
+    let mut accounts = vec![(mint, mint_data, spl_token::id())];
+    for (owner, token_account, amount) in &holders {
+        let mut token_data = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                mint,
+                owner: owner.pubkey(),
+                amount: *amount,
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            },
+            &mut token_data,
+        )
+        .unwrap();
+        accounts.push((*token_account, token_data, spl_token::id()));
+        accounts.push((owner.pubkey(), Vec::new(), solana_sdk::system_program::id()));
+    }
+    for (address, data, owner) in accounts {
+        program_test.add_account(
+            address,
+            Account {
+                lamports: 1_000_000_000,
+                data,
+                owner,
+                ..Account::default()
+            },
+        );
+    }
+
+    let mut context = program_test.start_with_context().await;
+    set_time(&mut context, start_time).await;
+    let mut client = TokenClient::new(
+        context.banks_client.clone(),
+        program_id,
+        Box::new(context.payer.insecure_clone()),
+    );
+    let token_info = Keypair::new();
+    client.initialize_token_info(&token_info, &admin, &mint).await.unwrap();
+    client.initialize_vault(&mint).await.unwrap();
+    client
+        .initialize_staking_pool(&mint, &token_info.pubkey(), &admin, params.reward_rate)
+        .await
+        .unwrap();
+    client
+        .initialize_staking_config(&mint, &token_info.pubkey(), &admin, params)
+        .await
+        .unwrap();
+    Staking {
+        context,
+        client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info: token_info.pubkey(),
+    }
+}
+
+/// Moves the chain to a later slot, so that repeated transactions get a new
+/// blockhash, with the clock at `unix_timestamp`.
+async fn set_time(context: &mut ProgramTestContext, unix_timestamp: i64) {
+    let slot = context.banks_client.get_root_slot().await.unwrap();
+    context.warp_to_slot(slot + 2).unwrap();
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
+
+fn assert_token_error(result: Result<Signature, ClientError>, expected: TokenError) {
+    let error = match result {
+        Err(ClientError::Transport(error)) => error,
+        other => panic!("expected {:?}, got {:?}", expected, other),
+    };
+    match error.downcast_ref::<BanksClientError>() {
+        Some(BanksClientError::TransactionError(TransactionError::InstructionError(_, InstructionError::Custom(code)))) => {
+            assert_eq!(*code, expected as u32, "expected {:?}", expected)
+        }
+        _ => panic!("expected {:?}, got {}", expected, error),
+    }
+}
+
+async fn token_balance(banks_client: &mut BanksClient, address: &Pubkey) -> u64 {
+    use spl_token::state::Account as TokenAccount;
+
+    let account = banks_client.get_account(*address).await.unwrap().unwrap();
+    assert_eq!(account.owner, spl_token::id());
+    TokenAccount::unpack(&account.data).unwrap().amount
+}
+
+#[tokio::test]
+async fn test_stake_moves_tokens_through_the_vault() {
+    use solana_token::state::{find_stake_address, find_vault_address};
+
+    const SUPPLY: u64 = 1_000;
+    const START: i64 = 1_000_000;
+
+    let staker = Keypair::new();
+    let token_account = Pubkey::new_unique();
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        ..
+    } = start_staking(&[(&staker, token_account, SUPPLY)], 0, weekly(0), START).await;
+    let (vault, _) = find_vault_address(&program_id, &mint);
+    let (stake_account, _) = find_stake_address(&program_id, &mint, &staker.pubkey());
+
+    assert!(client.initialize_vault(&mint).await.is_err());
+    client.initialize_stake(&mint, &staker, LockTier::Days30).await.unwrap();
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!((position.owner, position.mint, position.amount), (staker.pubkey(), mint, 0));
+
+    client.stake(&token_account, &mint, &staker, 400).await.unwrap();
+    // Staking more than the token account holds fails in the transfer.
+    assert!(client.stake(&token_account, &mint, &staker, SUPPLY).await.is_err());
+
+    let balances = |context: &mut ProgramTestContext| {
+        let mut banks_client = context.banks_client.clone();
+        async move {
+            let supply = banks_client.get_account(mint).await.unwrap().unwrap();
+            (
+                token_balance(&mut banks_client, &token_account).await,
+                token_balance(&mut banks_client, &vault).await,
+                Mint::unpack(&supply.data).unwrap().supply,
+            )
+        }
+    };
+    assert_eq!(balances(&mut context).await, (SUPPLY - 400, 400, SUPPLY));
+    assert_eq!(client.fetch_stake_info(&stake_account).await.unwrap().amount, 400);
+
+    // Someone else has no position of their own to take from.
+    let intruder = Keypair::new();
+    assert!(client.unstake(&token_account, &mint, &intruder, 100).await.is_err());
+
+    // Still locked.
+    assert_token_error(
+        client.unstake(&token_account, &mint, &staker, 100).await,
+        TokenError::StakeLocked,
+    );
+
+    set_time(&mut context, START + LockTier::Days30.duration()).await;
+    client.unstake(&token_account, &mint, &staker, 100).await.unwrap();
+    assert_eq!(balances(&mut context).await, (SUPPLY - 300, 300, SUPPLY));
+    client.unstake(&token_account, &mint, &staker, 300).await.unwrap();
+    assert_eq!(balances(&mut context).await, (SUPPLY, 0, SUPPLY));
+    assert_eq!(client.fetch_stake_info(&stake_account).await.unwrap().amount, 0);
+}
+
+#[tokio::test]
+async fn test_rewards_are_shared_pro_rata() {
+    use solana_token::state::find_reward_vault_address;
+
+    const START: i64 = 1_000_000;
+
+    let (alice, bob) = (Keypair::new(), Keypair::new());
+    let (alice_tokens, bob_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&[(&alice, alice_tokens, 300), (&bob, bob_tokens, 100)], 1_000, weekly(10), START).await;
+    let (reward_vault, _) = find_reward_vault_address(&program_id, &mint);
+
+    // Only the admin funds rewards.
+    assert!(client
+        .fund_rewards(&mint, &token_info, &alice_tokens, &alice, 300)
+        .await
+        .is_err());
+    let community_rewards = client.fetch_token_info(&token_info).await.unwrap().community_rewards;
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 1_000)
+        .await
+        .unwrap();
+    assert_eq!(
+        client.fetch_token_info(&token_info).await.unwrap().community_rewards,
+        community_rewards - 1_000
+    );
+    assert_eq!(token_balance(&mut context.banks_client, &reward_vault).await, 1_000);
+
+    for (staker, tokens, amount) in [(&alice, alice_tokens, 300), (&bob, bob_tokens, 100)] {
+        client.initialize_stake(&mint, staker, LockTier::Days30).await.unwrap();
+        client.stake(&tokens, &mint, staker, amount).await.unwrap();
+    }
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 0);
+
+    // 10 per second for 40 seconds, split 3 to 1.
+    set_time(&mut context, START + 40).await;
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 300);
+    assert_eq!(client.query_pending_rewards(&mint, &bob.pubkey()).await.unwrap(), 100);
+    client.claim_rewards(&alice_tokens, &mint, &alice).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 300);
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 0);
+
+    // Emission stops once the 600 rewards left are out.
+    set_time(&mut context, START + 1_000).await;
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 450);
+    assert_eq!(client.query_pending_rewards(&mint, &bob.pubkey()).await.unwrap(), 250);
+    client.claim_rewards(&alice_tokens, &mint, &alice).await.unwrap();
+    client.claim_rewards(&bob_tokens, &mint, &bob).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 750);
+    assert_eq!(token_balance(&mut context.banks_client, &bob_tokens).await, 250);
+    assert_eq!(token_balance(&mut context.banks_client, &reward_vault).await, 0);
+
+    // Nobody can claim into an account they do not own.
+    set_time(&mut context, START + 2_000).await;
+    assert!(client.claim_rewards(&alice_tokens, &mint, &bob).await.is_err());
+}
+
+#[tokio::test]
+async fn test_positions_from_before_the_pool_are_counted_when_touched() {
+    use solana_sdk::account::AccountSharedData;
+    use solana_token::state::{find_stake_address, find_staking_pool_address, StakeInfo, StakingPool};
+
+    const START: i64 = 1_000_000;
+
+    let alice = Keypair::new();
+    let bob = Keypair::new();
+    let alice_tokens = Pubkey::new_unique();
+    let bob_tokens = Pubkey::new_unique();
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        ..
+    } = start_staking(&[(&alice, alice_tokens, 400), (&bob, bob_tokens, 100)], 0, weekly(0), START).await;
+    let (staking_pool, _) = find_staking_pool_address(&program_id, &mint);
+    let (stake_account, _) = find_stake_address(&program_id, &mint, &alice.pubkey());
+    client.initialize_stake(&mint, &alice, LockTier::Days30).await.unwrap();
+    client.stake(&alice_tokens, &mint, &alice, 300).await.unwrap();
+
+    // Turn the position into one staked before the pool: its tokens are in
+    // the vault, but it weighs nothing and the pool does not count it.
+    let mut account = context.banks_client.get_account(stake_account).await.unwrap().unwrap();
+    let mut position = StakeInfo::unpack(&account.data).unwrap();
+    position.weight = 0;
+    StakeInfo::pack(position, &mut account.data).unwrap();
+    context.set_account(&stake_account, &AccountSharedData::from(account));
+    let mut account = context.banks_client.get_account(staking_pool).await.unwrap().unwrap();
+    let mut pool = StakingPool::unpack(&account.data).unwrap();
+    (pool.total_staked, pool.total_weight) = (0, 0);
+    StakingPool::pack(pool, &mut account.data).unwrap();
+    context.set_account(&staking_pool, &AccountSharedData::from(account));
+
+    client.initialize_stake(&mint, &bob, LockTier::Days30).await.unwrap();
+    client.stake(&bob_tokens, &mint, &bob, 100).await.unwrap();
+    let pool = client.fetch_staking_pool(&staking_pool).await.unwrap();
+    assert_eq!((pool.total_staked, pool.total_weight), (100, 100));
+
+    // The first top-up counts the whole position.
+    client.stake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+    let pool = client.fetch_staking_pool(&staking_pool).await.unwrap();
+    assert_eq!((pool.total_staked, pool.total_weight), (500, 500));
+
+    set_time(&mut context, START + LockTier::Days30.duration()).await;
+    client.unstake(&alice_tokens, &mint, &alice, 400).await.unwrap();
+    let pool = client.fetch_staking_pool(&staking_pool).await.unwrap();
+    assert_eq!((pool.total_staked, pool.total_weight), (100, 100));
+}
+
+#[tokio::test]
+async fn test_staking_config_rules() {
+    use solana_token::state::find_staking_config_address;
+
+    const START: i64 = 1_000_000;
+
+    let staker = Keypair::new();
+    let tokens = Pubkey::new_unique();
+    // The config lock outlasts the 30 day tier.
+    let params = StakingParams {
+        min_stake: 100,
+        lock_duration: 60 * 24 * 60 * 60,
+        max_total_stake: 500,
+        ..weekly(0)
+    };
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        token_info,
+        ..
+    } = start_staking(&[(&staker, tokens, 1_000)], 0, params, START).await;
+    let (staking_config, _) = find_staking_config_address(&program_id, &mint);
+    assert_eq!(client.fetch_staking_config(&staking_config).await.unwrap().params, params);
+
+    client.initialize_stake(&mint, &staker, LockTier::Days30).await.unwrap();
+    assert_token_error(client.stake(&tokens, &mint, &staker, 50).await, TokenError::StakeBelowMinimum);
+    assert_token_error(client.stake(&tokens, &mint, &staker, 600).await, TokenError::MaxTotalStakeExceeded);
+    client.stake(&tokens, &mint, &staker, 400).await.unwrap();
+    assert_token_error(client.unstake(&tokens, &mint, &staker, 100).await, TokenError::StakeLocked);
+    set_time(&mut context, START + LockTier::Days30.duration()).await;
+    assert_token_error(client.unstake(&tokens, &mint, &staker, 100).await, TokenError::StakeLocked);
+
+    set_time(&mut context, START + params.lock_duration).await;
+    assert_token_error(
+        client.initialize_staking_config(&mint, &token_info, &admin, params).await,
+        TokenError::AlreadyInitialized,
+    );
+    // The position is either closed or kept at the minimum.
+    assert_token_error(client.unstake(&tokens, &mint, &staker, 350).await, TokenError::StakeBelowMinimum);
+    client.unstake(&tokens, &mint, &staker, 300).await.unwrap();
+
+    // Only the admin changes the rules.
+    let paused = StakingParams { paused: true, ..params };
+    assert_token_error(
+        client.update_staking_config(&mint, &token_info, &staker, paused).await,
+        TokenError::InvalidAuthority,
+    );
+    client.update_staking_config(&mint, &token_info, &admin, paused).await.unwrap();
+    assert_token_error(client.stake(&tokens, &mint, &staker, 10).await, TokenError::StakingPaused);
+    assert_token_error(client.unstake(&tokens, &mint, &staker, 10).await, TokenError::StakingPaused);
+
+    let relaxed = StakingParams {
+        min_stake: 0,
+        lock_duration: 0,
+        max_total_stake: 150,
+        ..params
+    };
+    client.update_staking_config(&mint, &token_info, &admin, relaxed).await.unwrap();
+    assert_eq!(client.fetch_staking_config(&staking_config).await.unwrap().params, relaxed);
+    assert_token_error(client.stake(&tokens, &mint, &staker, 100).await, TokenError::MaxTotalStakeExceeded);
+    client.stake(&tokens, &mint, &staker, 50).await.unwrap();
+    // Averaged with the 100 tokens unlocked for a month, the top-up is
+    // unlocked too.
+    client.unstake(&tokens, &mint, &staker, 120).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &tokens).await, 970);
+}
+
+#[tokio::test]
+async fn test_lock_tiers_boost_rewards() {
+    const START: i64 = 1_000_000;
+
+    let (alice, bob) = (Keypair::new(), Keypair::new());
+    let (alice_tokens, bob_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+        ..
+    } = start_staking(&[(&alice, alice_tokens, 100), (&bob, bob_tokens, 100)], 1_000, weekly(3), START).await;
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 1_000)
+        .await
+        .unwrap();
+
+    client.initialize_stake(&mint, &alice, LockTier::Days30).await.unwrap();
+    client.initialize_stake(&mint, &bob, LockTier::Days365).await.unwrap();
+    client.stake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+    client.stake(&bob_tokens, &mint, &bob, 100).await.unwrap();
+    let (bob_stake, _) = solana_token::state::find_stake_address(&program_id, &mint, &bob.pubkey());
+    let position = client.fetch_stake_info(&bob_stake).await.unwrap();
+    assert_eq!((position.weight, position.unlock_time), (200, START + LockTier::Days365.duration()));
+
+    // 3 per second for 30 seconds, split 1 to 2.
+    set_time(&mut context, START + 30).await;
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 30);
+    assert_eq!(client.query_pending_rewards(&mint, &bob.pubkey()).await.unwrap(), 60);
+
+    assert_token_error(
+        client.extend_lock(&mint, &bob, LockTier::Days180).await,
+        TokenError::LockNotExtended,
+    );
+    client.extend_lock(&mint, &alice, LockTier::Days180).await.unwrap();
+
+    // 105 more over 35 seconds, split 150 to 200.
+    set_time(&mut context, START + 65).await;
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 75);
+    assert_eq!(client.query_pending_rewards(&mint, &bob.pubkey()).await.unwrap(), 120);
+
+    // Each position unlocks on its own schedule.
+    set_time(&mut context, START + LockTier::Days180.duration()).await;
+    assert_token_error(
+        client.unstake(&alice_tokens, &mint, &alice, 100).await,
+        TokenError::StakeLocked,
+    );
+    assert_token_error(client.unstake(&bob_tokens, &mint, &bob, 100).await, TokenError::StakeLocked);
+    set_time(&mut context, START + 30 + LockTier::Days180.duration()).await;
+    client.unstake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+    set_time(&mut context, START + LockTier::Days365.duration()).await;
+    client.unstake(&bob_tokens, &mint, &bob, 100).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unbonding_queue() {
+    use solana_token::state::{find_stake_address, find_unbonding_address, find_vault_address};
+
+    const START: i64 = 1_000_000;
+    const COOLDOWN: i64 = 3 * 24 * 60 * 60;
+
+    let (alice, bob) = (Keypair::new(), Keypair::new());
+    let (alice_tokens, bob_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let params = StakingParams {
+        lock_duration: 0,
+        unbonding_period: COOLDOWN,
+        ..weekly(10)
+    };
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&[(&alice, alice_tokens, 400), (&bob, bob_tokens, 100)], 1_000, params, START).await;
+    let (stake_account, _) = find_stake_address(&program_id, &mint, &alice.pubkey());
+    let (vault, _) = find_vault_address(&program_id, &mint);
+    for (staker, tokens, amount) in [(&alice, alice_tokens, 400), (&bob, bob_tokens, 100)] {
+        client.initialize_stake(&mint, staker, LockTier::Days30).await.unwrap();
+        client.stake(&tokens, &mint, staker, amount).await.unwrap();
+    }
+
+    // Unstaking waits for the tier to unlock and then goes through the queue.
+    set_time(&mut context, START + LockTier::Days30.duration()).await;
+    assert_token_error(
+        client.unstake(&alice_tokens, &mint, &alice, 100).await,
+        TokenError::UnbondingRequired,
+    );
+    assert_eq!(client.request_unstake(&mint, &alice, 300).await.unwrap(), 0);
+    assert_eq!(client.request_unstake(&mint, &alice, 100).await.unwrap(), 1);
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!((position.amount, position.weight, position.unbonding_amount), (0, 0, 400));
+    let (first, _) = find_unbonding_address(&program_id, &stake_account, 0);
+    let entry = client.fetch_unbonding_entry(&first).await.unwrap();
+    assert_eq!((entry.amount, entry.withdrawable_at), (300, START + LockTier::Days30.duration() + COOLDOWN));
+
+    // Unbonding tokens earn nothing, so bob gets the whole emission.
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 1_000)
+        .await
+        .unwrap();
+    set_time(&mut context, START + LockTier::Days30.duration() + 10).await;
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 0);
+    assert_eq!(client.query_pending_rewards(&mint, &bob.pubkey()).await.unwrap(), 100);
+
+    assert_token_error(
+        client.withdraw_unbonded(&alice_tokens, &mint, &alice, 0).await,
+        TokenError::UnbondingNotComplete,
+    );
+    // Someone else cannot touch the entries of alice.
+    assert!(client.cancel_unbonding(&mint, &bob, 1).await.is_err());
+
+    // Cancelling restakes under the lock the tokens left with.
+    client.cancel_unbonding(&mint, &alice, 1).await.unwrap();
+    assert!(client.transport_mut().get_account(find_unbonding_address(&program_id, &stake_account, 1).0).await.unwrap().is_none());
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!((position.amount, position.unbonding_amount), (100, 300));
+    assert_eq!((position.start_time, position.unlock_time), (START, START + LockTier::Days30.duration()));
+
+    set_time(&mut context, START + LockTier::Days30.duration() + COOLDOWN).await;
+    client.withdraw_unbonded(&alice_tokens, &mint, &alice, 0).await.unwrap();
+    assert!(client.transport_mut().get_account(first).await.unwrap().is_none());
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 300);
+    assert_eq!(token_balance(&mut context.banks_client, &vault).await, 200);
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!((position.amount, position.unbonding_amount, position.next_unbonding_id), (100, 0, 2));
+}
+
+#[tokio::test]
+async fn test_top_up_averages_stake_age() {
+    use solana_token::state::find_stake_address;
+
+    const START: i64 = 1_000_000;
+
+    let (alice, bob) = (Keypair::new(), Keypair::new());
+    let (alice_tokens, bob_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+        ..
+    } = start_staking(&[(&alice, alice_tokens, 400), (&bob, bob_tokens, 100)], 1_000, weekly(4), START).await;
+    let (stake_account, _) = find_stake_address(&program_id, &mint, &alice.pubkey());
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 1_000)
+        .await
+        .unwrap();
+    for (staker, tokens, amount) in [(&alice, alice_tokens, 300), (&bob, bob_tokens, 100)] {
+        client.initialize_stake(&mint, staker, LockTier::Days30).await.unwrap();
+        client.stake(&tokens, &mint, staker, amount).await.unwrap();
+    }
+
+    // A top-up keeps what was earned and only adds its own share from then.
+    set_time(&mut context, START + 50).await;
+    client.stake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 150);
+    set_time(&mut context, START + 100).await;
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 310);
+    assert_eq!(client.query_pending_rewards(&mint, &bob.pubkey()).await.unwrap(), 90);
+
+    // 100 tokens 50 seconds younger than 300 others move the position by a
+    // quarter of that.
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!(position.start_time, START + 12);
+    assert_eq!(position.unlock_time, START + 12 + LockTier::Days30.duration());
+
+    set_time(&mut context, START + 11 + LockTier::Days30.duration()).await;
+    assert_token_error(
+        client.unstake(&alice_tokens, &mint, &alice, 200).await,
+        TokenError::StakeLocked,
+    );
+    set_time(&mut context, START + 12 + LockTier::Days30.duration()).await;
+    client.unstake(&alice_tokens, &mint, &alice, 200).await.unwrap();
+
+    // Unstaking part of the position leaves its age alone.
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!((position.amount, position.start_time), (200, START + 12));
+}
+
+#[tokio::test]
+async fn test_emergency_unstake_penalty() {
+    use solana_token::instruction;
+    use solana_token::state::{
+        find_reward_vault_address, find_staking_pool_address, PenaltyCurve, PenaltyDestination,
+    };
+
+    const START: i64 = 1_000_000;
+    const DAY: i64 = 24 * 60 * 60;
+
+    let staker = Keypair::new();
+    let tokens = Pubkey::new_unique();
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&[(&staker, tokens, 1_000)], 0, weekly(0), START).await;
+    client.initialize_stake(&mint, &staker, LockTier::Days30).await.unwrap();
+    client.stake(&tokens, &mint, &staker, 1_000).await.unwrap();
+
+    // Without a penalty curve a locked position stays locked.
+    assert_token_error(client.emergency_unstake(&tokens, &mint, &staker, 100).await, TokenError::StakeLocked);
+
+    // Halfway through the lock, half of the 50% penalty is left.
+    let linear = StakingParams {
+        penalty_curve: PenaltyCurve::LinearDecay,
+        penalty_bps: 5_000,
+        penalty_destination: PenaltyDestination::RewardPool,
+        ..weekly(0)
+    };
+    client.update_staking_config(&mint, &token_info, &admin, linear).await.unwrap();
+    set_time(&mut context, START + 15 * DAY).await;
+    client.emergency_unstake(&tokens, &mint, &staker, 400).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &tokens).await, 300);
+    let (reward_vault, _) = find_reward_vault_address(&program_id, &mint);
+    assert_eq!(token_balance(&mut context.banks_client, &reward_vault).await, 100);
+    let (staking_pool, _) = find_staking_pool_address(&program_id, &mint);
+    let pool = client.fetch_staking_pool(&staking_pool).await.unwrap();
+    assert_eq!((pool.total_staked, pool.undistributed_rewards), (600, 100));
+
+    let flat = StakingParams {
+        penalty_curve: PenaltyCurve::Flat,
+        penalty_bps: 1_000,
+        penalty_destination: PenaltyDestination::Burn,
+        ..weekly(0)
+    };
+    client.update_staking_config(&mint, &token_info, &admin, flat).await.unwrap();
+    client.emergency_unstake(&tokens, &mint, &staker, 200).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &tokens).await, 480);
+    let mint_account = context.banks_client.get_account(mint).await.unwrap().unwrap();
+    assert_eq!(Mint::unpack(&mint_account.data).unwrap().supply, 980);
+
+    // The treasury has to be a token account of the mint.
+    let (_, other_tokens) = create_mint(&mut context, &[(&admin, 0)]).await;
+    let other_tokens = other_tokens[0];
+    let treasury_at = |treasury| StakingParams {
+        penalty_destination: PenaltyDestination::Treasury,
+        treasury,
+        ..flat
+    };
+    assert_token_error(
+        client.update_staking_config(&mint, &token_info, &admin, treasury_at(other_tokens)).await,
+        TokenError::InvalidPenaltyAccount,
+    );
+    assert!(client
+        .update_staking_config(&mint, &token_info, &admin, treasury_at(staking_pool))
+        .await
+        .is_err());
+
+    let treasury = StakingParams {
+        penalty_destination: PenaltyDestination::Treasury,
+        treasury: admin_tokens,
+        ..flat
+    };
+    client.update_staking_config(&mint, &token_info, &admin, treasury).await.unwrap();
+    let burn = instruction::emergency_unstake(&program_id, &tokens, &mint, &mint, &staker.pubkey(), 100, None);
+    assert_token_error(client.send(&[burn], &[&staker]).await, TokenError::InvalidPenaltyAccount);
+    client.emergency_unstake(&tokens, &mint, &staker, 100).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &tokens).await, 570);
+    assert_eq!(token_balance(&mut context.banks_client, &admin_tokens).await, 10);
+
+    // Unlocked positions leave for free.
+    set_time(&mut context, START + LockTier::Days30.duration()).await;
+    client.emergency_unstake(&tokens, &mint, &staker, 100).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &tokens).await, 670);
+    assert_eq!(token_balance(&mut context.banks_client, &admin_tokens).await, 10);
+}
+
+#[tokio::test]
+async fn test_liquid_staking_receipts() {
+    use solana_token::state::{find_liquid_pool_address, find_receipt_mint_address};
+
+    const START: i64 = 1_000_000;
+
+    let (alice, bob) = (Keypair::new(), Keypair::new());
+    let (alice_tokens, bob_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&[(&alice, alice_tokens, 1_000), (&bob, bob_tokens, 1_000)], 1_000, weekly(10), START).await;
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 1_000)
+        .await
+        .unwrap();
+    client.initialize_liquid_pool(&mint, &token_info, &admin).await.unwrap();
+    let (liquid_pool, _) = find_liquid_pool_address(&program_id, &mint);
+    let (receipt_mint, _) = find_receipt_mint_address(&program_id, &mint);
+    assert_eq!(client.fetch_liquid_pool(&liquid_pool).await.unwrap().receipt_mint, receipt_mint);
+    let receipt_mint_account = context.banks_client.get_account(receipt_mint).await.unwrap().unwrap();
+    assert_eq!(
+        Mint::unpack(&receipt_mint_account.data).unwrap().mint_authority,
+        COption::Some(liquid_pool)
+    );
+
+    let receipts = create_receipt_accounts(&mut context, &mut client, &mint, &[&alice, &bob]).await;
+    let (alice_receipts, bob_receipts) = (receipts[0], receipts[1]);
+
+    // The first deposit sets the exchange rate at one to one.
+    client.liquid_stake(&alice_tokens, &alice_receipts, &mint, &alice, 100).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &alice_receipts).await, 100);
+
+    // 100 rewards are staked again before the next deposit, doubling the
+    // rate.
+    set_time(&mut context, START + 10).await;
+    client.liquid_stake(&bob_tokens, &bob_receipts, &mint, &bob, 200).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &bob_receipts).await, 100);
+
+    // Deposits leave the position of the pool unlocked, so receipts can be
+    // redeemed right after someone else deposited. 100 more rewards make
+    // 200 receipts worth 500 before bob deposits again.
+    set_time(&mut context, START + 20).await;
+    client.liquid_stake(&bob_tokens, &bob_receipts, &mint, &bob, 250).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &bob_receipts).await, 200);
+    client.liquid_unstake(&alice_tokens, &alice_receipts, &mint, &alice, 100).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &alice_receipts).await, 0);
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 1_150);
+    assert_token_error(
+        client.liquid_unstake(&bob_tokens, &bob_receipts, &mint, &bob, 0).await,
+        TokenError::AmountTooSmall,
+    );
+    client.liquid_unstake(&bob_tokens, &bob_receipts, &mint, &bob, 200).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &bob_tokens).await, 1_050);
+}
+
+#[tokio::test]
+async fn test_liquid_receipts_redeemed_through_unbonding() {
+    use solana_token::state::{find_liquid_pool_address, find_stake_address, find_unbonding_address};
+
+    const START: i64 = 1_000_000;
+    const COOLDOWN: i64 = 3 * 24 * 60 * 60;
+
+    let (alice, bob) = (Keypair::new(), Keypair::new());
+    let (alice_tokens, bob_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let params = StakingParams {
+        unbonding_period: COOLDOWN,
+        ..weekly(0)
+    };
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        token_info,
+        ..
+    } = start_staking(&[(&alice, alice_tokens, 1_000), (&bob, bob_tokens, 1_000)], 0, params, START).await;
+    client.initialize_liquid_pool(&mint, &token_info, &admin).await.unwrap();
+    let (liquid_pool, _) = find_liquid_pool_address(&program_id, &mint);
+    let (stake_account, _) = find_stake_address(&program_id, &mint, &liquid_pool);
+    let receipts = create_receipt_accounts(&mut context, &mut client, &mint, &[&alice, &bob]).await;
+    let (alice_receipts, bob_receipts) = (receipts[0], receipts[1]);
+    client.liquid_stake(&alice_tokens, &alice_receipts, &mint, &alice, 300).await.unwrap();
+
+    assert_token_error(
+        client.liquid_unstake(&alice_tokens, &alice_receipts, &mint, &alice, 100).await,
+        TokenError::UnbondingRequired,
+    );
+    assert_eq!(client.request_liquid_unstake(&alice_receipts, &mint, &alice, 100).await.unwrap(), 0);
+    assert_eq!(token_balance(&mut context.banks_client, &alice_receipts).await, 200);
+    let (entry_address, _) = find_unbonding_address(&program_id, &stake_account, 0);
+    let entry = client.fetch_unbonding_entry(&entry_address).await.unwrap();
+    assert_eq!((entry.owner, entry.amount, entry.withdrawable_at), (alice.pubkey(), 100, START + COOLDOWN));
+
+    // Others keep depositing while the entry unbonds, at the same rate.
+    set_time(&mut context, START + 10).await;
+    client.liquid_stake(&bob_tokens, &bob_receipts, &mint, &bob, 100).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &bob_receipts).await, 100);
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!((position.amount, position.unbonding_amount), (300, 100));
+
+    // Only alice withdraws her entry, and only once it has unbonded.
+    assert_token_error(
+        client.withdraw_liquid_unbonded(&bob_tokens, &mint, &bob, 0).await,
+        TokenError::InvalidAuthority,
+    );
+    assert_token_error(
+        client.withdraw_liquid_unbonded(&alice_tokens, &mint, &alice, 0).await,
+        TokenError::UnbondingNotComplete,
+    );
+    set_time(&mut context, START + COOLDOWN).await;
+    client.withdraw_liquid_unbonded(&alice_tokens, &mint, &alice, 0).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 800);
+    assert!(client.transport_mut().get_account(entry_address).await.unwrap().is_none());
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!((position.amount, position.unbonding_amount, position.next_unbonding_id), (300, 0, 1));
+}
+
+/// Creates a receipt token account of the liquid pool of `mint` for each
+/// owner.
+async fn create_receipt_accounts(
+    context: &mut ProgramTestContext,
+    client: &mut TokenClient<BanksClient>,
+    mint: &Pubkey,
+    owners: &[&Keypair],
+) -> Vec<Pubkey> {
+    use solana_sdk::system_instruction;
+    use solana_token::state::find_receipt_mint_address;
+    use spl_token::state::Account as TokenAccount;
+
+    let (receipt_mint, _) = find_receipt_mint_address(client.program_id(), mint);
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mut receipts = Vec::new();
+    for owner in owners {
+        let account = Keypair::new();
+        let instructions = [
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account3(&spl_token::id(), &account.pubkey(), &receipt_mint, &owner.pubkey())
+                .unwrap(),
+        ];
+        client.send(&instructions, &[&account]).await.unwrap();
+        receipts.push(account.pubkey());
+    }
+    receipts
+}
+
+#[tokio::test]
+async fn test_compound_rewards() {
+    use solana_token::state::{find_reward_vault_address, find_stake_address, find_vault_address};
+
+    const START: i64 = 1_000_000;
+
+    let alice = Keypair::new();
+    let alice_tokens = Pubkey::new_unique();
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&[(&alice, alice_tokens, 100)], 1_000, weekly(4), START).await;
+    let (stake_account, _) = find_stake_address(&program_id, &mint, &alice.pubkey());
+    let (vault, _) = find_vault_address(&program_id, &mint);
+    let (reward_vault, _) = find_reward_vault_address(&program_id, &mint);
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 1_000)
+        .await
+        .unwrap();
+    client.initialize_stake(&mint, &alice, LockTier::Days30).await.unwrap();
+    client.stake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+    let unlock_time = client.fetch_stake_info(&stake_account).await.unwrap().unlock_time;
+
+    // Compounding moves the rewards into the vault and leaves the lock alone.
+    set_time(&mut context, START + 50).await;
+    client.compound_rewards(&mint, &alice).await.unwrap();
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!((position.amount, position.pending_rewards, position.unlock_time), (300, 0, unlock_time));
+    assert_eq!(token_balance(&mut context.banks_client, &vault).await, 300);
+    assert_eq!(token_balance(&mut context.banks_client, &reward_vault).await, 800);
+    // Retries need a new blockhash, or they are deduplicated.
+    set_time(&mut context, START + 50).await;
+    assert_token_error(client.compound_rewards(&mint, &alice).await, TokenError::NoRewardsToClaim);
+
+    // Like staking, compounding stops while paused and at the maximum.
+    set_time(&mut context, START + 100).await;
+    let paused = StakingParams {
+        paused: true,
+        ..weekly(4)
+    };
+    client.update_staking_config(&mint, &token_info, &admin, paused).await.unwrap();
+    assert_token_error(client.compound_rewards(&mint, &alice).await, TokenError::StakingPaused);
+    let capped = StakingParams {
+        max_total_stake: 400,
+        ..weekly(4)
+    };
+    client.update_staking_config(&mint, &token_info, &admin, capped).await.unwrap();
+    assert_token_error(
+        client.compound_rewards(&mint, &alice).await,
+        TokenError::MaxTotalStakeExceeded,
+    );
+    client.update_staking_config(&mint, &token_info, &admin, weekly(4)).await.unwrap();
+
+    // Cranks only compound positions that opted in.
+    set_time(&mut context, START + 100).await;
+    assert_token_error(
+        client.crank_compound(&mint, &alice.pubkey()).await,
+        TokenError::AutoCompoundDisabled,
+    );
+    client.set_auto_compound(&mint, &alice, true).await.unwrap();
+    set_time(&mut context, START + 100).await;
+    client.crank_compound(&mint, &alice.pubkey()).await.unwrap();
+    // The reward per token of 200 over 300 tokens rounds down.
+    assert_eq!(client.fetch_stake_info(&stake_account).await.unwrap().amount, 499);
+    assert_eq!(token_balance(&mut context.banks_client, &reward_vault).await, 601);
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 0);
+}
+
+#[tokio::test]
+async fn test_vote_escrow_locking() {
+    use solana_token::state::{find_vote_lock_address, VOTE_LOCK_WEEK as WEEK};
+
+    const START: i64 = 100 * WEEK;
+
+    let (alice, bob) = (Keypair::new(), Keypair::new());
+    let (alice_tokens, bob_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        token_info,
+        ..
+    } = start_staking(&[(&alice, alice_tokens, 7_560), (&bob, bob_tokens, 100)], 0, weekly(0), START).await;
+    client.initialize_locker(&mint, &token_info, &admin, 4 * WEEK).await.unwrap();
+
+    // Power starts at the share of the longest lock and decays to zero.
+    client
+        .create_vote_lock(&alice_tokens, &mint, &alice, 3_780, START + 2 * WEEK + 5)
+        .await
+        .unwrap();
+    assert_eq!(client.query_voting_power(&mint, &alice.pubkey(), START).await.unwrap(), 1_890);
+    assert_eq!(client.query_voting_power(&mint, &alice.pubkey(), START + 2 * WEEK).await.unwrap(), 0);
+    assert_token_error(
+        client.create_vote_lock(&bob_tokens, &mint, &bob, 100, START + 5 * WEEK).await,
+        TokenError::InvalidUnlockTime,
+    );
+
+    client.increase_vote_lock_amount(&alice_tokens, &mint, &alice, 3_780).await.unwrap();
+    assert_eq!(client.query_total_voting_power(&mint, START).await.unwrap(), 3_780);
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 0);
+
+    set_time(&mut context, START + WEEK).await;
+    client.extend_vote_lock(&mint, &alice, START + 4 * WEEK).await.unwrap();
+    assert_eq!(client.query_total_voting_power(&mint, START + WEEK).await.unwrap(), 5_670);
+    assert_eq!(client.query_total_voting_power(&mint, START + 3 * WEEK).await.unwrap(), 1_890);
+    assert_token_error(
+        client.withdraw_vote_lock(&alice_tokens, &mint, &alice).await,
+        TokenError::VoteLockNotExpired,
+    );
+
+    set_time(&mut context, START + 4 * WEEK).await;
+    assert_token_error(
+        client.increase_vote_lock_amount(&alice_tokens, &mint, &alice, 1).await,
+        TokenError::VoteLockExpired,
+    );
+    client.withdraw_vote_lock(&alice_tokens, &mint, &alice).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 7_560);
+    let (vote_lock, _) = find_vote_lock_address(&program_id, &mint, &alice.pubkey());
+    assert!(context.banks_client.get_account(vote_lock).await.unwrap().is_none());
+
+    // The history of the total survives the withdrawal.
+    assert_eq!(client.query_total_voting_power(&mint, START).await.unwrap(), 3_780);
+    assert_eq!(client.query_total_voting_power(&mint, START + WEEK).await.unwrap(), 5_670);
+    assert_eq!(client.query_total_voting_power(&mint, START + 4 * WEEK).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_operator_delegation() {
+    use solana_token::instruction;
+    use solana_token::state::{find_operator_address, find_stake_address};
+
+    const START: i64 = 1_000_000;
+
+    let (alice, carol, dave) = (Keypair::new(), Keypair::new(), Keypair::new());
+    let (alice_tokens, carol_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let holders = [(&alice, alice_tokens, 100), (&carol, carol_tokens, 0), (&dave, Pubkey::new_unique(), 0)];
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&holders, 2_000, weekly(4), START).await;
+    let (stake_account, _) = find_stake_address(&program_id, &mint, &alice.pubkey());
+    let (carol_operator, _) = find_operator_address(&program_id, &mint, &carol.pubkey());
+    let (dave_operator, _) = find_operator_address(&program_id, &mint, &dave.pubkey());
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 1_000)
+        .await
+        .unwrap();
+    client.register_operator(&mint, &carol, 1_000).await.unwrap();
+    client.register_operator(&mint, &dave, 2_000).await.unwrap();
+    client.initialize_stake(&mint, &alice, LockTier::Days30).await.unwrap();
+    client.stake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+    client.delegate_stake(&mint, &alice, &carol_operator).await.unwrap();
+
+    // The operator takes its commission out of the rewards of the position,
+    // passed on to it as the position is settled.
+    set_time(&mut context, START + 50).await;
+    let claim = instruction::claim_rewards(&program_id, &alice_tokens, &mint, &alice.pubkey(), Some(&dave_operator));
+    assert_token_error(client.send(&[claim], &[&alice]).await, TokenError::InvalidOperator);
+    client.claim_rewards(&alice_tokens, &mint, &alice).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 180);
+    assert_eq!(client.fetch_stake_info(&stake_account).await.unwrap().pending_commission, 0);
+    assert_eq!(client.fetch_operator(&carol_operator).await.unwrap().pending_commission, 20);
+    client.claim_commission(&carol_tokens, &mint, &carol).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &carol_tokens).await, 20);
+
+    // Redelegating waits for the unstake lock.
+    assert_token_error(
+        client.delegate_stake(&mint, &alice, &dave_operator).await,
+        TokenError::StakeLocked,
+    );
+    let unlock_time = client.fetch_stake_info(&stake_account).await.unwrap().unlock_time;
+    set_time(&mut context, unlock_time).await;
+    client.delegate_stake(&mint, &alice, &dave_operator).await.unwrap();
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert_eq!((position.operator, position.commission_bps), (dave_operator, 2_000));
+    assert_eq!(position.pending_rewards, 720);
+    let carol_state = client.fetch_operator(&carol_operator).await.unwrap();
+    assert_eq!((carol_state.delegations, carol_state.pending_commission), (0, 80));
+    assert_eq!(client.fetch_operator(&dave_operator).await.unwrap().delegations, 1);
+
+    // What was earned under the previous operator stays with it.
+    client.claim_commission(&carol_tokens, &mint, &carol).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &carol_tokens).await, 100);
+    assert_eq!(client.fetch_operator(&carol_operator).await.unwrap().collected_commission, 100);
+
+    // Undelegating waits for the unstake lock as well, which a top-up renews.
+    client.stake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 1_000)
+        .await
+        .unwrap();
+    assert_token_error(client.undelegate_stake(&mint, &alice).await, TokenError::StakeLocked);
+    let unlock_time = client.fetch_stake_info(&stake_account).await.unwrap().unlock_time;
+    set_time(&mut context, unlock_time).await;
+    client.undelegate_stake(&mint, &alice).await.unwrap();
+    let position = client.fetch_stake_info(&stake_account).await.unwrap();
+    assert!(!position.is_delegated());
+    assert_eq!((position.commission_bps, position.pending_commission), (0, 0));
+    let dave_state = client.fetch_operator(&dave_operator).await.unwrap();
+    assert_eq!((dave_state.delegations, dave_state.pending_commission), (0, 200));
+    set_time(&mut context, unlock_time + 1).await;
+    let undelegate = instruction::undelegate_stake(&program_id, &mint, &alice.pubkey(), &dave_operator);
+    assert_token_error(client.send(&[undelegate], &[&alice]).await, TokenError::InvalidOperator);
+}
+
+/// Creates a mint of another token on a running chain, with the payer as
+/// mint authority, and a token account holding `amount` for each `(owner,
+/// amount)`. Returns the mint and the token accounts.
+async fn create_mint(context: &mut ProgramTestContext, holders: &[(&Keypair, u64)]) -> (Pubkey, Vec<Pubkey>) {
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::Transaction;
+    use spl_token::state::Account as TokenAccount;
+
+    let payer = context.payer.insecure_clone();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mint = Keypair::new();
+    let mut instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(Mint::LEN),
+            Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint2(&spl_token::id(), &mint.pubkey(), &payer.pubkey(), None, 6).unwrap(),
+    ];
+    let token_accounts: Vec<Keypair> = holders.iter().map(|_| Keypair::new()).collect();
+    for ((owner, amount), token_account) in holders.iter().zip(&token_accounts) {
+        instructions.extend([
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &token_account.pubkey(),
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account3(
+                &spl_token::id(),
+                &token_account.pubkey(),
+                &mint.pubkey(),
+                &owner.pubkey(),
+            )
+            .unwrap(),
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &token_account.pubkey(),
+                &payer.pubkey(),
+                &[],
+                *amount,
+            )
+            .unwrap(),
+        ]);
+    }
+    let mut signers = vec![&payer, &mint];
+    signers.extend(&token_accounts);
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(&instructions, Some(&payer.pubkey()), &signers, blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+    (mint.pubkey(), token_accounts.iter().map(Keypair::pubkey).collect())
+}
+
+#[tokio::test]
+async fn test_farm_rewards_another_mint() {
+    use solana_token::state::find_farm_vault_address;
+
+    const START: i64 = 1_000_000;
+
+    let (alice, bob) = (Keypair::new(), Keypair::new());
+    let (alice_rewards, bob_rewards) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&[(&alice, alice_rewards, 0), (&bob, bob_rewards, 0)], 1_000, weekly(0), START).await;
+    let (lp_mint, lp_accounts) = create_mint(&mut context, &[(&alice, 100), (&bob, 300)]).await;
+    let (alice_lp, bob_lp) = (lp_accounts[0], lp_accounts[1]);
+
+    // The farm stakes the LP token and pays the program token.
+    let farm = client
+        .create_farm(&mint, &token_info, &lp_mint, &mint, &admin, 0, 4, START + 100, START + 300)
+        .await
+        .unwrap();
+    client.fund_farm(&farm, &admin_tokens, &admin, 1_000).await.unwrap();
+    let (farm_vault, _) = find_farm_vault_address(&program_id, &farm);
+
+    // Nothing is emitted before the start.
+    client.farm_stake(&farm, &alice_lp, &alice, 100).await.unwrap();
+    set_time(&mut context, START + 200).await;
+    assert_eq!(client.query_farm_rewards(&farm, &alice.pubkey()).await.unwrap(), 400);
+    client.farm_stake(&farm, &bob_lp, &bob, 300).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &farm_vault).await, 400);
+
+    // Nor after the end.
+    set_time(&mut context, START + 400).await;
+    assert_eq!(client.query_farm_rewards(&farm, &alice.pubkey()).await.unwrap(), 500);
+    assert_eq!(client.query_farm_rewards(&farm, &bob.pubkey()).await.unwrap(), 300);
+    assert_token_error(client.farm_stake(&farm, &bob_lp, &bob, 1).await, TokenError::FarmEnded);
+
+    client.claim_farm_rewards(&farm, &alice_rewards, &alice).await.unwrap();
+    client.farm_unstake(&farm, &bob_lp, &bob, 300).await.unwrap();
+    client.claim_farm_rewards(&farm, &bob_rewards, &bob).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &alice_rewards).await, 500);
+    assert_eq!(token_balance(&mut context.banks_client, &bob_rewards).await, 300);
+    assert_eq!(token_balance(&mut context.banks_client, &bob_lp).await, 300);
+    let farm_state = client.fetch_farm(&farm).await.unwrap();
+    assert_eq!((farm_state.total_staked, farm_state.undistributed_rewards), (100, 200));
+
+    // Only the admin extends the farm, and only into the future.
+    assert_token_error(
+        client.update_farm(&farm, &token_info, &alice, 2, START + 500).await,
+        TokenError::InvalidAuthority,
+    );
+    assert!(client.update_farm(&farm, &token_info, &admin, 2, START + 400).await.is_err());
+    client.update_farm(&farm, &token_info, &admin, 2, START + 500).await.unwrap();
+
+    // The new rate applies from the update on, out of what is left.
+    set_time(&mut context, START + 450).await;
+    assert_eq!(client.query_farm_rewards(&farm, &alice.pubkey()).await.unwrap(), 100);
+    set_time(&mut context, START + 600).await;
+    assert_eq!(client.query_farm_rewards(&farm, &alice.pubkey()).await.unwrap(), 200);
+}
+
+#[tokio::test]
+async fn test_emission_schedule_halves_within_community_rewards() {
+    use solana_token::state::{find_emission_schedule_address, find_staking_pool_address, EpochEmission};
+
+    const START: i64 = 1_000_000;
+
+    let alice = Keypair::new();
+    let alice_tokens = Pubkey::new_unique();
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&[(&alice, alice_tokens, 100)], 39_600, weekly(5), START).await;
+    let (staking_pool, _) = find_staking_pool_address(&program_id, &mint);
+
+    // Leave 39_600 of the community rewards, and fund 36_000 of them.
+    let community_rewards = client.fetch_token_info(&token_info).await.unwrap().community_rewards;
+    client
+        .create_vesting(
+            &Keypair::new(),
+            &token_info,
+            &admin,
+            &Pubkey::new_unique(),
+            AllocationType::Community,
+            community_rewards - 39_600,
+            START,
+            START,
+            START + WEEK,
+        )
+        .await
+        .unwrap();
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 36_000)
+        .await
+        .unwrap();
+    client.initialize_stake(&mint, &alice, LockTier::Days30).await.unwrap();
+    client.stake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+
+    // Only the admin puts the pool on a schedule, of epochs an hour or
+    // longer.
+    assert!(client
+        .initialize_emission_schedule(&mint, &token_info, &alice, HOUR, 28_800, 5_000)
+        .await
+        .is_err());
+    assert!(client
+        .initialize_emission_schedule(&mint, &token_info, &admin, HOUR - 1, 28_800, 5_000)
+        .await
+        .is_err());
+    client
+        .initialize_emission_schedule(&mint, &token_info, &admin, HOUR, 28_800, 5_000)
+        .await
+        .unwrap();
+    let schedule = client
+        .fetch_emission_schedule(&find_emission_schedule_address(&program_id, &mint).0)
+        .await
+        .unwrap();
+    assert_eq!((schedule.epoch_duration, schedule.decay_bps), (HOUR, 5_000));
+    assert_eq!(
+        client.query_emission_schedule(&mint).await.unwrap(),
+        EpochEmission { epoch: 0, start_time: START, end_time: START + HOUR, emission: 28_800 }
+    );
+    assert_eq!(client.fetch_staking_pool(&staking_pool).await.unwrap().reward_rate, 8);
+
+    // Emission stops at the end of the epoch until the next one starts.
+    set_time(&mut context, START + HOUR / 2).await;
+    assert_token_error(client.advance_epoch(&mint, &token_info).await, TokenError::EpochNotEnded);
+    set_time(&mut context, START + 3 * HOUR / 2).await;
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 28_800);
+
+    // Halving 28_800 is capped by the 3_600 left to fund and the 7_200
+    // funded and not emitted, and the late epoch still starts at the last
+    // end.
+    client.advance_epoch(&mint, &token_info).await.unwrap();
+    assert_eq!(
+        client.query_emission_schedule(&mint).await.unwrap(),
+        EpochEmission { epoch: 1, start_time: START + HOUR, end_time: START + 2 * HOUR, emission: 10_800 }
+    );
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 34_200);
+
+    // The schedule keeps setting the rate over the staking config.
+    client
+        .update_staking_config(&mint, &token_info, &admin, weekly(50))
+        .await
+        .unwrap();
+    assert_eq!(client.fetch_staking_pool(&staking_pool).await.unwrap().reward_rate, 3);
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 3_600)
+        .await
+        .unwrap();
+
+    // With every community reward emitted, the next epochs emit nothing.
+    set_time(&mut context, START + 5 * HOUR / 2).await;
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 39_600);
+    client.advance_epoch(&mint, &token_info).await.unwrap();
+    assert_eq!(client.query_emission_schedule(&mint).await.unwrap().emission, 0);
+    client.claim_rewards(&alice_tokens, &mint, &alice).await.unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &alice_tokens).await, 39_600);
+
+    // Once the emission is zero, a crank skips every epoch it missed.
+    set_time(&mut context, START + 1_000 * HOUR + HOUR / 2).await;
+    client.advance_epoch(&mint, &token_info).await.unwrap();
+    assert_eq!(
+        client.query_emission_schedule(&mint).await.unwrap(),
+        EpochEmission { epoch: 1_000, start_time: START + 1_000 * HOUR, end_time: START + 1_001 * HOUR, emission: 0 }
+    );
+}
+
+#[tokio::test]
+async fn test_late_epoch_crank_goes_through_missed_epochs() {
+    use solana_token::state::{find_staking_pool_address, EpochEmission};
+
+    const START: i64 = 1_000_000;
+
+    let alice = Keypair::new();
+    let alice_tokens = Pubkey::new_unique();
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&[(&alice, alice_tokens, 100)], 72_000, weekly(5), START).await;
+    let (staking_pool, _) = find_staking_pool_address(&program_id, &mint);
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 72_000)
+        .await
+        .unwrap();
+    client.initialize_stake(&mint, &alice, LockTier::Days30).await.unwrap();
+    client.stake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+    client
+        .initialize_emission_schedule(&mint, &token_info, &admin, HOUR, 28_800, 5_000)
+        .await
+        .unwrap();
+
+    // Three epochs late, the crank halves the emission for each of them.
+    set_time(&mut context, START + 7 * HOUR / 2).await;
+    client.advance_epoch(&mint, &token_info).await.unwrap();
+    assert_eq!(
+        client.query_emission_schedule(&mint).await.unwrap(),
+        EpochEmission { epoch: 3, start_time: START + 3 * HOUR, end_time: START + 4 * HOUR, emission: 3_600 }
+    );
+    assert_eq!(client.fetch_staking_pool(&staking_pool).await.unwrap().reward_rate, 1);
+    // 28_800, 14_400 and 7_200 for the epochs that ended, and half of
+    // 3_600.
+    assert_eq!(client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(), 52_200);
+}
+
+#[tokio::test]
+async fn test_crank_many_epochs_late_takes_several_calls() {
+    use solana_token::state::{find_staking_pool_address, EpochEmission, MAX_EPOCHS_PER_CRANK};
+
+    const START: i64 = 1_000_000;
+
+    let alice = Keypair::new();
+    let alice_tokens = Pubkey::new_unique();
+    let Staking {
+        mut context,
+        mut client,
+        program_id,
+        mint,
+        admin,
+        admin_tokens,
+        token_info,
+    } = start_staking(&[(&alice, alice_tokens, 100)], 1_000_000, weekly(5), START).await;
+    let (staking_pool, _) = find_staking_pool_address(&program_id, &mint);
+    client
+        .fund_rewards(&mint, &token_info, &admin_tokens, &admin, 1_000_000)
+        .await
+        .unwrap();
+    client.initialize_stake(&mint, &alice, LockTier::Days30).await.unwrap();
+    client.stake(&alice_tokens, &mint, &alice, 100).await.unwrap();
+    // A constant emission of 3_600 an epoch.
+    client
+        .initialize_emission_schedule(&mint, &token_info, &admin, HOUR, 3_600, 0)
+        .await
+        .unwrap();
+
+    // A crank more epochs late than one call goes through stops at the
+    // cap, with the pool emitting up to the end of the last epoch it
+    // reached.
+    let late = MAX_EPOCHS_PER_CRANK as i64 + 8;
+    set_time(&mut context, START + late * HOUR + HOUR / 2).await;
+    client.advance_epoch(&mint, &token_info).await.unwrap();
+    let cap = MAX_EPOCHS_PER_CRANK as i64;
+    assert_eq!(
+        client.query_emission_schedule(&mint).await.unwrap(),
+        EpochEmission {
+            epoch: MAX_EPOCHS_PER_CRANK,
+            start_time: START + cap * HOUR,
+            end_time: START + (cap + 1) * HOUR,
+            emission: 3_600,
+        }
+    );
+    assert_eq!(client.fetch_staking_pool(&staking_pool).await.unwrap().emission_end, START + (cap + 1) * HOUR);
+    assert_eq!(
+        client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(),
+        3_600 * (cap as u64 + 1)
+    );
+
+    // The next crank goes on from there to the current epoch, without
+    // losing the emission of the epochs in between. Retries need a new
+    // blockhash, or they are deduplicated.
+    set_time(&mut context, START + late * HOUR + HOUR / 2).await;
+    client.advance_epoch(&mint, &token_info).await.unwrap();
+    assert_eq!(
+        client.query_emission_schedule(&mint).await.unwrap(),
+        EpochEmission {
+            epoch: late as u64,
+            start_time: START + late * HOUR,
+            end_time: START + (late + 1) * HOUR,
+            emission: 3_600,
+        }
+    );
+    assert_eq!(
+        client.query_pending_rewards(&mint, &alice.pubkey()).await.unwrap(),
+        3_600 * late as u64 + 1_800
+    );
+    set_time(&mut context, START + late * HOUR + HOUR / 2).await;
+    assert_token_error(client.advance_epoch(&mint, &token_info).await, TokenError::EpochNotEnded);
+}
+
+#[tokio::test]
+async fn test_vesting_cliff() {
+    const START: i64 = 1_000_000;
+
+    let bob = Keypair::new();
+    let bob_tokens = Pubkey::new_unique();
+    let Staking {
+        mut context,
+        mut client,
+        admin,
+        admin_tokens,
+        token_info,
+        ..
+    } = start_staking(&[(&bob, bob_tokens, 0)], 1_000, weekly(0), START).await;
+
+    // The cliff must fall between the start and the end.
+    for (cliff_time, end_time) in [(START - 1, START + 400), (START + 401, START + 400)] {
+        assert!(client
+            .create_vesting(
+                &Keypair::new(),
+                &token_info,
+                &admin,
+                &bob.pubkey(),
+                AllocationType::Team,
+                1_000,
+                START,
+                cliff_time,
+                end_time,
+            )
+            .await
+            .is_err());
+    }
+    let vesting = Keypair::new();
+    client
+        .create_vesting(
+            &vesting,
+            &token_info,
+            &admin,
+            &bob.pubkey(),
+            AllocationType::Team,
+            1_000,
+            START,
+            START + 100,
+            START + 400,
+        )
+        .await
+        .unwrap();
+    let vesting = vesting.pubkey();
+
+    // Nothing is released before the cliff.
+    set_time(&mut context, START + 99).await;
+    assert_eq!(client.query_vested_amount(&vesting).await.unwrap(), 0);
+    assert_token_error(
+        client.release_vested_tokens(&vesting, &bob_tokens, &admin_tokens, &admin).await,
+        TokenError::VestingCliffNotReached,
+    );
+
+    // The quarter vested by the cliff unlocks at once, and the rest keeps
+    // vesting linearly.
+    set_time(&mut context, START + 100).await;
+    client
+        .release_vested_tokens(&vesting, &bob_tokens, &admin_tokens, &admin)
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &bob_tokens).await, 250);
+    set_time(&mut context, START + 300).await;
+    assert_eq!(client.query_releasable_amount(&vesting).await.unwrap(), 500);
+    client
+        .release_vested_tokens(&vesting, &bob_tokens, &admin_tokens, &admin)
+        .await
+        .unwrap();
+    assert_eq!(token_balance(&mut context.banks_client, &bob_tokens).await, 750);
+    assert_eq!(client.fetch_vesting_schedule(&vesting).await.unwrap().released_amount, 750);
+}
@@ -0,0 +1,18 @@
+//! Writes the program IDL as JSON, to the given path or to stdout.
+
+use std::fs;
+use std::process;
+
+fn main() {
+    let idl = serde_json::to_string_pretty(&solana_token::idl::generate())
+        .expect("IDL is valid JSON");
+    match std::env::args().nth(1) {
+        Some(path) => {
+            if let Err(err) = fs::write(&path, idl + "\n") {
+                eprintln!("failed to write {}: {}", path, err);
+                process::exit(1);
+            }
+        }
+        None => println!("{}", idl),
+    }
+}
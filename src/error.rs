@@ -1,7 +1,8 @@
 use solana_program::program_error::ProgramError;
+use num_derive::FromPrimitive;
 use thiserror::Error;
 
-#[derive(Error, Debug, Copy, Clone)]
+#[derive(Error, Debug, Copy, Clone, FromPrimitive)]
 pub enum TokenError {
     #[error("Invalid instruction")]
     InvalidInstruction,
@@ -25,6 +26,52 @@ pub enum TokenError {
     VestingNotStarted,
     #[error("No tokens to release")]
     NoTokensToRelease,
+    #[error("Account belongs to a different account type")]
+    InvalidAccountType,
+    #[error("Unsupported account layout version")]
+    UnsupportedAccountVersion,
+    #[error("Account must be writable")]
+    AccountNotWritable,
+    #[error("No rewards to claim")]
+    NoRewardsToClaim,
+    #[error("Stake is below the minimum")]
+    StakeBelowMinimum,
+    #[error("Stake is still locked")]
+    StakeLocked,
+    #[error("Maximum total stake exceeded")]
+    MaxTotalStakeExceeded,
+    #[error("Staking is paused")]
+    StakingPaused,
+    #[error("Lock tier must be longer than the current one")]
+    LockNotExtended,
+    #[error("Unstaking goes through an unbonding request")]
+    UnbondingRequired,
+    #[error("Unbonding period has not ended")]
+    UnbondingNotComplete,
+    #[error("Penalty account does not match the staking config")]
+    InvalidPenaltyAccount,
+    #[error("Amount is too small to exchange")]
+    AmountTooSmall,
+    #[error("Stake position has not opted into auto-compound")]
+    AutoCompoundDisabled,
+    #[error("Unlock time must be later than the current one and within the longest lock")]
+    InvalidUnlockTime,
+    #[error("Vote lock has expired")]
+    VoteLockExpired,
+    #[error("Vote lock has not expired")]
+    VoteLockNotExpired,
+    #[error("Voting power at that time is no longer recorded")]
+    VotingPowerUnavailable,
+    #[error("Operator does not match the stake position")]
+    InvalidOperator,
+    #[error("Farm has ended")]
+    FarmEnded,
+    #[error("Emission epoch has not ended")]
+    EpochNotEnded,
+    #[error("Vesting cliff not reached")]
+    VestingCliffNotReached,
+    #[error("Liquid pool has receipts outstanding but no stake")]
+    EmptyLiquidPool,
 }
 
 impl From<TokenError> for ProgramError {
@@ -0,0 +1,420 @@
+//! Structured events logged for every state change.
+//!
+//! Each event is logged with `sol_log_data` as a single field: one
+//! [`EVENT_VERSION`] byte followed by the Borsh encoding of a
+//! [`TokenEvent`]. It shows up in the transaction logs as a
+//! `Program data: <base64>` line, which [`parse_logs`] turns back into
+//! events.
+
+use base64::Engine;
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::log::sol_log_data;
+use solana_program::pubkey::Pubkey;
+use thiserror::Error;
+
+use crate::instruction::AllocationType;
+use crate::state::{LockTier, PenaltyDestination, StakingParams};
+
+/// Version of the event encoding, logged before every event.
+pub const EVENT_VERSION: u8 = 2;
+
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub enum TokenEvent {
+    TokenInfoInitialized {
+        token_info: Pubkey,
+        mint: Pubkey,
+        mint_authority: Pubkey,
+        total_supply: u64,
+        timestamp: i64,
+    },
+    VestingCreated {
+        vesting_account: Pubkey,
+        beneficiary: Pubkey,
+        allocation_type: AllocationType,
+        amount: u64,
+        start_time: i64,
+        cliff_time: i64,
+        end_time: i64,
+        timestamp: i64,
+    },
+    VestingReleased {
+        vesting_account: Pubkey,
+        beneficiary: Pubkey,
+        amount: u64,
+        /// Total released by the schedule so far, this release included.
+        released_amount: u64,
+        timestamp: i64,
+    },
+    Staked {
+        stake_account: Pubkey,
+        staker: Pubkey,
+        amount: u64,
+        /// Amount staked in the account after this stake.
+        staked_amount: u64,
+        timestamp: i64,
+    },
+    Unstaked {
+        stake_account: Pubkey,
+        staker: Pubkey,
+        amount: u64,
+        /// Amount left staked in the account.
+        staked_amount: u64,
+        timestamp: i64,
+    },
+    Minted {
+        mint: Pubkey,
+        account: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    Burned {
+        mint: Pubkey,
+        account: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    Frozen {
+        mint: Pubkey,
+        account: Pubkey,
+        timestamp: i64,
+    },
+    Thawed {
+        mint: Pubkey,
+        account: Pubkey,
+        timestamp: i64,
+    },
+    AuthorityChanged {
+        account: Pubkey,
+        authority_type: u8,
+        new_authority: Option<Pubkey>,
+        timestamp: i64,
+    },
+    StakeInitialized {
+        stake_account: Pubkey,
+        owner: Pubkey,
+        mint: Pubkey,
+        lock_tier: LockTier,
+        timestamp: i64,
+    },
+    StakingPoolInitialized {
+        staking_pool: Pubkey,
+        mint: Pubkey,
+        reward_rate: u64,
+        /// Stake already in the vault when the pool was created.
+        total_staked: u64,
+        timestamp: i64,
+    },
+    RewardsFunded {
+        staking_pool: Pubkey,
+        amount: u64,
+        /// Funded rewards not emitted yet, this funding included.
+        undistributed_rewards: u64,
+        timestamp: i64,
+    },
+    RewardsClaimed {
+        stake_account: Pubkey,
+        staker: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    /// Logged when a staking config is created as well as when it changes.
+    StakingConfigUpdated {
+        staking_config: Pubkey,
+        params: StakingParams,
+        timestamp: i64,
+    },
+    LockExtended {
+        stake_account: Pubkey,
+        staker: Pubkey,
+        lock_tier: LockTier,
+        unlock_time: i64,
+        weight: u64,
+        timestamp: i64,
+    },
+    UnstakeRequested {
+        stake_account: Pubkey,
+        unbonding_entry: Pubkey,
+        staker: Pubkey,
+        amount: u64,
+        staked_amount: u64,
+        withdrawable_at: i64,
+        timestamp: i64,
+    },
+    UnbondedWithdrawn {
+        stake_account: Pubkey,
+        unbonding_entry: Pubkey,
+        staker: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    /// Logged when unbonding tokens go back into their position.
+    UnbondingCancelled {
+        stake_account: Pubkey,
+        unbonding_entry: Pubkey,
+        staker: Pubkey,
+        amount: u64,
+        staked_amount: u64,
+        timestamp: i64,
+    },
+    EmergencyUnstaked {
+        stake_account: Pubkey,
+        staker: Pubkey,
+        /// Amount taken out of the position, the penalty included.
+        amount: u64,
+        penalty: u64,
+        penalty_destination: PenaltyDestination,
+        staked_amount: u64,
+        timestamp: i64,
+    },
+    LiquidPoolInitialized {
+        liquid_pool: Pubkey,
+        mint: Pubkey,
+        receipt_mint: Pubkey,
+        timestamp: i64,
+    },
+    /// `staked_amount` over `receipt_supply`, both after the deposit, is the
+    /// exchange rate of the pool.
+    LiquidStaked {
+        liquid_pool: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        receipt_amount: u64,
+        staked_amount: u64,
+        receipt_supply: u64,
+        timestamp: i64,
+    },
+    LiquidUnstaked {
+        liquid_pool: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        receipt_amount: u64,
+        staked_amount: u64,
+        receipt_supply: u64,
+        timestamp: i64,
+    },
+    RewardsCompounded {
+        stake_account: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        staked_amount: u64,
+        timestamp: i64,
+    },
+    AutoCompoundSet {
+        stake_account: Pubkey,
+        staker: Pubkey,
+        enabled: bool,
+        timestamp: i64,
+    },
+    LockerInitialized {
+        locker: Pubkey,
+        mint: Pubkey,
+        max_lock_duration: i64,
+        timestamp: i64,
+    },
+    /// `voting_power` and `total_voting_power` are as of `timestamp`, after
+    /// the change.
+    VoteLockCreated {
+        vote_lock: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        unlock_time: i64,
+        voting_power: u64,
+        total_voting_power: u64,
+        timestamp: i64,
+    },
+    VoteLockIncreased {
+        vote_lock: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        locked_amount: u64,
+        voting_power: u64,
+        total_voting_power: u64,
+        timestamp: i64,
+    },
+    VoteLockExtended {
+        vote_lock: Pubkey,
+        owner: Pubkey,
+        unlock_time: i64,
+        voting_power: u64,
+        total_voting_power: u64,
+        timestamp: i64,
+    },
+    VoteLockWithdrawn {
+        vote_lock: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    OperatorRegistered {
+        operator: Pubkey,
+        mint: Pubkey,
+        authority: Pubkey,
+        commission_bps: u16,
+        timestamp: i64,
+    },
+    /// `previous_operator` is the default pubkey for a first delegation.
+    StakeDelegated {
+        stake_account: Pubkey,
+        staker: Pubkey,
+        operator: Pubkey,
+        previous_operator: Pubkey,
+        staked_amount: u64,
+        timestamp: i64,
+    },
+    CommissionClaimed {
+        operator: Pubkey,
+        authority: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    FarmCreated {
+        farm: Pubkey,
+        id: u64,
+        staked_mint: Pubkey,
+        reward_mint: Pubkey,
+        emission_rate: u64,
+        start_time: i64,
+        end_time: i64,
+        timestamp: i64,
+    },
+    FarmFunded {
+        farm: Pubkey,
+        funder: Pubkey,
+        amount: u64,
+        undistributed_rewards: u64,
+        timestamp: i64,
+    },
+    FarmStaked {
+        farm_position: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        staked_amount: u64,
+        total_staked: u64,
+        timestamp: i64,
+    },
+    FarmUnstaked {
+        farm_position: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        staked_amount: u64,
+        total_staked: u64,
+        timestamp: i64,
+    },
+    FarmRewardsClaimed {
+        farm_position: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    },
+    EmissionScheduleInitialized {
+        emission_schedule: Pubkey,
+        epoch_duration: i64,
+        initial_emission: u64,
+        decay_bps: u16,
+        epoch_emission: u64,
+        timestamp: i64,
+    },
+    EpochAdvanced {
+        emission_schedule: Pubkey,
+        epoch: u64,
+        start_time: i64,
+        end_time: i64,
+        emission: u64,
+        reward_rate: u64,
+        timestamp: i64,
+    },
+    StakeUndelegated {
+        stake_account: Pubkey,
+        staker: Pubkey,
+        operator: Pubkey,
+        staked_amount: u64,
+        timestamp: i64,
+    },
+    LiquidUnstakeRequested {
+        liquid_pool: Pubkey,
+        unbonding_entry: Pubkey,
+        owner: Pubkey,
+        amount: u64,
+        receipt_amount: u64,
+        staked_amount: u64,
+        receipt_supply: u64,
+        withdrawable_at: i64,
+        timestamp: i64,
+    },
+    FarmUpdated {
+        farm: Pubkey,
+        emission_rate: u64,
+        end_time: i64,
+        timestamp: i64,
+    },
+}
+
+impl TokenEvent {
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = vec![EVENT_VERSION];
+        self.serialize(&mut data)
+            .expect("serializing into a Vec cannot fail");
+        data
+    }
+
+    pub fn unpack(data: &[u8]) -> Result<Self, EventError> {
+        match data.split_first() {
+            Some((&EVENT_VERSION, event)) => {
+                Self::try_from_slice(event).map_err(|_| EventError::InvalidEvent)
+            }
+            Some((&version, _)) => Err(EventError::UnsupportedVersion(version)),
+            None => Err(EventError::InvalidEvent),
+        }
+    }
+
+    /// Logs the event.
+    pub fn emit(&self) {
+        sol_log_data(&[&self.pack()]);
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum EventError {
+    #[error("event data is not valid base64")]
+    InvalidBase64,
+    #[error("unsupported event version {0}")]
+    UnsupportedVersion(u8),
+    #[error("invalid event data")]
+    InvalidEvent,
+}
+
+/// Decodes the events logged by `program_id` from the log messages of a
+/// transaction, in order.
+///
+/// Calls are tracked through the `invoke` and `success`/`failed` lines, so
+/// data logged by other programs, including ones invoked from this program,
+/// is skipped.
+pub fn parse_logs<S: AsRef<str>>(program_id: &Pubkey, logs: &[S]) -> Result<Vec<TokenEvent>, EventError> {
+    let program_id = program_id.to_string();
+    let mut call_stack: Vec<&str> = Vec::new();
+    let mut events = Vec::new();
+
+    for line in logs {
+        let line = line.as_ref();
+        if let Some(data) = line.strip_prefix("Program data: ") {
+            if call_stack.last() != Some(&program_id.as_str()) {
+                continue;
+            }
+            let data = base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|_| EventError::InvalidBase64)?;
+            events.push(TokenEvent::unpack(&data)?);
+        } else if let Some(rest) = line.strip_prefix("Program ") {
+            let mut words = rest.split(' ');
+            let program = words.next().unwrap_or_default();
+            match words.next() {
+                Some("invoke") => call_stack.push(program),
+                Some("success") | Some("failed:") => {
+                    call_stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(events)
+}
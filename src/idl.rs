@@ -0,0 +1,775 @@
+//! JSON IDL describing the program's instructions, account layouts and
+//! errors.
+//!
+//! The output follows the layout used by Shank, so existing Solana client
+//! generators can consume it. Argument and field types come from the Borsh
+//! schemas derived on the instruction and state types; only the account
+//! lists are spelled out here.
+
+use borsh::schema::{BorshSchemaContainer, Declaration, Definition, Fields};
+use borsh::BorshSchema;
+use num_traits::FromPrimitive;
+use serde_json::{json, Value};
+use solana_program::program_pack::Pack;
+
+use crate::error::TokenError;
+use crate::instruction::TokenInstruction;
+use crate::state::{
+    EmissionSchedule, Farm, FarmPosition, LiquidPool, Locker, Operator, StakeInfo, StakingConfig, StakingPool,
+    UnbondingEntry, VestingSchedule, VoteLock,
+};
+use crate::token_info::TokenInfo;
+
+/// `(name, writable, signer)` of one account passed to an instruction.
+pub type AccountSpec = (&'static str, bool, bool);
+
+/// Accounts expected by every instruction, indexed by instruction tag.
+/// Accounts passed only in some cases follow in [`OPTIONAL_ACCOUNTS`].
+pub const INSTRUCTION_ACCOUNTS: &[&[AccountSpec]] = &[
+    // InitializeMint
+    &[
+        ("mint", true, false),
+        ("mintAuthority", false, false),
+        ("freezeAuthority", false, false),
+        ("rent", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // InitializeAccount
+    &[
+        ("account", true, false),
+        ("mint", false, false),
+        ("owner", false, false),
+        ("rent", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // Transfer
+    &[
+        ("source", true, false),
+        ("destination", true, false),
+        ("authority", false, true),
+        ("tokenProgram", false, false),
+    ],
+    // Burn
+    &[
+        ("account", true, false),
+        ("mint", true, false),
+        ("authority", false, true),
+        ("tokenProgram", false, false),
+    ],
+    // MintTo
+    &[
+        ("mint", true, false),
+        ("account", true, false),
+        ("mintAuthority", false, true),
+        ("tokenProgram", false, false),
+    ],
+    // Freeze
+    &[
+        ("account", true, false),
+        ("mint", false, false),
+        ("freezeAuthority", false, true),
+        ("tokenProgram", false, false),
+    ],
+    // Thaw
+    &[
+        ("account", true, false),
+        ("mint", false, false),
+        ("freezeAuthority", false, true),
+        ("tokenProgram", false, false),
+    ],
+    // SetAuthority
+    &[
+        ("accountOrMint", true, false),
+        ("currentAuthority", false, true),
+        ("tokenProgram", false, false),
+    ],
+    // Stake
+    &[
+        ("stakeAccount", true, false),
+        ("tokenAccount", true, false),
+        ("vault", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("mint", false, false),
+        ("staker", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // Unstake
+    &[
+        ("stakeAccount", true, false),
+        ("tokenAccount", true, false),
+        ("vault", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("mint", false, false),
+        ("staker", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // UpgradeProgram
+    &[
+        ("program", true, false),
+        ("buffer", true, false),
+        ("spill", true, false),
+        ("upgradeAuthority", false, true),
+        ("rent", false, false),
+        ("bpfLoaderUpgradeable", false, false),
+    ],
+    // InitializeTokenInfo
+    &[
+        ("tokenInfo", true, false),
+        ("mintAuthority", false, true),
+        ("mint", false, false),
+    ],
+    // CreateVestingSchedule
+    &[
+        ("vestingAccount", true, false),
+        ("tokenInfo", true, false),
+        ("mintAuthority", false, true),
+    ],
+    // ReleaseVestedTokens
+    &[
+        ("vestingAccount", true, false),
+        ("beneficiaryTokenAccount", true, false),
+        ("sourceTokenAccount", true, false),
+        ("sourceAuthority", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // MigrateAccount
+    &[
+        ("account", true, false),
+        ("tokenInfo", false, false),
+        ("admin", true, true),
+        ("systemProgram", false, false),
+    ],
+    // QueryVestedAmount
+    &[("vestingAccount", false, false), ("clock", false, false)],
+    // QueryReleasableAmount
+    &[("vestingAccount", false, false), ("clock", false, false)],
+    // QueryStakeInfo
+    &[("stakeAccount", false, false)],
+    // QueryAllocationRemaining
+    &[("tokenInfo", false, false)],
+    // InitializeVault
+    &[
+        ("vault", true, false),
+        ("mint", false, false),
+        ("payer", true, true),
+        ("systemProgram", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // InitializeStake
+    &[
+        ("stakeAccount", true, false),
+        ("mint", false, false),
+        ("owner", true, true),
+        ("systemProgram", false, false),
+    ],
+    // InitializeStakingPool
+    &[
+        ("stakingPool", true, false),
+        ("rewardVault", true, false),
+        ("vault", false, false),
+        ("mint", false, false),
+        ("tokenInfo", false, false),
+        ("admin", true, true),
+        ("systemProgram", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // FundRewards
+    &[
+        ("stakingPool", true, false),
+        ("rewardVault", true, false),
+        ("tokenInfo", true, false),
+        ("sourceTokenAccount", true, false),
+        ("mint", false, false),
+        ("admin", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // ClaimRewards
+    &[
+        ("stakeAccount", true, false),
+        ("stakingPool", true, false),
+        ("rewardVault", true, false),
+        ("destination", true, false),
+        ("mint", false, false),
+        ("staker", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // QueryPendingRewards
+    &[
+        ("stakeAccount", false, false),
+        ("stakingPool", false, false),
+        ("clock", false, false),
+    ],
+    // InitializeStakingConfig
+    &[
+        ("stakingConfig", true, false),
+        ("stakingPool", true, false),
+        ("mint", false, false),
+        ("tokenInfo", false, false),
+        ("admin", true, true),
+        ("clock", false, false),
+        ("systemProgram", false, false),
+    ],
+    // UpdateStakingConfig
+    &[
+        ("stakingConfig", true, false),
+        ("stakingPool", true, false),
+        ("mint", false, false),
+        ("tokenInfo", false, false),
+        ("admin", false, true),
+        ("clock", false, false),
+    ],
+    // ExtendLock
+    &[
+        ("stakeAccount", true, false),
+        ("stakingPool", true, false),
+        ("mint", false, false),
+        ("staker", false, true),
+        ("clock", false, false),
+    ],
+    // RequestUnstake
+    &[
+        ("stakeAccount", true, false),
+        ("unbondingEntry", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("mint", false, false),
+        ("staker", true, true),
+        ("clock", false, false),
+        ("systemProgram", false, false),
+    ],
+    // WithdrawUnbonded
+    &[
+        ("stakeAccount", true, false),
+        ("unbondingEntry", true, false),
+        ("tokenAccount", true, false),
+        ("vault", true, false),
+        ("stakingConfig", false, false),
+        ("mint", false, false),
+        ("staker", true, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // CancelUnbonding
+    &[
+        ("stakeAccount", true, false),
+        ("unbondingEntry", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("mint", false, false),
+        ("staker", true, true),
+        ("clock", false, false),
+    ],
+    // EmergencyUnstake
+    &[
+        ("stakeAccount", true, false),
+        ("tokenAccount", true, false),
+        ("vault", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("mint", true, false),
+        ("penaltyAccount", true, false),
+        ("staker", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // InitializeLiquidPool
+    &[
+        ("liquidPool", true, false),
+        ("receiptMint", true, false),
+        ("stakeAccount", true, false),
+        ("mint", false, false),
+        ("tokenInfo", false, false),
+        ("admin", true, true),
+        ("systemProgram", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // LiquidStake
+    &[
+        ("liquidPool", false, false),
+        ("stakeAccount", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("vault", true, false),
+        ("rewardVault", true, false),
+        ("mint", false, false),
+        ("receiptMint", true, false),
+        ("tokenAccount", true, false),
+        ("receiptAccount", true, false),
+        ("owner", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // LiquidUnstake
+    &[
+        ("liquidPool", false, false),
+        ("stakeAccount", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("vault", true, false),
+        ("rewardVault", true, false),
+        ("mint", false, false),
+        ("receiptMint", true, false),
+        ("tokenAccount", true, false),
+        ("receiptAccount", true, false),
+        ("owner", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // CompoundRewards, as sent by a crank
+    &[
+        ("stakeAccount", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("vault", true, false),
+        ("rewardVault", true, false),
+        ("mint", false, false),
+        ("owner", false, false),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // SetAutoCompound
+    &[
+        ("stakeAccount", true, false),
+        ("mint", false, false),
+        ("staker", false, true),
+    ],
+    // InitializeLocker
+    &[
+        ("locker", true, false),
+        ("lockerVault", true, false),
+        ("mint", false, false),
+        ("tokenInfo", false, false),
+        ("admin", true, true),
+        ("systemProgram", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // CreateVoteLock
+    &[
+        ("voteLock", true, false),
+        ("locker", true, false),
+        ("tokenAccount", true, false),
+        ("lockerVault", true, false),
+        ("mint", false, false),
+        ("owner", true, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+        ("systemProgram", false, false),
+    ],
+    // IncreaseVoteLockAmount
+    &[
+        ("voteLock", true, false),
+        ("locker", true, false),
+        ("tokenAccount", true, false),
+        ("lockerVault", true, false),
+        ("mint", false, false),
+        ("owner", true, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // ExtendVoteLock
+    &[
+        ("voteLock", true, false),
+        ("locker", true, false),
+        ("mint", false, false),
+        ("owner", false, true),
+        ("clock", false, false),
+    ],
+    // WithdrawVoteLock
+    &[
+        ("voteLock", true, false),
+        ("locker", true, false),
+        ("tokenAccount", true, false),
+        ("lockerVault", true, false),
+        ("mint", false, false),
+        ("owner", true, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // QueryVotingPower
+    &[
+        ("voteLock", false, false),
+        ("locker", false, false),
+    ],
+    // QueryTotalVotingPower
+    &[("locker", false, false)],
+    // RegisterOperator
+    &[
+        ("operator", true, false),
+        ("mint", false, false),
+        ("authority", true, true),
+        ("systemProgram", false, false),
+    ],
+    // DelegateStake
+    &[
+        ("stakeAccount", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("mint", false, false),
+        ("operator", true, false),
+        ("staker", false, true),
+        ("clock", false, false),
+    ],
+    // ClaimCommission
+    &[
+        ("operator", true, false),
+        ("rewardVault", true, false),
+        ("destination", true, false),
+        ("mint", false, false),
+        ("authority", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // CreateFarm
+    &[
+        ("farm", true, false),
+        ("farmVault", true, false),
+        ("farmRewardVault", true, false),
+        ("stakedMint", false, false),
+        ("rewardMint", false, false),
+        ("mint", false, false),
+        ("tokenInfo", false, false),
+        ("admin", true, true),
+        ("systemProgram", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // FundFarm
+    &[
+        ("farm", true, false),
+        ("farmRewardVault", true, false),
+        ("sourceTokenAccount", true, false),
+        ("rewardMint", false, false),
+        ("funder", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // FarmStake
+    &[
+        ("farmPosition", true, false),
+        ("farm", true, false),
+        ("farmVault", true, false),
+        ("tokenAccount", true, false),
+        ("stakedMint", false, false),
+        ("owner", true, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+        ("systemProgram", false, false),
+    ],
+    // FarmUnstake
+    &[
+        ("farmPosition", true, false),
+        ("farm", true, false),
+        ("farmVault", true, false),
+        ("tokenAccount", true, false),
+        ("stakedMint", false, false),
+        ("owner", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // ClaimFarmRewards
+    &[
+        ("farmPosition", true, false),
+        ("farm", true, false),
+        ("farmRewardVault", true, false),
+        ("destination", true, false),
+        ("rewardMint", false, false),
+        ("owner", false, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // QueryFarmRewards
+    &[
+        ("farmPosition", false, false),
+        ("farm", false, false),
+        ("clock", false, false),
+    ],
+    // InitializeEmissionSchedule
+    &[
+        ("emissionSchedule", true, false),
+        ("stakingPool", true, false),
+        ("mint", false, false),
+        ("tokenInfo", false, false),
+        ("admin", true, true),
+        ("clock", false, false),
+        ("systemProgram", false, false),
+    ],
+    // AdvanceEpoch
+    &[
+        ("emissionSchedule", true, false),
+        ("stakingPool", true, false),
+        ("tokenInfo", false, false),
+        ("mint", false, false),
+        ("clock", false, false),
+    ],
+    // QueryEmissionSchedule
+    &[("emissionSchedule", false, false)],
+    // UndelegateStake
+    &[
+        ("stakeAccount", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("mint", false, false),
+        ("operator", true, false),
+        ("staker", false, true),
+        ("clock", false, false),
+    ],
+    // RequestLiquidUnstake
+    &[
+        ("liquidPool", false, false),
+        ("stakeAccount", true, false),
+        ("unbondingEntry", true, false),
+        ("stakingPool", true, false),
+        ("stakingConfig", false, false),
+        ("vault", true, false),
+        ("rewardVault", true, false),
+        ("mint", false, false),
+        ("receiptMint", true, false),
+        ("receiptAccount", true, false),
+        ("owner", true, true),
+        ("clock", false, false),
+        ("systemProgram", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // WithdrawLiquidUnbonded
+    &[
+        ("liquidPool", false, false),
+        ("stakeAccount", true, false),
+        ("unbondingEntry", true, false),
+        ("tokenAccount", true, false),
+        ("vault", true, false),
+        ("stakingConfig", false, false),
+        ("mint", false, false),
+        ("owner", true, true),
+        ("clock", false, false),
+        ("tokenProgram", false, false),
+    ],
+    // UpdateFarm
+    &[
+        ("farm", true, false),
+        ("mint", false, false),
+        ("tokenInfo", false, false),
+        ("admin", false, true),
+        ("clock", false, false),
+    ],
+];
+
+/// Accounts some instructions take after those of [`INSTRUCTION_ACCOUNTS`]
+/// only in some cases, as `(tag, account)`. Instructions on a stake position
+/// take its operator when it is delegated.
+pub const OPTIONAL_ACCOUNTS: &[(u8, AccountSpec)] = &[
+    // Stake
+    (8, ("operator", true, false)),
+    // Unstake
+    (9, ("operator", true, false)),
+    // ClaimRewards
+    (23, ("operator", true, false)),
+    // InitializeStakingConfig, for penalties sent to the treasury
+    (25, ("treasury", false, false)),
+    // UpdateStakingConfig, for penalties sent to the treasury
+    (26, ("treasury", false, false)),
+    // ExtendLock
+    (27, ("operator", true, false)),
+    // RequestUnstake
+    (28, ("operator", true, false)),
+    // CancelUnbonding
+    (30, ("operator", true, false)),
+    // EmergencyUnstake
+    (31, ("operator", true, false)),
+    // CompoundRewards
+    (35, ("operator", true, false)),
+    // DelegateStake, for a position that is delegated already
+    (45, ("currentOperator", true, false)),
+];
+
+/// Builds the IDL as a JSON value.
+pub fn generate() -> Value {
+    let mut types = Vec::new();
+    let mut known_types = Vec::new();
+
+    let instruction_schema = BorshSchemaContainer::for_type::<TokenInstruction>();
+    let variants = match instruction_schema.get_definition(instruction_schema.declaration()) {
+        Some(Definition::Enum { variants, .. }) => variants,
+        _ => unreachable!("TokenInstruction is an enum"),
+    };
+    assert_eq!(variants.len(), INSTRUCTION_ACCOUNTS.len());
+    let instructions: Vec<Value> = variants
+        .iter()
+        .zip(INSTRUCTION_ACCOUNTS)
+        .enumerate()
+        .map(|(tag, ((_, name, declaration), accounts))| {
+            let args = named_fields(declaration, &instruction_schema, &mut types, &mut known_types);
+            let optional = OPTIONAL_ACCOUNTS
+                .iter()
+                .filter(|(optional_tag, _)| *optional_tag as usize == tag)
+                .map(|(_, (name, writable, signer))| {
+                    json!({ "name": name, "isMut": writable, "isSigner": signer, "isOptional": true })
+                });
+            let accounts: Vec<Value> = accounts
+                .iter()
+                .map(|(name, writable, signer)| {
+                    json!({ "name": name, "isMut": writable, "isSigner": signer })
+                })
+                .chain(optional)
+                .collect();
+            json!({
+                "name": lower_camel_case(name),
+                "accounts": accounts,
+                "args": args,
+                "discriminant": { "type": "u8", "value": tag },
+            })
+        })
+        .collect();
+
+    let accounts = vec![
+        account::<TokenInfo>(TokenInfo::LEN, &mut types, &mut known_types),
+        account::<VestingSchedule>(VestingSchedule::LEN, &mut types, &mut known_types),
+        account::<StakeInfo>(StakeInfo::LEN, &mut types, &mut known_types),
+        account::<StakingPool>(StakingPool::LEN, &mut types, &mut known_types),
+        account::<StakingConfig>(StakingConfig::LEN, &mut types, &mut known_types),
+        account::<UnbondingEntry>(UnbondingEntry::LEN, &mut types, &mut known_types),
+        account::<LiquidPool>(LiquidPool::LEN, &mut types, &mut known_types),
+        account::<Locker>(Locker::LEN, &mut types, &mut known_types),
+        account::<VoteLock>(VoteLock::LEN, &mut types, &mut known_types),
+        account::<Operator>(Operator::LEN, &mut types, &mut known_types),
+        account::<Farm>(Farm::LEN, &mut types, &mut known_types),
+        account::<FarmPosition>(FarmPosition::LEN, &mut types, &mut known_types),
+        account::<EmissionSchedule>(EmissionSchedule::LEN, &mut types, &mut known_types),
+    ];
+
+    let errors: Vec<Value> = (0..)
+        .map_while(TokenError::from_u32)
+        .map(|error| {
+            json!({
+                "code": error as u32,
+                "name": format!("{:?}", error),
+                "msg": error.to_string(),
+            })
+        })
+        .collect();
+
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "name": env!("CARGO_PKG_NAME").replace('-', "_"),
+        "instructions": instructions,
+        "accounts": accounts,
+        "types": types,
+        "errors": errors,
+        "metadata": { "origin": "shank" },
+    })
+}
+
+fn account<T: BorshSchema>(
+    size: usize,
+    types: &mut Vec<Value>,
+    known_types: &mut Vec<Declaration>,
+) -> Value {
+    let schema = BorshSchemaContainer::for_type::<T>();
+    let fields = named_fields(schema.declaration(), &schema, types, known_types);
+    json!({
+        "name": schema.declaration(),
+        "size": size,
+        "type": { "kind": "struct", "fields": fields },
+    })
+}
+
+fn named_fields(
+    declaration: &Declaration,
+    schema: &BorshSchemaContainer,
+    types: &mut Vec<Value>,
+    known_types: &mut Vec<Declaration>,
+) -> Vec<Value> {
+    match schema.get_definition(declaration).expect("declared type has a definition") {
+        Definition::Struct {
+            fields: Fields::NamedFields(fields),
+        } => fields
+            .iter()
+            .map(|(name, declaration)| {
+                json!({
+                    "name": lower_camel_case(name),
+                    "type": idl_type(declaration, schema, types, known_types),
+                })
+            })
+            .collect(),
+        Definition::Struct {
+            fields: Fields::Empty,
+        } => Vec::new(),
+        definition => panic!("unsupported definition for {}: {:?}", declaration, definition),
+    }
+}
+
+fn idl_type(
+    declaration: &Declaration,
+    schema: &BorshSchemaContainer,
+    types: &mut Vec<Value>,
+    known_types: &mut Vec<Declaration>,
+) -> Value {
+    if declaration == "Pubkey" {
+        return json!("publicKey");
+    }
+    match schema.get_definition(declaration) {
+        None | Some(Definition::Primitive(_)) => json!(declaration),
+        Some(Definition::Sequence {
+            length_width: Definition::ARRAY_LENGTH_WIDTH,
+            length_range,
+            elements,
+        }) => {
+            json!({ "array": [idl_type(elements, schema, types, known_types), length_range.end()] })
+        }
+        Some(Definition::Sequence { elements, .. }) => {
+            json!({ "vec": idl_type(elements, schema, types, known_types) })
+        }
+        Some(Definition::Enum { variants, .. }) if declaration.starts_with("Option<") => {
+            json!({ "option": idl_type(&variants[1].2, schema, types, known_types) })
+        }
+        Some(Definition::Enum { variants, .. }) => {
+            if !known_types.contains(declaration) {
+                known_types.push(declaration.clone());
+                let variants: Vec<Value> = variants
+                    .iter()
+                    .map(|(_, name, _)| json!({ "name": name }))
+                    .collect();
+                types.push(json!({
+                    "name": declaration,
+                    "type": { "kind": "enum", "variants": variants },
+                }));
+            }
+            json!({ "defined": declaration })
+        }
+        Some(Definition::Struct { .. }) => {
+            if !known_types.contains(declaration) {
+                known_types.push(declaration.clone());
+                let fields = named_fields(declaration, schema, types, known_types);
+                types.push(json!({
+                    "name": declaration,
+                    "type": { "kind": "struct", "fields": fields },
+                }));
+            }
+            json!({ "defined": declaration })
+        }
+        Some(Definition::Tuple { .. }) => panic!("tuples are not supported: {}", declaration),
+    }
+}
+
+fn lower_camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper_next = false;
+    for (i, c) in name.chars().enumerate() {
+        if c == '_' {
+            upper_next = true;
+        } else if i == 0 {
+            out.extend(c.to_lowercase());
+        } else if upper_next {
+            out.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
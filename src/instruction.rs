@@ -1,99 +1,2168 @@
+use solana_program::bpf_loader_upgradeable;
+use solana_program::instruction::{AccountMeta, Instruction};
 use solana_program::program_error::ProgramError;
 use solana_program::pubkey::Pubkey;
-use solana_program::program_pack::Pack;
-use spl_token::state::Account as TokenAccount;
+use solana_program::{system_program, sysvar};
 
 use crate::error::TokenError;
-use arrayref::{array_ref, array_refs};
+use crate::state::{
+    find_emission_schedule_address, find_farm_address, find_farm_position_address, find_farm_reward_vault_address,
+    find_farm_vault_address, find_liquid_pool_address, find_locker_address, find_locker_vault_address,
+    find_operator_address, find_receipt_mint_address, find_reward_vault_address, find_stake_address,
+    find_staking_config_address, find_staking_pool_address, find_unbonding_address, find_vault_address,
+    find_vote_lock_address, AccountType, LockTier, PenaltyDestination, StakingParams,
+};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 
-#[derive(Debug)]
+/// Instructions supported by the program.
+///
+/// Instruction data is the Borsh encoding of this enum: a one byte tag
+/// followed by the variant's fields. Integers are little-endian, pubkeys are
+/// their raw 32 bytes and options are a 0 byte for `None` or a 1 byte
+/// followed by the value, except for the new authority of
+/// [`TokenInstruction::SetAuthority`]. Payloads must have exactly the
+/// documented length, anything shorter or longer is rejected with
+/// `TokenError::InvalidInstruction`.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub enum TokenInstruction {
+    /// Tag 0. `decimals: u8`.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The mint to initialize.
+    ///   1. `[]` The mint authority.
+    ///   2. `[]` The freeze authority, or the default pubkey for none.
+    ///   3. `[]` Rent sysvar.
+    ///   4. `[]` SPL token program.
     InitializeMint { decimals: u8 },
+    /// Tag 1. No arguments.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The token account to initialize.
+    ///   1. `[]` The mint of the account.
+    ///   2. `[]` The owner of the account.
+    ///   3. `[]` Rent sysvar.
+    ///   4. `[]` SPL token program.
     InitializeAccount,
+    /// Tag 2. `amount: u64`.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The source token account.
+    ///   1. `[writable]` The destination token account.
+    ///   2. `[signer]` The owner of the source account.
+    ///   3. `[]` SPL token program.
     Transfer { amount: u64 },
+    /// Tag 3. `amount: u64`.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The token account to burn from.
+    ///   1. `[writable]` The mint.
+    ///   2. `[signer]` The owner of the token account.
+    ///   3. `[]` SPL token program.
     Burn { amount: u64 },
+    /// Tag 4. `amount: u64`.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The mint.
+    ///   1. `[writable]` The token account to mint to.
+    ///   2. `[signer]` The mint authority.
+    ///   3. `[]` SPL token program.
     MintTo { amount: u64 },
+    /// Tag 5. No arguments.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The token account to freeze.
+    ///   1. `[]` The mint.
+    ///   2. `[signer]` The freeze authority.
+    ///   3. `[]` SPL token program.
     Freeze,
+    /// Tag 6. No arguments.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The token account to thaw.
+    ///   1. `[]` The mint.
+    ///   2. `[signer]` The freeze authority.
+    ///   3. `[]` SPL token program.
     Thaw,
-    SetAuthority { authority_type: u8, new_authority: Option<Pubkey> },
+    /// Tag 7. `authority_type: u8` optionally followed by the 32 byte new
+    /// authority, without the tag byte of a Borsh option. Omitting the pubkey
+    /// clears the authority.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The mint or token account to change.
+    ///   1. `[signer]` The current authority.
+    ///   2. `[]` SPL token program.
+    SetAuthority {
+        authority_type: u8,
+        #[borsh(
+            serialize_with = "trailing_pubkey::serialize",
+            deserialize_with = "trailing_pubkey::deserialize"
+        )]
+        new_authority: Option<Pubkey>,
+    },
+    /// Tag 8. `amount: u64`.
+    ///
+    /// Moves `amount` from the staker's token account into the vault. The
+    /// new tokens lock for the tier of the position from now, averaged with
+    /// the lock of the tokens already staked, see [`StakeInfo::deposit`].
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The staker's stake account, see [`find_stake_address`].
+    ///   1. `[writable]` The token account the stake is taken from.
+    ///   2. `[writable]` The vault of the mint, see [`find_vault_address`].
+    ///   3. `[writable]` The staking pool of the mint, see [`find_staking_pool_address`].
+    ///   4. `[]` The staking config of the mint, see [`find_staking_config_address`].
+    ///   5. `[]` The mint.
+    ///   6. `[signer]` The staker, owner of the token account.
+    ///   7. `[]` Clock sysvar.
+    ///   8. `[]` SPL token program.
+    ///   9. `[writable]` The operator the position is delegated to, only for a
+    ///      delegated position.
     Stake { amount: u64 },
+    /// Tag 9. `amount: u64`.
+    ///
+    /// Returns `amount` from the vault to the staker's token account once the
+    /// position is unlocked. Only available when the staking config has no
+    /// unbonding period, see [`TokenInstruction::RequestUnstake`].
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The staker's stake account, see [`find_stake_address`].
+    ///   1. `[writable]` The token account the stake is returned to.
+    ///   2. `[writable]` The vault of the mint, see [`find_vault_address`].
+    ///   3. `[writable]` The staking pool of the mint, see [`find_staking_pool_address`].
+    ///   4. `[]` The staking config of the mint, see [`find_staking_config_address`].
+    ///   5. `[]` The mint.
+    ///   6. `[signer]` The staker, owner of the token account.
+    ///   7. `[]` Clock sysvar.
+    ///   8. `[]` SPL token program.
+    ///   9. `[writable]` The operator the position is delegated to, only for a
+    ///      delegated position.
     Unstake { amount: u64 },
+    /// Tag 10. No arguments.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` This program.
+    ///   1. `[writable]` The buffer holding the new program data.
+    ///   2. `[writable]` The account receiving the buffer's lamports.
+    ///   3. `[signer]` The upgrade authority.
+    ///   4. `[]` Rent sysvar.
+    ///   5. `[]` BPF upgradeable loader.
     UpgradeProgram,
+    /// Tag 11. No arguments.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The token info account.
+    ///   1. `[signer]` The mint authority.
+    ///   2. `[]` The SPL mint, whose mint authority must be account 1.
     InitializeTokenInfo,
+    /// Tag 12. `beneficiary: Pubkey`, `allocation_type: u8` (see
+    /// [`AllocationType`]), `amount: u64`, `start_time: i64`,
+    /// `cliff_time: i64`, `end_time: i64`. 65 bytes of arguments in total.
+    ///
+    /// Vests `amount` of the allocation to `beneficiary` from `start_time`
+    /// to `end_time`, with nothing released before `cliff_time`, which must
+    /// fall between them. See [`VestingSchedule`] for what unlocks at the
+    /// cliff.
+    ///
+    /// [`VestingSchedule`]: crate::state::VestingSchedule
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The vesting account.
+    ///   1. `[writable]` The token info account.
+    ///   2. `[signer]` The mint authority recorded in the token info.
     CreateVestingSchedule {
         beneficiary: Pubkey,
         allocation_type: AllocationType,
         amount: u64,
         start_time: i64,
+        cliff_time: i64,
         end_time: i64,
     },
+    /// Tag 13. No arguments.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The vesting account.
+    ///   1. `[writable]` A token account owned by the beneficiary.
+    ///   2. `[writable]` The token account the vested tokens are released from.
+    ///   3. `[signer]` The owner of the source token account.
+    ///   4. `[]` Clock sysvar.
+    ///   5. `[]` SPL token program.
     ReleaseVestedTokens,
+    /// Tag 14. `account_type: u8` (see [`AccountType`]), the type the
+    /// account is expected to hold.
+    ///
+    /// Rewrites an account stored in an older layout version in the current
+    /// one, growing it and topping up its rent exemption as needed. Accounts
+    /// already in the current layout are left untouched.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The account to migrate.
+    ///   1. `[]` The token info account. May be account 0.
+    ///   2. `[writable, signer]` The mint authority recorded in the token
+    ///      info, paying for the extra rent.
+    ///   3. `[]` System program.
+    MigrateAccount { account_type: AccountType },
+    /// Tag 15. No arguments.
+    ///
+    /// Returns the amount vested so far as a `u64`, through
+    /// `set_return_data`. Changes no accounts.
+    ///
+    /// Accounts:
+    ///   0. `[]` The vesting account.
+    ///   1. `[]` Clock sysvar.
+    QueryVestedAmount,
+    /// Tag 16. No arguments.
+    ///
+    /// Returns the amount vested and not yet released as a `u64`, through
+    /// `set_return_data`. Changes no accounts.
+    ///
+    /// Accounts:
+    ///   0. `[]` The vesting account.
+    ///   1. `[]` Clock sysvar.
+    QueryReleasableAmount,
+    /// Tag 17. No arguments.
+    ///
+    /// Returns the Borsh encoded `StakeInfo` of the stake account, through
+    /// `set_return_data`. Changes no accounts.
+    ///
+    /// Accounts:
+    ///   0. `[]` The stake account.
+    QueryStakeInfo,
+    /// Tag 18. `allocation_type: u8` (see [`AllocationType`]).
+    ///
+    /// Returns the amount of the allocation not yet committed to vesting
+    /// schedules as a `u64`, through `set_return_data`. Changes no accounts.
+    ///
+    /// Accounts:
+    ///   0. `[]` The token info account.
+    QueryAllocationRemaining { allocation_type: AllocationType },
+    /// Tag 19. No arguments.
+    ///
+    /// Creates the vault holding the tokens staked with a mint.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The vault, see [`find_vault_address`].
+    ///   1. `[]` The mint.
+    ///   2. `[writable, signer]` The account paying for the vault.
+    ///   3. `[]` System program.
+    ///   4. `[]` SPL token program.
+    InitializeVault,
+    /// Tag 20. `lock_tier: LockTier`
+    ///
+    /// Creates the stake account of an owner for a mint, locking every stake
+    /// into it for the duration of `lock_tier`.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account, see [`find_stake_address`].
+    ///   1. `[]` The mint.
+    ///   2. `[writable, signer]` The owner, paying for the account.
+    ///   3. `[]` System program.
+    InitializeStake { lock_tier: LockTier },
+    /// Tag 21. `reward_rate: u64`, rewards emitted per second.
+    ///
+    /// Creates the staking pool of a mint and the vault its rewards are
+    /// paid from. The pool starts out empty: stakes already in the stake
+    /// vault are counted towards its totals, and start earning, once their
+    /// positions are next staked to, unstaked from or relocked.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The staking pool, see [`find_staking_pool_address`].
+    ///   1. `[writable]` The reward vault, see [`find_reward_vault_address`].
+    ///   2. `[]` The stake vault of the mint, see [`find_vault_address`].
+    ///   3. `[]` The mint.
+    ///   4. `[]` The token info of the mint.
+    ///   5. `[writable, signer]` The mint authority recorded in the token
+    ///      info, paying for the accounts.
+    ///   6. `[]` System program.
+    ///   7. `[]` SPL token program.
+    InitializeStakingPool { reward_rate: u64 },
+    /// Tag 22. `amount: u64`
+    ///
+    /// Moves `amount` into the reward vault, out of the community rewards
+    /// allocation.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The staking pool.
+    ///   1. `[writable]` The reward vault.
+    ///   2. `[writable]` The token info of the mint.
+    ///   3. `[writable]` The token account the rewards are taken from.
+    ///   4. `[]` The mint.
+    ///   5. `[signer]` The mint authority recorded in the token info, owner
+    ///      of the token account.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[]` SPL token program.
+    FundRewards { amount: u64 },
+    /// Tag 23. No arguments.
+    ///
+    /// Pays the staker the rewards earned by their stake.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The staker's stake account.
+    ///   1. `[writable]` The staking pool.
+    ///   2. `[writable]` The reward vault.
+    ///   3. `[writable]` A token account owned by the staker.
+    ///   4. `[]` The mint.
+    ///   5. `[signer]` The staker.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[]` SPL token program.
+    ///   8. `[writable]` The operator the position is delegated to, only for a
+    ///      delegated position.
+    ClaimRewards,
+    /// Tag 24. No arguments. Returns the rewards a stake could claim now,
+    /// as a little-endian u64.
+    ///
+    /// Accounts:
+    ///   0. `[]` The stake account.
+    ///   1. `[]` The staking pool of its mint.
+    ///   2. `[]` Clock sysvar.
+    QueryPendingRewards,
+    /// Tag 25. `params: StakingParams`
+    ///
+    /// Creates the staking config of a mint. The staking pool switches to
+    /// its reward rate.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The staking config, see [`find_staking_config_address`].
+    ///   1. `[writable]` The staking pool of the mint.
+    ///   2. `[]` The mint.
+    ///   3. `[]` The token info of the mint.
+    ///   4. `[writable, signer]` The mint authority recorded in the token
+    ///      info, paying for the account.
+    ///   5. `[]` Clock sysvar.
+    ///   6. `[]` System program.
+    ///   7. `[]` The `treasury` token account of the params, holding the
+    ///      mint, only when penalties go to the treasury.
+    InitializeStakingConfig { params: StakingParams },
+    /// Tag 26. `params: StakingParams`
+    ///
+    /// Replaces the staking rules of a mint. Rewards earned so far are kept
+    /// at the previous rate.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The staking config.
+    ///   1. `[writable]` The staking pool of the mint.
+    ///   2. `[]` The mint.
+    ///   3. `[]` The token info of the mint.
+    ///   4. `[signer]` The mint authority recorded in the token info.
+    ///   5. `[]` Clock sysvar.
+    ///   6. `[]` The `treasury` token account of the params, holding the
+    ///      mint, only when penalties go to the treasury.
+    UpdateStakingConfig { params: StakingParams },
+    /// Tag 27. `lock_tier: LockTier`, longer than the current tier.
+    ///
+    /// Moves a stake position to a longer lock, which unlocks the full new
+    /// duration from now and earns with the new multiplier from now on.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account of the staker, see [`find_stake_address`].
+    ///   1. `[writable]` The staking pool of the mint.
+    ///   2. `[]` The mint.
+    ///   3. `[signer]` The staker.
+    ///   4. `[]` Clock sysvar.
+    ///   5. `[writable]` The operator the position is delegated to, only for a
+    ///      delegated position.
+    ExtendLock { lock_tier: LockTier },
+    /// Tag 28. `amount: u64`
+    ///
+    /// Takes `amount` out of an unlocked stake position into a new
+    /// unbonding entry, where it earns nothing until withdrawn after the
+    /// unbonding period of the staking config.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account of the staker, see [`find_stake_address`].
+    ///   1. `[writable]` The new unbonding entry, see [`find_unbonding_address`],
+    ///      for the next unbonding id of the stake account.
+    ///   2. `[writable]` The staking pool of the mint.
+    ///   3. `[]` The staking config of the mint.
+    ///   4. `[]` The mint.
+    ///   5. `[writable, signer]` The staker, paying for the entry.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[]` System program.
+    ///   8. `[writable]` The operator the position is delegated to, only for a
+    ///      delegated position.
+    RequestUnstake { amount: u64 },
+    /// Tag 29. No arguments.
+    ///
+    /// Pays an unbonding entry out of the vault once its unbonding period
+    /// has ended and closes it.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account of the staker.
+    ///   1. `[writable]` The unbonding entry.
+    ///   2. `[writable]` The token account the tokens are returned to.
+    ///   3. `[writable]` The vault of the mint, see [`find_vault_address`].
+    ///   4. `[]` The staking config of the mint.
+    ///   5. `[]` The mint.
+    ///   6. `[writable, signer]` The staker, owner of the token account,
+    ///      receiving the rent of the entry.
+    ///   7. `[]` Clock sysvar.
+    ///   8. `[]` SPL token program.
+    WithdrawUnbonded,
+    /// Tag 30. No arguments.
+    ///
+    /// Puts the tokens of an unbonding entry back into the stake position,
+    /// under the lock the position already has, and closes the entry.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account of the staker.
+    ///   1. `[writable]` The unbonding entry.
+    ///   2. `[writable]` The staking pool of the mint.
+    ///   3. `[]` The staking config of the mint.
+    ///   4. `[]` The mint.
+    ///   5. `[writable, signer]` The staker, receiving the rent of the entry.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[writable]` The operator the position is delegated to, only for a
+    ///      delegated position.
+    CancelUnbonding,
+    /// Tag 31. `amount: u64`
+    ///
+    /// Takes `amount` out of a locked stake position back to the staker at
+    /// once, skipping any unbonding period, for the penalty of the staking
+    /// config, see [`StakingParams::early_unstake_penalty`]. The penalty is
+    /// kept out of the tokens returned and burned or sent to the treasury
+    /// or the reward pool. Unlocked positions pay nothing, but still go
+    /// through unbonding when the staking config has an unbonding period.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account of the staker.
+    ///   1. `[writable]` The token account receiving the tokens.
+    ///   2. `[writable]` The vault.
+    ///   3. `[writable]` The staking pool of the mint.
+    ///   4. `[]` The staking config of the mint.
+    ///   5. `[writable]` The mint.
+    ///   6. `[writable]` The account receiving the penalty, see
+    ///      [`StakingParams::penalty_account`].
+    ///   7. `[signer]` The staker.
+    ///   8. `[]` Clock sysvar.
+    ///   9. `[]` SPL Token program.
+    ///   10. `[writable]` The operator the position is delegated to, only for
+    ///       a delegated position.
+    EmergencyUnstake { amount: u64 },
+    /// Tag 32. No arguments.
+    ///
+    /// Creates the liquid pool of a mint, its receipt mint, with the
+    /// decimals of the mint and the liquid pool as mint authority, and the
+    /// stake position of the pool.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The liquid pool, see [`find_liquid_pool_address`].
+    ///   1. `[writable]` The receipt mint, see [`find_receipt_mint_address`].
+    ///   2. `[writable]` The stake account of the liquid pool.
+    ///   3. `[]` The mint.
+    ///   4. `[]` The token info of the mint.
+    ///   5. `[writable, signer]` The mint authority of the token info, paying
+    ///      for the accounts.
+    ///   6. `[]` System program.
+    ///   7. `[]` SPL Token program.
+    InitializeLiquidPool,
+    /// Tag 33. `amount: u64`
+    ///
+    /// Stakes `amount` through the liquid pool and mints receipt tokens for
+    /// it at the exchange rate of the pool, after staking the rewards of
+    /// the pool again. The position of the pool is held to the limits of
+    /// the staking config, but never locked, see
+    /// [`crate::state::LiquidPool`].
+    ///
+    /// Accounts:
+    ///   0. `[]` The liquid pool.
+    ///   1. `[writable]` The stake account of the liquid pool.
+    ///   2. `[writable]` The staking pool of the mint.
+    ///   3. `[]` The staking config of the mint.
+    ///   4. `[writable]` The vault.
+    ///   5. `[writable]` The reward vault.
+    ///   6. `[]` The mint.
+    ///   7. `[writable]` The receipt mint.
+    ///   8. `[writable]` The token account to stake from.
+    ///   9. `[writable]` The receipt token account to mint to.
+    ///   10. `[signer]` The owner of both token accounts.
+    ///   11. `[]` Clock sysvar.
+    ///   12. `[]` SPL Token program.
+    LiquidStake { amount: u64 },
+    /// Tag 34. `receipt_amount: u64`
+    ///
+    /// Burns `receipt_amount` receipt tokens and unstakes what they are
+    /// worth at the exchange rate of the pool, after staking the rewards of
+    /// the pool again. Like [`TokenInstruction::Unstake`], it fails when
+    /// unstaking needs unbonding, which goes through
+    /// [`TokenInstruction::RequestLiquidUnstake`] instead.
+    ///
+    /// Accounts: as for [`TokenInstruction::LiquidStake`], with the token
+    /// account receiving the unstaked tokens and the receipt token account
+    /// to burn from.
+    LiquidUnstake { receipt_amount: u64 },
+    /// Tag 35. No arguments.
+    ///
+    /// Stakes the pending rewards of a position into it, moving them from
+    /// the reward vault to the vault. The lock of the position stays as it
+    /// is, but like [`TokenInstruction::Stake`] it fails while staking is
+    /// paused or past the maximum total stake of the staking config.
+    /// Anyone can compound a position that opted into auto-compound.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account of the owner.
+    ///   1. `[writable]` The staking pool of the mint.
+    ///   2. `[]` The staking config of the mint.
+    ///   3. `[writable]` The vault.
+    ///   4. `[writable]` The reward vault.
+    ///   5. `[]` The mint.
+    ///   6. `[signer]` The owner of the position, only signing if the
+    ///      position has not opted into auto-compound.
+    ///   7. `[]` Clock sysvar.
+    ///   8. `[]` SPL Token program.
+    ///   9. `[writable]` The operator the position is delegated to, only for a
+    ///      delegated position.
+    CompoundRewards,
+    /// Tag 36. `enabled: bool`
+    ///
+    /// Opts a stake position in or out of auto-compound.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account of the staker.
+    ///   1. `[]` The mint.
+    ///   2. `[signer]` The staker.
+    SetAutoCompound { enabled: bool },
+    /// Tag 37. `max_lock_duration: i64`, a whole number of weeks up to
+    /// `MAX_VOTE_LOCK_WEEKS`.
+    ///
+    /// Creates the vote-escrow locker of a mint and the vault of its locked
+    /// tokens. Only the mint authority recorded in the token info can.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The locker account, derived from the mint.
+    ///   1. `[writable]` The locker vault, derived from the mint.
+    ///   2. `[]` The mint.
+    ///   3. `[]` The token info of the mint.
+    ///   4. `[writable, signer]` The mint authority, paying for the accounts.
+    ///   5. `[]` System program.
+    ///   6. `[]` SPL Token program.
+    InitializeLocker { max_lock_duration: i64 },
+    /// Tag 38. `amount: u64, unlock_time: i64`
+    ///
+    /// Locks `amount` in the locker until `unlock_time`, rounded down to a
+    /// week, for voting power decaying to zero by then.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The vote lock account, derived from the mint and the
+    ///      owner.
+    ///   1. `[writable]` The locker.
+    ///   2. `[writable]` The owner's token account to lock from.
+    ///   3. `[writable]` The locker vault.
+    ///   4. `[]` The mint.
+    ///   5. `[writable, signer]` The owner, paying for the vote lock account.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[]` SPL Token program.
+    ///   8. `[]` System program.
+    CreateVoteLock { amount: u64, unlock_time: i64 },
+    /// Tag 39. `amount: u64`
+    ///
+    /// Adds `amount` to a vote lock that has not expired, keeping its
+    /// unlock time.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The vote lock of the owner.
+    ///   1. `[writable]` The locker.
+    ///   2. `[writable]` The owner's token account to lock from.
+    ///   3. `[writable]` The locker vault.
+    ///   4. `[]` The mint.
+    ///   5. `[writable, signer]` The owner.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[]` SPL Token program.
+    IncreaseVoteLockAmount { amount: u64 },
+    /// Tag 40. `unlock_time: i64`
+    ///
+    /// Moves the unlock time of a vote lock that has not expired to a later
+    /// week.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The vote lock of the owner.
+    ///   1. `[writable]` The locker.
+    ///   2. `[]` The mint.
+    ///   3. `[signer]` The owner.
+    ///   4. `[]` Clock sysvar.
+    ExtendVoteLock { unlock_time: i64 },
+    /// Tag 41. No arguments.
+    ///
+    /// Withdraws the tokens of an expired vote lock and closes it, its rent
+    /// going to the owner.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The vote lock of the owner.
+    ///   1. `[writable]` The locker.
+    ///   2. `[writable]` The owner's token account to withdraw to.
+    ///   3. `[writable]` The locker vault.
+    ///   4. `[]` The mint.
+    ///   5. `[writable, signer]` The owner.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[]` SPL Token program.
+    WithdrawVoteLock,
+    /// Tag 42. `timestamp: i64`. Returns the voting power of a vote lock at
+    /// `timestamp`, as a little-endian u64. Times before the last change
+    /// of the lock are rejected.
+    ///
+    /// Accounts:
+    ///   0. `[]` The vote lock.
+    ///   1. `[]` The locker of its mint.
+    QueryVotingPower { timestamp: i64 },
+    /// Tag 43. `timestamp: i64`. Returns the total voting power of a locker
+    /// at `timestamp`, as a little-endian u64. Times older than the
+    /// checkpoints the locker keeps are rejected.
+    ///
+    /// Accounts:
+    ///   0. `[]` The locker.
+    QueryTotalVotingPower { timestamp: i64 },
+    /// Tag 44. `commission_bps: u16`, at most 10 000.
+    ///
+    /// Registers the caller as an operator of a mint, taking
+    /// `commission_bps` of the rewards of the positions delegated to it.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The operator account, derived from the mint and the
+    ///      authority.
+    ///   1. `[]` The mint.
+    ///   2. `[writable, signer]` The operator authority, paying for the
+    ///      account.
+    ///   3. `[]` System program.
+    RegisterOperator { commission_bps: u16 },
+    /// Tag 45. No arguments.
+    ///
+    /// Delegates a stake position to an operator. Rewards earned until then
+    /// are settled at the commission of the current operator, if any, and
+    /// moving away from one is only possible once the position is unlocked.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account of the staker.
+    ///   1. `[writable]` The staking pool of the mint.
+    ///   2. `[]` The staking config of the mint.
+    ///   3. `[]` The mint.
+    ///   4. `[writable]` The operator to delegate to.
+    ///   5. `[signer]` The staker.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[writable]` The current operator, only for a delegated position.
+    DelegateStake,
+    /// Tag 46. No arguments.
+    ///
+    /// Collects the commission passed on to an operator by its delegated
+    /// positions, which happens whenever one of them is settled.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The operator.
+    ///   1. `[writable]` The reward vault.
+    ///   2. `[writable]` The token account receiving the commission.
+    ///   3. `[]` The mint.
+    ///   4. `[signer]` The operator authority.
+    ///   5. `[]` Clock sysvar.
+    ///   6. `[]` SPL Token program.
+    ClaimCommission,
+    /// Tag 47. `id: u64, emission_rate: u64, start_time: i64, end_time: i64`
+    ///
+    /// Creates farm `id` of the mint, with its vault and reward vault.
+    /// Stakers of the staked mint earn `emission_rate` of the reward mint
+    /// per second between `start_time` and `end_time`. Only the mint
+    /// authority recorded in the token info can.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The farm account, derived from the mint and `id`.
+    ///   1. `[writable]` The farm vault, derived from the farm.
+    ///   2. `[writable]` The farm reward vault, derived from the farm.
+    ///   3. `[]` The staked mint.
+    ///   4. `[]` The reward mint.
+    ///   5. `[]` The mint.
+    ///   6. `[]` The token info of the mint.
+    ///   7. `[writable, signer]` The mint authority, paying for the accounts.
+    ///   8. `[]` System program.
+    ///   9. `[]` SPL Token program.
+    CreateFarm {
+        id: u64,
+        emission_rate: u64,
+        start_time: i64,
+        end_time: i64,
+    },
+    /// Tag 48. `amount: u64`
+    ///
+    /// Adds `amount` of the reward mint to the rewards of a farm. Anyone
+    /// can fund a farm.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The farm.
+    ///   1. `[writable]` The farm reward vault.
+    ///   2. `[writable]` The token account to fund from.
+    ///   3. `[]` The reward mint.
+    ///   4. `[signer]` The owner of the token account.
+    ///   5. `[]` Clock sysvar.
+    ///   6. `[]` SPL Token program.
+    FundFarm { amount: u64 },
+    /// Tag 49. `amount: u64`
+    ///
+    /// Stakes `amount` of the staked mint in a farm, creating the position
+    /// of the owner on the first stake. Rejected once the farm has ended.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The farm position, derived from the farm and owner.
+    ///   1. `[writable]` The farm.
+    ///   2. `[writable]` The farm vault.
+    ///   3. `[writable]` The token account to stake from.
+    ///   4. `[]` The staked mint.
+    ///   5. `[writable, signer]` The owner, paying for a new position.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[]` SPL Token program.
+    ///   8. `[]` System program.
+    FarmStake { amount: u64 },
+    /// Tag 50. `amount: u64`
+    ///
+    /// Unstakes `amount` from a farm position. Farms have no lock.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The farm position.
+    ///   1. `[writable]` The farm.
+    ///   2. `[writable]` The farm vault.
+    ///   3. `[writable]` The token account receiving the tokens.
+    ///   4. `[]` The staked mint.
+    ///   5. `[signer]` The owner.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[]` SPL Token program.
+    FarmUnstake { amount: u64 },
+    /// Tag 51. No arguments.
+    ///
+    /// Pays the rewards earned by a farm position.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The farm position.
+    ///   1. `[writable]` The farm.
+    ///   2. `[writable]` The farm reward vault.
+    ///   3. `[writable]` The token account receiving the rewards.
+    ///   4. `[]` The reward mint.
+    ///   5. `[signer]` The owner.
+    ///   6. `[]` Clock sysvar.
+    ///   7. `[]` SPL Token program.
+    ClaimFarmRewards,
+    /// Tag 52. No arguments.
+    ///
+    /// Returns the rewards a farm position could claim now as a
+    /// little-endian u64 in the return data.
+    ///
+    /// Accounts:
+    ///   0. `[]` The farm position.
+    ///   1. `[]` The farm.
+    ///   2. `[]` Clock sysvar.
+    QueryFarmRewards,
+    /// Tag 53. `epoch_duration: i64, initial_emission: u64, decay_bps: u16`
+    ///
+    /// Puts the staking pool of the mint on an emission schedule, starting
+    /// its first epoch now. Each epoch lasts `epoch_duration` seconds, at
+    /// least `MIN_EPOCH_DURATION`, and emits `decay_bps` less than the one before, from `initial_emission`
+    /// and within the community rewards left. From then on the schedule
+    /// sets the reward rate instead of the staking config. Only the mint
+    /// authority recorded in the token info can.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The emission schedule, see
+    ///      [`find_emission_schedule_address`].
+    ///   1. `[writable]` The staking pool of the mint.
+    ///   2. `[]` The mint.
+    ///   3. `[]` The token info of the mint.
+    ///   4. `[writable, signer]` The mint authority, paying for the account.
+    ///   5. `[]` Clock sysvar.
+    ///   6. `[]` System program.
+    InitializeEmissionSchedule {
+        epoch_duration: i64,
+        initial_emission: u64,
+        decay_bps: u16,
+    },
+    /// Tag 54. No arguments.
+    ///
+    /// Starts the next epoch of an emission schedule once the current one
+    /// has ended. The new epoch starts where the last one ended, so an epoch
+    /// advanced late emits what it missed, and a crank more than an epoch
+    /// late goes through each epoch it missed in turn, up to
+    /// `MAX_EPOCHS_PER_CRANK` of them, and the next crank goes on from
+    /// there. Epochs after the emission reaches zero are skipped at once.
+    /// Anyone can.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The emission schedule.
+    ///   1. `[writable]` The staking pool of the mint.
+    ///   2. `[]` The token info of the mint.
+    ///   3. `[]` The mint.
+    ///   4. `[]` Clock sysvar.
+    AdvanceEpoch,
+    /// Tag 55. No arguments.
+    ///
+    /// Returns the Borsh encoded `EpochEmission` of the current epoch of an
+    /// emission schedule, through `set_return_data`. Changes no accounts.
+    ///
+    /// Accounts:
+    ///   0. `[]` The emission schedule.
+    QueryEmissionSchedule,
+    /// Tag 56. No arguments.
+    ///
+    /// Takes a stake position away from its operator. Like redelegating,
+    /// only possible once the position is unlocked. Rewards earned until
+    /// then are settled at the commission of the operator, which is passed
+    /// on to it.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The stake account of the staker.
+    ///   1. `[writable]` The staking pool of the mint.
+    ///   2. `[]` The staking config of the mint.
+    ///   3. `[]` The mint.
+    ///   4. `[writable]` The operator the position is delegated to.
+    ///   5. `[signer]` The staker.
+    ///   6. `[]` Clock sysvar.
+    UndelegateStake,
+    /// Tag 57. `receipt_amount: u64`
+    ///
+    /// Burns `receipt_amount` receipt tokens and takes what they are worth
+    /// at the exchange rate of the pool, after staking the rewards of the
+    /// pool again, out of the position of the pool into a new unbonding
+    /// entry of the position. Only the owner of the receipts can withdraw
+    /// it, after the unbonding period of the staking config.
+    ///
+    /// Accounts:
+    ///   0. `[]` The liquid pool.
+    ///   1. `[writable]` The stake account of the liquid pool.
+    ///   2. `[writable]` The next unbonding entry of the stake account, see
+    ///      [`find_unbonding_address`].
+    ///   3. `[writable]` The staking pool of the mint.
+    ///   4. `[]` The staking config of the mint.
+    ///   5. `[writable]` The vault.
+    ///   6. `[writable]` The reward vault.
+    ///   7. `[]` The mint.
+    ///   8. `[writable]` The receipt mint.
+    ///   9. `[writable]` The receipt token account to burn from.
+    ///   10. `[writable, signer]` The owner of the receipt token account,
+    ///       paying for the entry.
+    ///   11. `[]` Clock sysvar.
+    ///   12. `[]` System program.
+    ///   13. `[]` SPL Token program.
+    RequestLiquidUnstake { receipt_amount: u64 },
+    /// Tag 58. No arguments.
+    ///
+    /// Pays out an unbonding entry of the liquid pool once its unbonding
+    /// period has passed, and closes it.
+    ///
+    /// Accounts:
+    ///   0. `[]` The liquid pool.
+    ///   1. `[writable]` The stake account of the liquid pool.
+    ///   2. `[writable]` The unbonding entry.
+    ///   3. `[writable]` The token account to withdraw to.
+    ///   4. `[writable]` The vault.
+    ///   5. `[]` The staking config of the mint.
+    ///   6. `[]` The mint.
+    ///   7. `[writable, signer]` The owner of the entry, receiving its rent.
+    ///   8. `[]` Clock sysvar.
+    ///   9. `[]` SPL Token program.
+    WithdrawLiquidUnbonded,
+    /// Tag 59. `emission_rate: u64, end_time: i64`
+    ///
+    /// Changes the emission rate and end time of a farm. Rewards up to now
+    /// are emitted at the old rate first. Only the mint authority recorded
+    /// in the token info can.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` The farm.
+    ///   1. `[]` The mint.
+    ///   2. `[]` The token info of the mint.
+    ///   3. `[signer]` The mint authority.
+    ///   4. `[]` Clock sysvar.
+    UpdateFarm { emission_rate: u64, end_time: i64 },
 }
 
-#[derive(Debug)]
+/// Bucket of the token supply a vesting schedule draws from, encoded as a
+/// single byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[borsh(use_discriminant = true)]
 pub enum AllocationType {
-    Team,
-    Investors,
-    Liquidity,
-    Development,
-    Community,
-    Strategic,
+    Team = 0,
+    Investors = 1,
+    Liquidity = 2,
+    Development = 3,
+    Community = 4,
+    Strategic = 5,
+}
+
+impl TryFrom<u8> for AllocationType {
+    type Error = ProgramError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => AllocationType::Team,
+            1 => AllocationType::Investors,
+            2 => AllocationType::Liquidity,
+            3 => AllocationType::Development,
+            4 => AllocationType::Community,
+            5 => AllocationType::Strategic,
+            _ => return Err(TokenError::InvalidInstruction.into()),
+        })
+    }
 }
 
 impl TokenInstruction {
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        use TokenError::InvalidInstruction;
-        let (&tag, rest) = input.split_first().ok_or(InvalidInstruction)?;
-        Ok(match tag {
-            0 => {
-                let decimals = rest.get(0).copied().ok_or(InvalidInstruction)?;
-                Self::InitializeMint { decimals }
-            }
-            1 => Self::InitializeAccount,
-            2 => {
-                let amount = Self::unpack_amount(rest)?;
-                Self::Transfer { amount }
-            }
-            3 => {
-                let amount = Self::unpack_amount(rest)?;
-                Self::Burn { amount }
-            }
-            4 => {
-                let amount = Self::unpack_amount(rest)?;
-                Self::MintTo { amount }
-            }
-            5 => Self::Freeze,
-            6 => Self::Thaw,
-            7 => {
-                let (authority_type, rest) = rest.split_first().ok_or(InvalidInstruction)?;
-                let mut new_authority = None;
-                if !rest.is_empty() {
-                    new_authority = Some(Pubkey::new(rest));
-                }
-                Self::SetAuthority {
-                    authority_type: *authority_type,
-                    new_authority,
-                }
-            }
-            8 => {
-                let amount = Self::unpack_amount(rest)?;
-                Self::Stake { amount }
-            }
-            9 => {
-                let amount = Self::unpack_amount(rest)?;
-                Self::Unstake { amount }
-            }
-            10 => Self::UpgradeProgram,
-            _ => return Err(InvalidInstruction.into()),
-        })
+        Self::try_from_slice(input).map_err(|_| TokenError::InvalidInstruction.into())
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        borsh::to_vec(self).expect("serializing into a Vec cannot fail")
+    }
+}
+
+/// Encoding of an optional pubkey at the end of an instruction: its raw 32
+/// bytes, or nothing at all for `None`.
+mod trailing_pubkey {
+    use borsh::io::{Error, ErrorKind, Read, Result, Write};
+    use solana_program::pubkey::{Pubkey, PUBKEY_BYTES};
+
+    pub fn serialize<W: Write>(key: &Option<Pubkey>, writer: &mut W) -> Result<()> {
+        match key {
+            Some(key) => writer.write_all(key.as_ref()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn deserialize<R: Read>(reader: &mut R) -> Result<Option<Pubkey>> {
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest)?;
+        if rest.is_empty() {
+            return Ok(None);
+        }
+        let key = <[u8; PUBKEY_BYTES]>::try_from(rest.as_slice())
+            .map_err(|_| Error::new(ErrorKind::InvalidData, "expected nothing or a 32 byte pubkey"))?;
+        Ok(Some(Pubkey::new_from_array(key)))
+    }
+}
+
+pub fn initialize_mint(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    mint_authority: &Pubkey,
+    freeze_authority: Option<&Pubkey>,
+    decimals: u8,
+) -> Instruction {
+    let freeze_authority = freeze_authority.copied().unwrap_or_default();
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*mint_authority, false),
+            AccountMeta::new_readonly(freeze_authority, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::InitializeMint { decimals }.pack(),
+    }
+}
+
+pub fn initialize_account(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::InitializeAccount.pack(),
+    }
+}
+
+pub fn transfer(
+    program_id: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::Transfer { amount }.pack(),
+    }
+}
+
+pub fn burn(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::Burn { amount }.pack(),
+    }
+}
+
+pub fn mint_to(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    account: &Pubkey,
+    mint_authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::MintTo { amount }.pack(),
+    }
+}
+
+pub fn freeze(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    freeze_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*freeze_authority, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::Freeze.pack(),
+    }
+}
+
+pub fn thaw(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    freeze_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*freeze_authority, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::Thaw.pack(),
+    }
+}
+
+pub fn set_authority(
+    program_id: &Pubkey,
+    account_or_mint: &Pubkey,
+    new_authority: Option<&Pubkey>,
+    authority_type: u8,
+    current_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account_or_mint, false),
+            AccountMeta::new_readonly(*current_authority, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::SetAuthority {
+            authority_type,
+            new_authority: new_authority.copied(),
+        }
+        .pack(),
+    }
+}
+
+pub fn stake(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    staker: &Pubkey,
+    amount: u64,
+    operator: Option<&Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: stake_accounts(program_id, token_account, mint, staker, operator),
+        data: TokenInstruction::Stake { amount }.pack(),
+    }
+}
+
+pub fn unstake(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    staker: &Pubkey,
+    amount: u64,
+    operator: Option<&Pubkey>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: stake_accounts(program_id, token_account, mint, staker, operator),
+        data: TokenInstruction::Unstake { amount }.pack(),
+    }
+}
+
+fn stake_accounts(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    staker: &Pubkey,
+    operator: Option<&Pubkey>,
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new(find_stake_address(program_id, mint, staker).0, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new(find_vault_address(program_id, mint).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*staker, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(operator_account(operator));
+    accounts
+}
+
+/// The operator account following the other accounts of an instruction on a
+/// delegated position.
+fn operator_account(operator: Option<&Pubkey>) -> Option<AccountMeta> {
+    operator.map(|operator| AccountMeta::new(*operator, false))
+}
+
+pub fn upgrade_program(
+    program_id: &Pubkey,
+    buffer: &Pubkey,
+    spill: &Pubkey,
+    upgrade_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*program_id, false),
+            AccountMeta::new(*buffer, false),
+            AccountMeta::new(*spill, false),
+            AccountMeta::new_readonly(*upgrade_authority, true),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(bpf_loader_upgradeable::id(), false),
+        ],
+        data: TokenInstruction::UpgradeProgram.pack(),
+    }
+}
+
+pub fn initialize_token_info(
+    program_id: &Pubkey,
+    token_info: &Pubkey,
+    mint_authority: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*token_info, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+            AccountMeta::new_readonly(*mint, false),
+        ],
+        data: TokenInstruction::InitializeTokenInfo.pack(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_vesting_schedule(
+    program_id: &Pubkey,
+    vesting_account: &Pubkey,
+    token_info: &Pubkey,
+    mint_authority: &Pubkey,
+    beneficiary: &Pubkey,
+    allocation_type: AllocationType,
+    amount: u64,
+    start_time: i64,
+    cliff_time: i64,
+    end_time: i64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*vesting_account, false),
+            AccountMeta::new(*token_info, false),
+            AccountMeta::new_readonly(*mint_authority, true),
+        ],
+        data: TokenInstruction::CreateVestingSchedule {
+            beneficiary: *beneficiary,
+            allocation_type,
+            amount,
+            start_time,
+            cliff_time,
+            end_time,
+        }
+        .pack(),
+    }
+}
+
+pub fn release_vested_tokens(
+    program_id: &Pubkey,
+    vesting_account: &Pubkey,
+    beneficiary_token_account: &Pubkey,
+    source_token_account: &Pubkey,
+    source_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*vesting_account, false),
+            AccountMeta::new(*beneficiary_token_account, false),
+            AccountMeta::new(*source_token_account, false),
+            AccountMeta::new_readonly(*source_authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::ReleaseVestedTokens.pack(),
+    }
+}
+
+pub fn migrate_account(
+    program_id: &Pubkey,
+    account: &Pubkey,
+    token_info: &Pubkey,
+    admin: &Pubkey,
+    account_type: AccountType,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*token_info, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenInstruction::MigrateAccount { account_type }.pack(),
+    }
+}
+
+pub fn query_vested_amount(program_id: &Pubkey, vesting_account: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*vesting_account, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: TokenInstruction::QueryVestedAmount.pack(),
+    }
+}
+
+pub fn query_releasable_amount(program_id: &Pubkey, vesting_account: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*vesting_account, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: TokenInstruction::QueryReleasableAmount.pack(),
+    }
+}
+
+pub fn query_stake_info(program_id: &Pubkey, stake_account: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(*stake_account, false)],
+        data: TokenInstruction::QueryStakeInfo.pack(),
+    }
+}
+
+pub fn query_allocation_remaining(
+    program_id: &Pubkey,
+    token_info: &Pubkey,
+    allocation_type: AllocationType,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(*token_info, false)],
+        data: TokenInstruction::QueryAllocationRemaining { allocation_type }.pack(),
+    }
+}
+
+pub fn initialize_vault(program_id: &Pubkey, mint: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_vault_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::InitializeVault.pack(),
+    }
+}
+
+pub fn initialize_stake(program_id: &Pubkey, mint: &Pubkey, owner: &Pubkey, lock_tier: LockTier) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_stake_address(program_id, mint, owner).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenInstruction::InitializeStake { lock_tier }.pack(),
+    }
+}
+
+pub fn initialize_staking_pool(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    token_info: &Pubkey,
+    admin: &Pubkey,
+    reward_rate: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+            AccountMeta::new(find_reward_vault_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(find_vault_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_info, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::InitializeStakingPool { reward_rate }.pack(),
+    }
+}
+
+pub fn fund_rewards(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    token_info: &Pubkey,
+    source_token_account: &Pubkey,
+    admin: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+            AccountMeta::new(find_reward_vault_address(program_id, mint).0, false),
+            AccountMeta::new(*token_info, false),
+            AccountMeta::new(*source_token_account, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::FundRewards { amount }.pack(),
+    }
+}
+
+pub fn claim_rewards(
+    program_id: &Pubkey,
+    destination: &Pubkey,
+    mint: &Pubkey,
+    staker: &Pubkey,
+    operator: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(find_stake_address(program_id, mint, staker).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new(find_reward_vault_address(program_id, mint).0, false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*staker, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(operator_account(operator));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::ClaimRewards.pack(),
+    }
+}
+
+pub fn query_pending_rewards(program_id: &Pubkey, mint: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(find_stake_address(program_id, mint, owner).0, false),
+            AccountMeta::new_readonly(find_staking_pool_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: TokenInstruction::QueryPendingRewards.pack(),
+    }
+}
+
+pub fn initialize_staking_config(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    token_info: &Pubkey,
+    admin: &Pubkey,
+    params: StakingParams,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(find_staking_config_address(program_id, mint).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*token_info, false),
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if params.penalty_destination == PenaltyDestination::Treasury {
+        accounts.push(AccountMeta::new_readonly(params.treasury, false));
+    }
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::InitializeStakingConfig { params }.pack(),
+    }
+}
+
+pub fn update_staking_config(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    token_info: &Pubkey,
+    admin: &Pubkey,
+    params: StakingParams,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(find_staking_config_address(program_id, mint).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*token_info, false),
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    if params.penalty_destination == PenaltyDestination::Treasury {
+        accounts.push(AccountMeta::new_readonly(params.treasury, false));
     }
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::UpdateStakingConfig { params }.pack(),
+    }
+}
+
+pub fn extend_lock(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    staker: &Pubkey,
+    lock_tier: LockTier,
+    operator: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(find_stake_address(program_id, mint, staker).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*staker, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    accounts.extend(operator_account(operator));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::ExtendLock { lock_tier }.pack(),
+    }
+}
+
+pub fn request_unstake(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    staker: &Pubkey,
+    unbonding_id: u64,
+    amount: u64,
+    operator: Option<&Pubkey>,
+) -> Instruction {
+    let (stake_account, _) = find_stake_address(program_id, mint, staker);
+    let mut accounts = vec![
+        AccountMeta::new(stake_account, false),
+        AccountMeta::new(find_unbonding_address(program_id, &stake_account, unbonding_id).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(*staker, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(operator_account(operator));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::RequestUnstake { amount }.pack(),
+    }
+}
+
+pub fn withdraw_unbonded(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    staker: &Pubkey,
+    unbonding_id: u64,
+) -> Instruction {
+    let (stake_account, _) = find_stake_address(program_id, mint, staker);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(stake_account, false),
+            AccountMeta::new(find_unbonding_address(program_id, &stake_account, unbonding_id).0, false),
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new(find_vault_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*staker, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::WithdrawUnbonded.pack(),
+    }
+}
+
+pub fn cancel_unbonding(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    staker: &Pubkey,
+    unbonding_id: u64,
+    operator: Option<&Pubkey>,
+) -> Instruction {
+    let (stake_account, _) = find_stake_address(program_id, mint, staker);
+    let mut accounts = vec![
+        AccountMeta::new(stake_account, false),
+        AccountMeta::new(find_unbonding_address(program_id, &stake_account, unbonding_id).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(*staker, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    accounts.extend(operator_account(operator));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::CancelUnbonding.pack(),
+    }
+}
+
+pub fn emergency_unstake(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    penalty_account: &Pubkey,
+    staker: &Pubkey,
+    amount: u64,
+    operator: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(find_stake_address(program_id, mint, staker).0, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new(find_vault_address(program_id, mint).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+        AccountMeta::new(*mint, false),
+        AccountMeta::new(*penalty_account, false),
+        AccountMeta::new_readonly(*staker, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(operator_account(operator));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::EmergencyUnstake { amount }.pack(),
+    }
+}
+
+pub fn initialize_liquid_pool(program_id: &Pubkey, mint: &Pubkey, token_info: &Pubkey, admin: &Pubkey) -> Instruction {
+    let (liquid_pool, _) = find_liquid_pool_address(program_id, mint);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(liquid_pool, false),
+            AccountMeta::new(find_receipt_mint_address(program_id, mint).0, false),
+            AccountMeta::new(find_stake_address(program_id, mint, &liquid_pool).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_info, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::InitializeLiquidPool.pack(),
+    }
+}
+
+pub fn liquid_stake(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    receipt_account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: liquid_accounts(program_id, token_account, receipt_account, mint, owner),
+        data: TokenInstruction::LiquidStake { amount }.pack(),
+    }
+}
+
+pub fn liquid_unstake(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    receipt_account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    receipt_amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: liquid_accounts(program_id, token_account, receipt_account, mint, owner),
+        data: TokenInstruction::LiquidUnstake { receipt_amount }.pack(),
+    }
+}
+
+pub fn request_liquid_unstake(
+    program_id: &Pubkey,
+    receipt_account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    unbonding_id: u64,
+    receipt_amount: u64,
+) -> Instruction {
+    let (liquid_pool, _) = find_liquid_pool_address(program_id, mint);
+    let (stake_account, _) = find_stake_address(program_id, mint, &liquid_pool);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(liquid_pool, false),
+            AccountMeta::new(stake_account, false),
+            AccountMeta::new(find_unbonding_address(program_id, &stake_account, unbonding_id).0, false),
+            AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+            AccountMeta::new(find_vault_address(program_id, mint).0, false),
+            AccountMeta::new(find_reward_vault_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(find_receipt_mint_address(program_id, mint).0, false),
+            AccountMeta::new(*receipt_account, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::RequestLiquidUnstake { receipt_amount }.pack(),
+    }
+}
+
+pub fn withdraw_liquid_unbonded(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    unbonding_id: u64,
+) -> Instruction {
+    let (liquid_pool, _) = find_liquid_pool_address(program_id, mint);
+    let (stake_account, _) = find_stake_address(program_id, mint, &liquid_pool);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(liquid_pool, false),
+            AccountMeta::new(stake_account, false),
+            AccountMeta::new(find_unbonding_address(program_id, &stake_account, unbonding_id).0, false),
+            AccountMeta::new(*token_account, false),
+            AccountMeta::new(find_vault_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*owner, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::WithdrawLiquidUnbonded.pack(),
+    }
+}
+
+fn liquid_accounts(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    receipt_account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Vec<AccountMeta> {
+    let (liquid_pool, _) = find_liquid_pool_address(program_id, mint);
+    vec![
+        AccountMeta::new_readonly(liquid_pool, false),
+        AccountMeta::new(find_stake_address(program_id, mint, &liquid_pool).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+        AccountMeta::new(find_vault_address(program_id, mint).0, false),
+        AccountMeta::new(find_reward_vault_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(find_receipt_mint_address(program_id, mint).0, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new(*receipt_account, false),
+        AccountMeta::new_readonly(*owner, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]
+}
+
+/// Compounds the rewards of the position of `owner`, signed by `owner` or,
+/// for a position that opted into auto-compound, by nobody.
+pub fn compound_rewards(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    owner_signs: bool,
+    operator: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(find_stake_address(program_id, mint, owner).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+        AccountMeta::new(find_vault_address(program_id, mint).0, false),
+        AccountMeta::new(find_reward_vault_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*owner, owner_signs),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(operator_account(operator));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::CompoundRewards.pack(),
+    }
+}
+
+pub fn set_auto_compound(program_id: &Pubkey, mint: &Pubkey, staker: &Pubkey, enabled: bool) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_stake_address(program_id, mint, staker).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*staker, true),
+        ],
+        data: TokenInstruction::SetAutoCompound { enabled }.pack(),
+    }
+}
+
+pub fn initialize_locker(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    token_info: &Pubkey,
+    admin: &Pubkey,
+    max_lock_duration: i64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_locker_address(program_id, mint).0, false),
+            AccountMeta::new(find_locker_vault_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_info, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::InitializeLocker { max_lock_duration }.pack(),
+    }
+}
+
+/// Accounts of the vote lock instructions moving tokens, in the order of
+/// [`TokenInstruction::CreateVoteLock`].
+fn vote_lock_accounts(program_id: &Pubkey, token_account: &Pubkey, mint: &Pubkey, owner: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(find_vote_lock_address(program_id, mint, owner).0, false),
+        AccountMeta::new(find_locker_address(program_id, mint).0, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new(find_locker_vault_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(*owner, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]
+}
+
+pub fn create_vote_lock(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    unlock_time: i64,
+) -> Instruction {
+    let mut accounts = vote_lock_accounts(program_id, token_account, mint, owner);
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::CreateVoteLock { amount, unlock_time }.pack(),
+    }
+}
+
+pub fn increase_vote_lock_amount(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vote_lock_accounts(program_id, token_account, mint, owner),
+        data: TokenInstruction::IncreaseVoteLockAmount { amount }.pack(),
+    }
+}
+
+pub fn extend_vote_lock(program_id: &Pubkey, mint: &Pubkey, owner: &Pubkey, unlock_time: i64) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_vote_lock_address(program_id, mint, owner).0, false),
+            AccountMeta::new(find_locker_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: TokenInstruction::ExtendVoteLock { unlock_time }.pack(),
+    }
+}
+
+pub fn withdraw_vote_lock(program_id: &Pubkey, token_account: &Pubkey, mint: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vote_lock_accounts(program_id, token_account, mint, owner),
+        data: TokenInstruction::WithdrawVoteLock.pack(),
+    }
+}
+
+pub fn query_voting_power(program_id: &Pubkey, mint: &Pubkey, owner: &Pubkey, timestamp: i64) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(find_vote_lock_address(program_id, mint, owner).0, false),
+            AccountMeta::new_readonly(find_locker_address(program_id, mint).0, false),
+        ],
+        data: TokenInstruction::QueryVotingPower { timestamp }.pack(),
+    }
+}
+
+pub fn query_total_voting_power(program_id: &Pubkey, mint: &Pubkey, timestamp: i64) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(find_locker_address(program_id, mint).0, false)],
+        data: TokenInstruction::QueryTotalVotingPower { timestamp }.pack(),
+    }
+}
+
+pub fn register_operator(program_id: &Pubkey, mint: &Pubkey, authority: &Pubkey, commission_bps: u16) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_operator_address(program_id, mint, authority).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenInstruction::RegisterOperator { commission_bps }.pack(),
+    }
+}
+
+pub fn delegate_stake(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    staker: &Pubkey,
+    operator: &Pubkey,
+    current_operator: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(find_stake_address(program_id, mint, staker).0, false),
+        AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new(*operator, false),
+        AccountMeta::new_readonly(*staker, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    if let Some(current_operator) = current_operator {
+        accounts.push(AccountMeta::new(*current_operator, false));
+    }
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::DelegateStake.pack(),
+    }
+}
+
+pub fn undelegate_stake(program_id: &Pubkey, mint: &Pubkey, staker: &Pubkey, operator: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_stake_address(program_id, mint, staker).0, false),
+            AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(find_staking_config_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*operator, false),
+            AccountMeta::new_readonly(*staker, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: TokenInstruction::UndelegateStake.pack(),
+    }
+}
+
+pub fn claim_commission(
+    program_id: &Pubkey,
+    destination: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_operator_address(program_id, mint, authority).0, false),
+            AccountMeta::new(find_reward_vault_address(program_id, mint).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::ClaimCommission.pack(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_farm(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    token_info: &Pubkey,
+    staked_mint: &Pubkey,
+    reward_mint: &Pubkey,
+    admin: &Pubkey,
+    id: u64,
+    emission_rate: u64,
+    start_time: i64,
+    end_time: i64,
+) -> Instruction {
+    let (farm, _) = find_farm_address(program_id, mint, id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(farm, false),
+            AccountMeta::new(find_farm_vault_address(program_id, &farm).0, false),
+            AccountMeta::new(find_farm_reward_vault_address(program_id, &farm).0, false),
+            AccountMeta::new_readonly(*staked_mint, false),
+            AccountMeta::new_readonly(*reward_mint, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_info, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::CreateFarm {
+            id,
+            emission_rate,
+            start_time,
+            end_time,
+        }
+        .pack(),
+    }
+}
+
+pub fn fund_farm(
+    program_id: &Pubkey,
+    farm: &Pubkey,
+    source_token_account: &Pubkey,
+    reward_mint: &Pubkey,
+    funder: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*farm, false),
+            AccountMeta::new(find_farm_reward_vault_address(program_id, farm).0, false),
+            AccountMeta::new(*source_token_account, false),
+            AccountMeta::new_readonly(*reward_mint, false),
+            AccountMeta::new_readonly(*funder, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::FundFarm { amount }.pack(),
+    }
+}
+
+pub fn update_farm(
+    program_id: &Pubkey,
+    farm: &Pubkey,
+    mint: &Pubkey,
+    token_info: &Pubkey,
+    admin: &Pubkey,
+    emission_rate: u64,
+    end_time: i64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*farm, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_info, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: TokenInstruction::UpdateFarm { emission_rate, end_time }.pack(),
+    }
+}
+
+/// Accounts shared by [`farm_stake`] and [`farm_unstake`].
+fn farm_position_accounts(
+    program_id: &Pubkey,
+    farm: &Pubkey,
+    token_account: &Pubkey,
+    staked_mint: &Pubkey,
+    owner: &Pubkey,
+    owner_pays: bool,
+) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(find_farm_position_address(program_id, farm, owner).0, false),
+        AccountMeta::new(*farm, false),
+        AccountMeta::new(find_farm_vault_address(program_id, farm).0, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new_readonly(*staked_mint, false),
+        if owner_pays {
+            AccountMeta::new(*owner, true)
+        } else {
+            AccountMeta::new_readonly(*owner, true)
+        },
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]
+}
+
+pub fn farm_stake(
+    program_id: &Pubkey,
+    farm: &Pubkey,
+    token_account: &Pubkey,
+    staked_mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut accounts = farm_position_accounts(program_id, farm, token_account, staked_mint, owner, true);
+    accounts.push(AccountMeta::new_readonly(system_program::id(), false));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: TokenInstruction::FarmStake { amount }.pack(),
+    }
+}
+
+pub fn farm_unstake(
+    program_id: &Pubkey,
+    farm: &Pubkey,
+    token_account: &Pubkey,
+    staked_mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: farm_position_accounts(program_id, farm, token_account, staked_mint, owner, false),
+        data: TokenInstruction::FarmUnstake { amount }.pack(),
+    }
+}
+
+pub fn claim_farm_rewards(
+    program_id: &Pubkey,
+    farm: &Pubkey,
+    destination: &Pubkey,
+    reward_mint: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_farm_position_address(program_id, farm, owner).0, false),
+            AccountMeta::new(*farm, false),
+            AccountMeta::new(find_farm_reward_vault_address(program_id, farm).0, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*reward_mint, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: TokenInstruction::ClaimFarmRewards.pack(),
+    }
+}
+
+pub fn query_farm_rewards(program_id: &Pubkey, farm: &Pubkey, owner: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(find_farm_position_address(program_id, farm, owner).0, false),
+            AccountMeta::new_readonly(*farm, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: TokenInstruction::QueryFarmRewards.pack(),
+    }
+}
+
+pub fn initialize_emission_schedule(
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    token_info: &Pubkey,
+    admin: &Pubkey,
+    epoch_duration: i64,
+    initial_emission: u64,
+    decay_bps: u16,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_emission_schedule_address(program_id, mint).0, false),
+            AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_info, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: TokenInstruction::InitializeEmissionSchedule {
+            epoch_duration,
+            initial_emission,
+            decay_bps,
+        }
+        .pack(),
+    }
+}
+
+pub fn advance_epoch(program_id: &Pubkey, mint: &Pubkey, token_info: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(find_emission_schedule_address(program_id, mint).0, false),
+            AccountMeta::new(find_staking_pool_address(program_id, mint).0, false),
+            AccountMeta::new_readonly(*token_info, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: TokenInstruction::AdvanceEpoch.pack(),
+    }
+}
 
-    fn unpack_amount(input: &[u8]) -> Result<u64, ProgramError> {
-        let amount = input
-            .get(..8)
-            .and_then(|slice| slice.try_into().ok())
-            .map(u64::from_le_bytes)
-            .ok_or(TokenError::InvalidInstruction)?;
-        Ok(amount)
+pub fn query_emission_schedule(program_id: &Pubkey, mint: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(find_emission_schedule_address(program_id, mint).0, false)],
+        data: TokenInstruction::QueryEmissionSchedule.pack(),
     }
 }
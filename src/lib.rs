@@ -1,10 +1,12 @@
+#[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
 pub mod error;
+pub mod event;
+#[cfg(feature = "idl")]
+pub mod idl;
 pub mod instruction;
+pub mod migration;
 pub mod processor;
 pub mod state;
 pub mod token_info;
-
-use solana_program::program_error::ProgramError;
-use solana_program::pubkey::Pubkey;
-use solana_program::program_pack::Pack;
\ No newline at end of file
+pub mod validation;
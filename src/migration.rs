@@ -0,0 +1,821 @@
+//! Upgrades of program accounts stored in an older layout version.
+//!
+//! Version 0 is the layout written before accounts carried an
+//! [`AccountHeader`]. Those accounts have no type tag, so they are
+//! recognized by their exact size, which differs between account types and
+//! from every later layout. Later versions are read from the header.
+
+use borsh::BorshDeserialize;
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::{IsInitialized, Pack};
+use solana_program::pubkey::Pubkey;
+
+use crate::error::TokenError;
+use crate::state::{
+    find_reward_vault_address, find_vault_address, AccountHeader, AccountType, EmissionSchedule, Farm, FarmPosition,
+    LiquidPool, LockTier, Locker, Operator, ProgramAccount, StakeInfo, StakingConfig, StakingParams, StakingPool, UnbondingEntry, VestingSchedule, VoteLock,
+};
+use crate::token_info::TokenInfo;
+
+/// Headerless layouts, field for field as they were first deployed.
+pub mod v0 {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::pubkey::Pubkey;
+
+    use crate::instruction::AllocationType;
+
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct TokenInfo {
+        pub total_supply: u64,
+        pub team_allocation: u64,
+        pub investors_allocation: u64,
+        pub liquidity_reserve: u64,
+        pub development_reserve: u64,
+        pub community_rewards: u64,
+        pub strategic_reserve: u64,
+        pub mint_authority: Pubkey,
+        pub mint: Pubkey,
+    }
+
+    impl TokenInfo {
+        pub const LEN: usize = 8 * 7 + 32 * 2;
+    }
+
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct VestingSchedule {
+        pub beneficiary: Pubkey,
+        pub total_amount: u64,
+        pub released_amount: u64,
+        pub start_time: i64,
+        pub end_time: i64,
+        pub allocation_type: AllocationType,
+    }
+
+    impl VestingSchedule {
+        pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 1;
+    }
+
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakeInfo {
+        pub amount: u64,
+        pub start_time: i64,
+    }
+
+    impl StakeInfo {
+        pub const LEN: usize = 8 + 8;
+    }
+}
+
+/// First headered layouts, for the types that changed since.
+pub mod v1 {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::pubkey::Pubkey;
+
+    use crate::instruction::AllocationType;
+    use crate::state::AccountHeader;
+
+    /// Vesting schedule from before cliffs.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct VestingSchedule {
+        pub header: AccountHeader,
+        pub beneficiary: Pubkey,
+        pub total_amount: u64,
+        pub released_amount: u64,
+        pub start_time: i64,
+        pub end_time: i64,
+        pub allocation_type: AllocationType,
+    }
+
+    impl VestingSchedule {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 8 + 8 + 8 + 8 + 1;
+    }
+
+    /// Stake position from before owners and mints were recorded.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakeInfo {
+        pub header: AccountHeader,
+        pub amount: u64,
+        pub start_time: i64,
+    }
+
+    impl StakeInfo {
+        pub const LEN: usize = AccountHeader::LEN + 8 + 8;
+    }
+
+    /// Staking pool from before lock tiers, sharing rewards by amount.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakingPool {
+        pub header: AccountHeader,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub reward_vault_bump: u8,
+        pub total_staked: u64,
+        pub reward_rate: u64,
+        pub reward_per_token: u128,
+        pub undistributed_rewards: u64,
+        pub last_update_time: i64,
+    }
+
+    impl StakingPool {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 1 + 1 + 8 + 8 + 16 + 8 + 8;
+    }
+
+    /// Staking rules from before unbonding.
+    #[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakingParams {
+        pub min_stake: u64,
+        pub lock_duration: i64,
+        pub reward_rate: u64,
+        pub max_total_stake: u64,
+        pub paused: bool,
+    }
+
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakingConfig {
+        pub header: AccountHeader,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub params: StakingParams,
+    }
+
+    impl StakingConfig {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 1 + 8 + 8 + 8 + 8 + 1;
+    }
+
+    /// Operator from before it kept the bump of the reward vault.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct Operator {
+        pub header: AccountHeader,
+        pub mint: Pubkey,
+        pub authority: Pubkey,
+        pub bump: u8,
+        pub commission_bps: u16,
+        pub delegations: u64,
+        pub pending_commission: u64,
+        pub collected_commission: u64,
+        pub created_at: i64,
+    }
+
+    impl Operator {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 2 + 8 + 8 + 8 + 8;
+    }
+
+    /// Unbonding entry from before liquid pools could unbond.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct UnbondingEntry {
+        pub header: AccountHeader,
+        pub stake_account: Pubkey,
+        pub id: u64,
+        pub bump: u8,
+        pub amount: u64,
+        pub requested_at: i64,
+        pub withdrawable_at: i64,
+    }
+
+    impl UnbondingEntry {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 8 + 1 + 8 + 8 + 8;
+    }
+}
+
+pub mod v2 {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::pubkey::Pubkey;
+
+    use crate::state::AccountHeader;
+
+    /// Stake position from before rewards.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakeInfo {
+        pub header: AccountHeader,
+        pub owner: Pubkey,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub amount: u64,
+        pub start_time: i64,
+        pub created_at: i64,
+        pub updated_at: i64,
+    }
+
+    impl StakeInfo {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 8 + 8 + 8 + 8;
+    }
+
+    /// Staking rules from before early unstake penalties.
+    #[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakingParams {
+        pub min_stake: u64,
+        pub lock_duration: i64,
+        pub reward_rate: u64,
+        pub max_total_stake: u64,
+        pub paused: bool,
+        pub unbonding_period: i64,
+    }
+
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakingConfig {
+        pub header: AccountHeader,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub params: StakingParams,
+    }
+
+    impl StakingConfig {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 1 + 8 + 8 + 8 + 8 + 1 + 8;
+    }
+
+    /// Staking pool from before emission schedules.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakingPool {
+        pub header: AccountHeader,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub reward_vault_bump: u8,
+        pub total_staked: u64,
+        pub total_weight: u64,
+        pub reward_rate: u64,
+        pub reward_per_token: u128,
+        pub undistributed_rewards: u64,
+        pub last_update_time: i64,
+    }
+
+    impl StakingPool {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 1 + 1 + 8 + 8 + 8 + 16 + 8 + 8;
+    }
+}
+
+pub mod v3 {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::pubkey::Pubkey;
+
+    use crate::state::{AccountHeader, StakingParams};
+
+    /// Stake position from before lock tiers.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakeInfo {
+        pub header: AccountHeader,
+        pub owner: Pubkey,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub amount: u64,
+        pub start_time: i64,
+        pub created_at: i64,
+        pub updated_at: i64,
+        pub reward_per_token_paid: u128,
+        pub pending_rewards: u64,
+    }
+
+    impl StakeInfo {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 16 + 8;
+    }
+
+    /// Staking config from before it kept the bump of the vault.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakingConfig {
+        pub header: AccountHeader,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub params: StakingParams,
+    }
+
+    impl StakingConfig {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 1 + StakingParams::LEN;
+    }
+}
+
+pub mod v4 {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::pubkey::Pubkey;
+
+    use crate::state::{AccountHeader, LockTier};
+
+    /// Stake position from before unbonding.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakeInfo {
+        pub header: AccountHeader,
+        pub owner: Pubkey,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub amount: u64,
+        pub weight: u64,
+        pub lock_tier: LockTier,
+        pub start_time: i64,
+        pub unlock_time: i64,
+        pub created_at: i64,
+        pub updated_at: i64,
+        pub reward_per_token_paid: u128,
+        pub pending_rewards: u64,
+    }
+
+    impl StakeInfo {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 16 + 8;
+    }
+}
+
+pub mod v5 {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::pubkey::Pubkey;
+
+    use crate::state::{AccountHeader, LockTier};
+
+    /// Stake position from before auto-compounding.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakeInfo {
+        pub header: AccountHeader,
+        pub owner: Pubkey,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub amount: u64,
+        pub weight: u64,
+        pub lock_tier: LockTier,
+        pub start_time: i64,
+        pub unlock_time: i64,
+        pub created_at: i64,
+        pub updated_at: i64,
+        pub reward_per_token_paid: u128,
+        pub pending_rewards: u64,
+        pub unbonding_amount: u64,
+        pub next_unbonding_id: u64,
+    }
+
+    impl StakeInfo {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 8;
+    }
+}
+
+pub mod v6 {
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::pubkey::Pubkey;
+
+    use crate::state::{AccountHeader, LockTier};
+
+    /// Stake position from before delegation to operators.
+    #[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+    pub struct StakeInfo {
+        pub header: AccountHeader,
+        pub owner: Pubkey,
+        pub mint: Pubkey,
+        pub bump: u8,
+        pub amount: u64,
+        pub weight: u64,
+        pub lock_tier: LockTier,
+        pub start_time: i64,
+        pub unlock_time: i64,
+        pub created_at: i64,
+        pub updated_at: i64,
+        pub reward_per_token_paid: u128,
+        pub pending_rewards: u64,
+        pub unbonding_amount: u64,
+        pub next_unbonding_id: u64,
+        pub auto_compound: bool,
+    }
+
+    impl StakeInfo {
+        pub const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 8 + 1;
+    }
+}
+
+/// An account type that can be read from any of its historical layouts.
+pub trait Migrate: ProgramAccount + Pack + IsInitialized {
+    type V0: BorshDeserialize;
+    const V0_LEN: usize;
+
+    fn from_v0(old: Self::V0) -> Self;
+
+    /// Decodes `data` stored in headered layout `version`, older than the
+    /// current one.
+    fn from_version(version: u8, data: &[u8]) -> Result<Self, ProgramError> {
+        let _ = (version, data);
+        Err(TokenError::UnsupportedAccountVersion.into())
+    }
+}
+
+impl Migrate for TokenInfo {
+    type V0 = v0::TokenInfo;
+    const V0_LEN: usize = v0::TokenInfo::LEN;
+
+    fn from_v0(old: v0::TokenInfo) -> Self {
+        TokenInfo {
+            header: AccountHeader::new::<Self>(),
+            total_supply: old.total_supply,
+            team_allocation: old.team_allocation,
+            investors_allocation: old.investors_allocation,
+            liquidity_reserve: old.liquidity_reserve,
+            development_reserve: old.development_reserve,
+            community_rewards: old.community_rewards,
+            strategic_reserve: old.strategic_reserve,
+            mint_authority: old.mint_authority,
+            mint: old.mint,
+        }
+    }
+}
+
+impl Migrate for VestingSchedule {
+    type V0 = v0::VestingSchedule;
+    const V0_LEN: usize = v0::VestingSchedule::LEN;
+
+    // Schedules from before cliffs have their cliff at the start.
+    fn from_v0(old: v0::VestingSchedule) -> Self {
+        VestingSchedule {
+            header: AccountHeader::new::<Self>(),
+            beneficiary: old.beneficiary,
+            total_amount: old.total_amount,
+            released_amount: old.released_amount,
+            start_time: old.start_time,
+            cliff_time: old.start_time,
+            end_time: old.end_time,
+            allocation_type: old.allocation_type,
+        }
+    }
+
+    fn from_version(version: u8, data: &[u8]) -> Result<Self, ProgramError> {
+        match version {
+            1 => {
+                let old = v1::VestingSchedule::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+                Ok(VestingSchedule {
+                    header: AccountHeader::new::<Self>(),
+                    beneficiary: old.beneficiary,
+                    total_amount: old.total_amount,
+                    released_amount: old.released_amount,
+                    start_time: old.start_time,
+                    cliff_time: old.start_time,
+                    end_time: old.end_time,
+                    allocation_type: old.allocation_type,
+                })
+            }
+            _ => Err(TokenError::UnsupportedAccountVersion.into()),
+        }
+    }
+}
+
+impl Migrate for StakeInfo {
+    type V0 = v0::StakeInfo;
+    const V0_LEN: usize = v0::StakeInfo::LEN;
+
+    fn from_v0(old: v0::StakeInfo) -> Self {
+        legacy_stake(old.amount, old.start_time)
+    }
+
+    fn from_version(version: u8, data: &[u8]) -> Result<Self, ProgramError> {
+        match version {
+            1 => {
+                let old = v1::StakeInfo::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+                Ok(legacy_stake(old.amount, old.start_time))
+            }
+            // Version 2 stakes predate the pool, which only counts them once
+            // they are first touched.
+            2 => {
+                let old = v2::StakeInfo::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+                Ok(StakeInfo {
+                    header: AccountHeader::new::<StakeInfo>(),
+                    owner: old.owner,
+                    mint: old.mint,
+                    bump: old.bump,
+                    amount: old.amount,
+                    weight: 0,
+                    lock_tier: LockTier::Days30,
+                    start_time: old.start_time,
+                    unlock_time: old.start_time,
+                    created_at: old.created_at,
+                    updated_at: old.updated_at,
+                    reward_per_token_paid: 0,
+                    pending_rewards: 0,
+                    unbonding_amount: 0,
+                    next_unbonding_id: 0,
+                    auto_compound: false,
+                    operator: Pubkey::default(),
+                    commission_bps: 0,
+                    pending_commission: 0,
+                })
+            }
+            // Positions from before lock tiers weigh their amount, as in the
+            // pool total, and stay bound by the lock of the staking config
+            // only.
+            3 => {
+                let old = v3::StakeInfo::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+                Ok(StakeInfo {
+                    header: AccountHeader::new::<StakeInfo>(),
+                    owner: old.owner,
+                    mint: old.mint,
+                    bump: old.bump,
+                    amount: old.amount,
+                    weight: old.amount,
+                    lock_tier: LockTier::Days30,
+                    start_time: old.start_time,
+                    unlock_time: old.start_time,
+                    created_at: old.created_at,
+                    updated_at: old.updated_at,
+                    reward_per_token_paid: old.reward_per_token_paid,
+                    pending_rewards: old.pending_rewards,
+                    unbonding_amount: 0,
+                    next_unbonding_id: 0,
+                    auto_compound: false,
+                    operator: Pubkey::default(),
+                    commission_bps: 0,
+                    pending_commission: 0,
+                })
+            }
+            4 => {
+                let old = v4::StakeInfo::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+                Ok(StakeInfo {
+                    header: AccountHeader::new::<StakeInfo>(),
+                    owner: old.owner,
+                    mint: old.mint,
+                    bump: old.bump,
+                    amount: old.amount,
+                    weight: old.weight,
+                    lock_tier: old.lock_tier,
+                    start_time: old.start_time,
+                    unlock_time: old.unlock_time,
+                    created_at: old.created_at,
+                    updated_at: old.updated_at,
+                    reward_per_token_paid: old.reward_per_token_paid,
+                    pending_rewards: old.pending_rewards,
+                    unbonding_amount: 0,
+                    next_unbonding_id: 0,
+                    auto_compound: false,
+                    operator: Pubkey::default(),
+                    commission_bps: 0,
+                    pending_commission: 0,
+                })
+            }
+            5 => {
+                let old = v5::StakeInfo::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+                Ok(StakeInfo {
+                    header: AccountHeader::new::<StakeInfo>(),
+                    owner: old.owner,
+                    mint: old.mint,
+                    bump: old.bump,
+                    amount: old.amount,
+                    weight: old.weight,
+                    lock_tier: old.lock_tier,
+                    start_time: old.start_time,
+                    unlock_time: old.unlock_time,
+                    created_at: old.created_at,
+                    updated_at: old.updated_at,
+                    reward_per_token_paid: old.reward_per_token_paid,
+                    pending_rewards: old.pending_rewards,
+                    unbonding_amount: old.unbonding_amount,
+                    next_unbonding_id: old.next_unbonding_id,
+                    auto_compound: false,
+                    operator: Pubkey::default(),
+                    commission_bps: 0,
+                    pending_commission: 0,
+                })
+            }
+            6 => {
+                let old = v6::StakeInfo::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+                Ok(StakeInfo {
+                    header: AccountHeader::new::<StakeInfo>(),
+                    owner: old.owner,
+                    mint: old.mint,
+                    bump: old.bump,
+                    amount: old.amount,
+                    weight: old.weight,
+                    lock_tier: old.lock_tier,
+                    start_time: old.start_time,
+                    unlock_time: old.unlock_time,
+                    created_at: old.created_at,
+                    updated_at: old.updated_at,
+                    reward_per_token_paid: old.reward_per_token_paid,
+                    pending_rewards: old.pending_rewards,
+                    unbonding_amount: old.unbonding_amount,
+                    next_unbonding_id: old.next_unbonding_id,
+                    auto_compound: old.auto_compound,
+                    operator: Pubkey::default(),
+                    commission_bps: 0,
+                    pending_commission: 0,
+                })
+            }
+            _ => Err(TokenError::UnsupportedAccountVersion.into()),
+        }
+    }
+}
+
+/// A position from before owners were recorded, with no owner or mint.
+/// Like every position from before the pool it weighs nothing until first
+/// touched.
+fn legacy_stake(amount: u64, start_time: i64) -> StakeInfo {
+    StakeInfo {
+        header: AccountHeader::new::<StakeInfo>(),
+        owner: Pubkey::default(),
+        mint: Pubkey::default(),
+        bump: 0,
+        amount,
+        weight: 0,
+        lock_tier: LockTier::Days30,
+        start_time,
+        unlock_time: start_time,
+        created_at: start_time,
+        updated_at: start_time,
+        reward_per_token_paid: 0,
+        pending_rewards: 0,
+        unbonding_amount: 0,
+        next_unbonding_id: 0,
+        auto_compound: false,
+        operator: Pubkey::default(),
+        commission_bps: 0,
+        pending_commission: 0,
+    }
+}
+
+/// Decodes a `T` stored in any supported layout, converted to the current
+/// one, along with the version it was stored in.
+pub fn unpack_any_version<T: Migrate>(data: &[u8]) -> Result<(T, u8), ProgramError> {
+    if data.len() == T::V0_LEN {
+        if data.iter().all(|&byte| byte == 0) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        let old = T::V0::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+        return Ok((T::from_v0(old), 0));
+    }
+    let header = AccountHeader::unpack(data)?;
+    if header.account_type != T::ACCOUNT_TYPE {
+        return Err(TokenError::InvalidAccountType.into());
+    }
+    if header.version != T::VERSION {
+        return Ok((T::from_version(header.version, data)?, header.version));
+    }
+    Ok((T::unpack(data)?, T::VERSION))
+}
+
+/// Rewrites `data`, holding an account of `account_type` owned by
+/// `program_id`, in the current layout. Returns `None` when it already is.
+pub fn migrate(program_id: &Pubkey, account_type: AccountType, data: &[u8]) -> Result<Option<Vec<u8>>, ProgramError> {
+    match account_type {
+        AccountType::TokenInfo => migrate_as::<TokenInfo>(data),
+        AccountType::VestingSchedule => migrate_as::<VestingSchedule>(data),
+        AccountType::StakeInfo => migrate_as::<StakeInfo>(data),
+        AccountType::StakingPool => migrate_staking_pool(data),
+        AccountType::StakingConfig => migrate_staking_config(program_id, data),
+        AccountType::UnbondingEntry => migrate_unbonding_entry(data),
+        // Liquid pools only exist in their first layout.
+        AccountType::LiquidPool => LiquidPool::unpack(data).map(|_| None),
+        AccountType::Locker => Locker::unpack(data).map(|_| None),
+        AccountType::VoteLock => VoteLock::unpack(data).map(|_| None),
+        AccountType::Farm => Farm::unpack(data).map(|_| None),
+        AccountType::FarmPosition => FarmPosition::unpack(data).map(|_| None),
+        AccountType::EmissionSchedule => EmissionSchedule::unpack(data).map(|_| None),
+        AccountType::Operator => migrate_operator(program_id, data),
+        AccountType::Uninitialized => Err(TokenError::InvalidAccountType.into()),
+    }
+}
+
+/// Pools, configs, operators and unbonding entries were never headerless, so unlike the
+/// [`Migrate`] types they only have headered versions to upgrade from.
+fn migrate_staking_pool(data: &[u8]) -> Result<Option<Vec<u8>>, ProgramError> {
+    let header = AccountHeader::unpack(data)?;
+    if header.account_type != AccountType::StakingPool {
+        return Err(TokenError::InvalidAccountType.into());
+    }
+    // Every position weighed its amount before lock tiers, and no pool was
+    // on an emission schedule before they existed.
+    let old = match header.version {
+        1 => {
+            let old = v1::StakingPool::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+            v2::StakingPool {
+                header: old.header,
+                mint: old.mint,
+                bump: old.bump,
+                reward_vault_bump: old.reward_vault_bump,
+                total_staked: old.total_staked,
+                total_weight: old.total_staked,
+                reward_rate: old.reward_rate,
+                reward_per_token: old.reward_per_token,
+                undistributed_rewards: old.undistributed_rewards,
+                last_update_time: old.last_update_time,
+            }
+        }
+        2 => v2::StakingPool::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?,
+        _ => return StakingPool::unpack(data).map(|_| None),
+    };
+    let pool = StakingPool {
+        header: AccountHeader::new::<StakingPool>(),
+        mint: old.mint,
+        bump: old.bump,
+        reward_vault_bump: old.reward_vault_bump,
+        total_staked: old.total_staked,
+        total_weight: old.total_weight,
+        reward_rate: old.reward_rate,
+        reward_per_token: old.reward_per_token,
+        undistributed_rewards: old.undistributed_rewards,
+        last_update_time: old.last_update_time,
+        emission_end: 0,
+    };
+    let mut migrated = vec![0; StakingPool::LEN];
+    StakingPool::pack(pool, &mut migrated)?;
+    Ok(Some(migrated))
+}
+
+fn migrate_staking_config(program_id: &Pubkey, data: &[u8]) -> Result<Option<Vec<u8>>, ProgramError> {
+    let header = AccountHeader::unpack(data)?;
+    if header.account_type != AccountType::StakingConfig {
+        return Err(TokenError::InvalidAccountType.into());
+    }
+    // Without an unbonding period unstaking stays immediate, and without a
+    // penalty curve locked positions stay locked, as they were.
+    let (mint, bump, params) = match header.version {
+        1 => {
+            let old = v1::StakingConfig::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+            let params = StakingParams {
+                min_stake: old.params.min_stake,
+                lock_duration: old.params.lock_duration,
+                reward_rate: old.params.reward_rate,
+                max_total_stake: old.params.max_total_stake,
+                paused: old.params.paused,
+                ..StakingParams::default()
+            };
+            (old.mint, old.bump, params)
+        }
+        2 => {
+            let old = v2::StakingConfig::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+            let params = StakingParams {
+                min_stake: old.params.min_stake,
+                lock_duration: old.params.lock_duration,
+                reward_rate: old.params.reward_rate,
+                max_total_stake: old.params.max_total_stake,
+                paused: old.params.paused,
+                unbonding_period: old.params.unbonding_period,
+                ..StakingParams::default()
+            };
+            (old.mint, old.bump, params)
+        }
+        3 => {
+            let old = v3::StakingConfig::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+            (old.mint, old.bump, old.params)
+        }
+        _ => return StakingConfig::unpack(data).map(|_| None),
+    };
+    let config = StakingConfig {
+        header: AccountHeader::new::<StakingConfig>(),
+        mint,
+        bump,
+        vault_bump: find_vault_address(program_id, &mint).1,
+        params,
+    };
+    let mut migrated = vec![0; StakingConfig::LEN];
+    StakingConfig::pack(config, &mut migrated)?;
+    Ok(Some(migrated))
+}
+
+/// Operators only gained the reward vault bump, which is derived again.
+fn migrate_operator(program_id: &Pubkey, data: &[u8]) -> Result<Option<Vec<u8>>, ProgramError> {
+    let header = AccountHeader::unpack(data)?;
+    if header.account_type != AccountType::Operator {
+        return Err(TokenError::InvalidAccountType.into());
+    }
+    if header.version != 1 {
+        return Operator::unpack(data).map(|_| None);
+    }
+    let old = v1::Operator::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+    let operator = Operator {
+        header: AccountHeader::new::<Operator>(),
+        mint: old.mint,
+        authority: old.authority,
+        bump: old.bump,
+        reward_vault_bump: find_reward_vault_address(program_id, &old.mint).1,
+        commission_bps: old.commission_bps,
+        delegations: old.delegations,
+        pending_commission: old.pending_commission,
+        collected_commission: old.collected_commission,
+        created_at: old.created_at,
+    };
+    let mut migrated = vec![0; Operator::LEN];
+    Operator::pack(operator, &mut migrated)?;
+    Ok(Some(migrated))
+}
+
+fn migrate_unbonding_entry(data: &[u8]) -> Result<Option<Vec<u8>>, ProgramError> {
+    let header = AccountHeader::unpack(data)?;
+    if header.account_type != AccountType::UnbondingEntry {
+        return Err(TokenError::InvalidAccountType.into());
+    }
+    if header.version != 1 {
+        return UnbondingEntry::unpack(data).map(|_| None);
+    }
+    // Only stake positions unbonded, so every entry goes to the owner of
+    // its position.
+    let old = v1::UnbondingEntry::try_from_slice(data).map_err(|_| ProgramError::InvalidAccountData)?;
+    let entry = UnbondingEntry {
+        header: AccountHeader::new::<UnbondingEntry>(),
+        stake_account: old.stake_account,
+        owner: Pubkey::default(),
+        id: old.id,
+        bump: old.bump,
+        amount: old.amount,
+        requested_at: old.requested_at,
+        withdrawable_at: old.withdrawable_at,
+    };
+    let mut migrated = vec![0; UnbondingEntry::LEN];
+    UnbondingEntry::pack(entry, &mut migrated)?;
+    Ok(Some(migrated))
+}
+
+fn migrate_as<T: Migrate>(data: &[u8]) -> Result<Option<Vec<u8>>, ProgramError> {
+    let (account, version) = unpack_any_version::<T>(data)?;
+    if version == T::VERSION {
+        return Ok(None);
+    }
+    let mut migrated = vec![0; T::LEN];
+    T::pack(account, &mut migrated)?;
+    Ok(Some(migrated))
+}
@@ -5,32 +5,50 @@ use solana_program::{
     pubkey::Pubkey,
     program_pack::Pack,
     sysvar::clock::Clock,
-    program::invoke,
+    program::{invoke, invoke_signed, set_return_data},
     rent::Rent,
+    system_instruction,
     sysvar::Sysvar,
-    msg,
 };
 
-use solana_program::program_pack::IsInitialized;
-use solana_program::account_info::next_account_info;
+use spl_token::instruction::AuthorityType;
 use spl_token::state::{Mint, Account as TokenAccount};
-use crate::state::{StakeInfo, VestingSchedule};
+use crate::state::{
+    find_farm_address, find_reward_vault_address, find_unbonding_address, find_vault_address, is_uninitialized,
+    AccountHeader, AccountType, EmissionSchedule,
+    EpochEmission, Farm, FarmPosition, EMISSION_SCHEDULE_SEED, MAX_EPOCHS_PER_CRANK, MIN_EPOCH_DURATION,
+    LiquidPool, LockTier, Locker, Operator, PenaltyDestination, StakeInfo, VoteLock, LIQUID_POOL_SEED, LOCKER_SEED, LOCKER_VAULT_SEED,
+    MAX_VOTE_LOCK_WEEKS, OPERATOR_SEED, FARM_POSITION_SEED, FARM_REWARD_VAULT_SEED, FARM_SEED, FARM_VAULT_SEED, RECEIPT_MINT_SEED, VOTE_LOCK_SEED, VOTE_LOCK_WEEK,
+    StakingConfig, StakingParams, StakingPool, UnbondingEntry, VestingSchedule, BASIS_POINTS, REWARD_VAULT_SEED,
+    STAKE_SEED, STAKING_CONFIG_SEED, STAKING_POOL_SEED, UNBONDING_SEED, VAULT_SEED,
+};
+use crate::migration;
 use crate::instruction::{TokenInstruction, AllocationType};
 use crate::error::TokenError;
-use crate::token_info::TokenInfo;
+use crate::event::TokenEvent;
+use crate::token_info::{TokenInfo, TOTAL_SUPPLY};
+use crate::validation::{
+    BurnAccounts, CancelUnbondingAccounts, ClaimRewardsAccounts, CompoundRewardsAccounts, EmergencyUnstakeAccounts, ExtendLockAccounts, RequestUnstakeAccounts,
+    WithdrawUnbondedAccounts, FundRewardsAccounts, InitializeLiquidPoolAccounts, InitializeStakingConfigAccounts,
+    LiquidStakeAccounts, SetAutoCompoundAccounts, InitializeLockerAccounts, VoteLockAccounts, ExtendVoteLockAccounts,
+    QueryVotingPowerAccounts, QueryTotalVotingPowerAccounts, RegisterOperatorAccounts, DelegateStakeAccounts,
+    ClaimCommissionAccounts, CreateFarmAccounts, FundFarmAccounts, FarmPositionAccounts, ClaimFarmRewardsAccounts,
+    QueryFarmRewardsAccounts, InitializeEmissionScheduleAccounts, AdvanceEpochAccounts, QueryEmissionScheduleAccounts,
+    UndelegateStakeAccounts, RequestLiquidUnstakeAccounts, WithdrawLiquidUnbondedAccounts, UpdateFarmAccounts,
+    InitializeStakingPoolAccounts, UpdateStakingConfigAccounts,
+    QueryPendingRewardsAccounts, CreateVestingScheduleAccounts, FreezeAccounts, InitializeAccountAccounts,
+    InitializeMintAccounts, InitializeStakeAccounts, InitializeTokenInfoAccounts, InitializeVaultAccounts,
+    MigrateAccountAccounts, MintToAccounts,
+    QueryAllocationRemainingAccounts, QueryStakeInfoAccounts, QueryVestingAccounts,
+    ReleaseVestedTokensAccounts, SetAuthorityAccounts, StakeAccounts, TransferAccounts,
+    UpgradeProgramAccounts,
+};
 use solana_program::program_option::COption;
 use solana_program::bpf_loader_upgradeable;
 
-use std::convert::TryFrom;
 
 pub struct Processor;
 
-impl IsInitialized for TokenInfo {
-    fn is_initialized(&self) -> bool {
-        self.total_supply != 0
-    }
-}
-
 impl Processor {
 
     pub fn process(
@@ -43,35 +61,35 @@ impl Processor {
         match instruction {
             TokenInstruction::InitializeMint { decimals } => {
                 solana_program::msg!("Instruction: Initialize Mint");
-                Self::process_initialize_mint(accounts, decimals, program_id)
+                Self::process_initialize_mint(accounts, decimals)
             }
             TokenInstruction::InitializeAccount => {
                 solana_program::msg!("Instruction: Initialize Account");
-                Self::process_initialize_account(accounts, program_id)
+                Self::process_initialize_account(accounts)
             }
             TokenInstruction::Transfer { amount } => {
                 solana_program::msg!("Instruction: Transfer");
-                Self::process_transfer(accounts, amount, program_id)
+                Self::process_transfer(accounts, amount)
             }
             TokenInstruction::Burn { amount } => {
                 solana_program::msg!("Instruction: Burn");
-                Self::process_burn(accounts, amount, program_id)
+                Self::process_burn(accounts, amount)
             }
             TokenInstruction::MintTo { amount } => {
                 solana_program::msg!("Instruction: Mint To");
-                Self::process_mint_to(accounts, amount, program_id)
+                Self::process_mint_to(accounts, amount)
             }
             TokenInstruction::Freeze => {
                 solana_program::msg!("Instruction: Freeze");
-                Self::process_freeze(accounts, program_id)
+                Self::process_freeze(accounts)
             }
             TokenInstruction::Thaw => {
                 solana_program::msg!("Instruction: Thaw");
-                Self::process_thaw(accounts, program_id)
+                Self::process_thaw(accounts)
             }
             TokenInstruction::SetAuthority { authority_type, new_authority } => {
                 solana_program::msg!("Instruction: Set Authority");
-                Self::process_set_authority(accounts, authority_type, new_authority, program_id)
+                Self::process_set_authority(accounts, authority_type, new_authority)
             }
             TokenInstruction::Stake { amount } => {
                 solana_program::msg!("Instruction: Stake");
@@ -89,14 +107,229 @@ impl Processor {
                 solana_program::msg!("Instruction: Initialize Token Info");
                 Self::process_initialize_token_info(accounts, program_id)
             },
-            TokenInstruction::CreateVestingSchedule { beneficiary, allocation_type, amount, start_time, end_time } => {
+            TokenInstruction::CreateVestingSchedule {
+                beneficiary,
+                allocation_type,
+                amount,
+                start_time,
+                cliff_time,
+                end_time,
+            } => {
                 solana_program::msg!("Instruction: Create Vesting Schedule");
-                Self::process_create_vesting_schedule(accounts, beneficiary, allocation_type, amount, start_time, end_time, program_id)
+                Self::process_create_vesting_schedule(
+                    accounts,
+                    beneficiary,
+                    allocation_type,
+                    amount,
+                    start_time,
+                    cliff_time,
+                    end_time,
+                    program_id,
+                )
             },
             TokenInstruction::ReleaseVestedTokens => {
                 solana_program::msg!("Instruction: Release Vested Tokens");
                 Self::process_release_vested_tokens(accounts, program_id)
             },
+            TokenInstruction::MigrateAccount { account_type } => {
+                solana_program::msg!("Instruction: Migrate Account");
+                Self::process_migrate_account(accounts, account_type, program_id)
+            },
+            TokenInstruction::QueryVestedAmount => {
+                solana_program::msg!("Instruction: Query Vested Amount");
+                Self::process_query_vesting(accounts, program_id, VestingSchedule::vested_amount)
+            },
+            TokenInstruction::QueryReleasableAmount => {
+                solana_program::msg!("Instruction: Query Releasable Amount");
+                Self::process_query_vesting(accounts, program_id, VestingSchedule::releasable_amount)
+            },
+            TokenInstruction::QueryStakeInfo => {
+                solana_program::msg!("Instruction: Query Stake Info");
+                Self::process_query_stake_info(accounts, program_id)
+            },
+            TokenInstruction::QueryAllocationRemaining { allocation_type } => {
+                solana_program::msg!("Instruction: Query Allocation Remaining");
+                Self::process_query_allocation_remaining(accounts, allocation_type, program_id)
+            },
+            TokenInstruction::InitializeVault => {
+                solana_program::msg!("Instruction: Initialize Vault");
+                Self::process_initialize_vault(accounts, program_id)
+            },
+            TokenInstruction::InitializeStake { lock_tier } => {
+                solana_program::msg!("Instruction: Initialize Stake");
+                Self::process_initialize_stake(accounts, lock_tier, program_id)
+            },
+            TokenInstruction::InitializeStakingPool { reward_rate } => {
+                solana_program::msg!("Instruction: Initialize Staking Pool");
+                Self::process_initialize_staking_pool(accounts, reward_rate, program_id)
+            },
+            TokenInstruction::FundRewards { amount } => {
+                solana_program::msg!("Instruction: Fund Rewards");
+                Self::process_fund_rewards(accounts, amount, program_id)
+            },
+            TokenInstruction::ClaimRewards => {
+                solana_program::msg!("Instruction: Claim Rewards");
+                Self::process_claim_rewards(accounts, program_id)
+            },
+            TokenInstruction::QueryPendingRewards => {
+                solana_program::msg!("Instruction: Query Pending Rewards");
+                Self::process_query_pending_rewards(accounts, program_id)
+            },
+            TokenInstruction::InitializeStakingConfig { params } => {
+                solana_program::msg!("Instruction: Initialize Staking Config");
+                Self::process_initialize_staking_config(accounts, params, program_id)
+            },
+            TokenInstruction::UpdateStakingConfig { params } => {
+                solana_program::msg!("Instruction: Update Staking Config");
+                Self::process_update_staking_config(accounts, params, program_id)
+            },
+            TokenInstruction::ExtendLock { lock_tier } => {
+                solana_program::msg!("Instruction: Extend Lock");
+                Self::process_extend_lock(accounts, lock_tier, program_id)
+            },
+            TokenInstruction::RequestUnstake { amount } => {
+                solana_program::msg!("Instruction: Request Unstake");
+                Self::process_request_unstake(accounts, amount, program_id)
+            },
+            TokenInstruction::WithdrawUnbonded => {
+                solana_program::msg!("Instruction: Withdraw Unbonded");
+                Self::process_withdraw_unbonded(accounts, program_id)
+            },
+            TokenInstruction::CancelUnbonding => {
+                solana_program::msg!("Instruction: Cancel Unbonding");
+                Self::process_cancel_unbonding(accounts, program_id)
+            },
+            TokenInstruction::EmergencyUnstake { amount } => {
+                solana_program::msg!("Instruction: Emergency Unstake");
+                Self::process_emergency_unstake(accounts, amount, program_id)
+            },
+            TokenInstruction::InitializeLiquidPool => {
+                solana_program::msg!("Instruction: Initialize Liquid Pool");
+                Self::process_initialize_liquid_pool(accounts, program_id)
+            },
+            TokenInstruction::LiquidStake { amount } => {
+                solana_program::msg!("Instruction: Liquid Stake");
+                Self::process_liquid_stake(accounts, amount, program_id)
+            },
+            TokenInstruction::LiquidUnstake { receipt_amount } => {
+                solana_program::msg!("Instruction: Liquid Unstake");
+                Self::process_liquid_unstake(accounts, receipt_amount, program_id)
+            },
+            TokenInstruction::CompoundRewards => {
+                solana_program::msg!("Instruction: Compound Rewards");
+                Self::process_compound_rewards(accounts, program_id)
+            },
+            TokenInstruction::SetAutoCompound { enabled } => {
+                solana_program::msg!("Instruction: Set Auto Compound");
+                Self::process_set_auto_compound(accounts, enabled, program_id)
+            },
+            TokenInstruction::InitializeLocker { max_lock_duration } => {
+                solana_program::msg!("Instruction: Initialize Locker");
+                Self::process_initialize_locker(accounts, max_lock_duration, program_id)
+            },
+            TokenInstruction::CreateVoteLock { amount, unlock_time } => {
+                solana_program::msg!("Instruction: Create Vote Lock");
+                Self::process_create_vote_lock(accounts, amount, unlock_time, program_id)
+            },
+            TokenInstruction::IncreaseVoteLockAmount { amount } => {
+                solana_program::msg!("Instruction: Increase Vote Lock Amount");
+                Self::process_increase_vote_lock_amount(accounts, amount, program_id)
+            },
+            TokenInstruction::ExtendVoteLock { unlock_time } => {
+                solana_program::msg!("Instruction: Extend Vote Lock");
+                Self::process_extend_vote_lock(accounts, unlock_time, program_id)
+            },
+            TokenInstruction::WithdrawVoteLock => {
+                solana_program::msg!("Instruction: Withdraw Vote Lock");
+                Self::process_withdraw_vote_lock(accounts, program_id)
+            },
+            TokenInstruction::QueryVotingPower { timestamp } => {
+                solana_program::msg!("Instruction: Query Voting Power");
+                Self::process_query_voting_power(accounts, timestamp, program_id)
+            },
+            TokenInstruction::QueryTotalVotingPower { timestamp } => {
+                solana_program::msg!("Instruction: Query Total Voting Power");
+                Self::process_query_total_voting_power(accounts, timestamp, program_id)
+            },
+            TokenInstruction::RegisterOperator { commission_bps } => {
+                solana_program::msg!("Instruction: Register Operator");
+                Self::process_register_operator(accounts, commission_bps, program_id)
+            },
+            TokenInstruction::DelegateStake => {
+                solana_program::msg!("Instruction: Delegate Stake");
+                Self::process_delegate_stake(accounts, program_id)
+            },
+            TokenInstruction::ClaimCommission => {
+                solana_program::msg!("Instruction: Claim Commission");
+                Self::process_claim_commission(accounts, program_id)
+            },
+            TokenInstruction::CreateFarm {
+                id,
+                emission_rate,
+                start_time,
+                end_time,
+            } => {
+                solana_program::msg!("Instruction: Create Farm");
+                Self::process_create_farm(accounts, id, emission_rate, start_time, end_time, program_id)
+            },
+            TokenInstruction::FundFarm { amount } => {
+                solana_program::msg!("Instruction: Fund Farm");
+                Self::process_fund_farm(accounts, amount, program_id)
+            },
+            TokenInstruction::FarmStake { amount } => {
+                solana_program::msg!("Instruction: Farm Stake");
+                Self::process_farm_stake(accounts, amount, program_id)
+            },
+            TokenInstruction::FarmUnstake { amount } => {
+                solana_program::msg!("Instruction: Farm Unstake");
+                Self::process_farm_unstake(accounts, amount, program_id)
+            },
+            TokenInstruction::ClaimFarmRewards => {
+                solana_program::msg!("Instruction: Claim Farm Rewards");
+                Self::process_claim_farm_rewards(accounts, program_id)
+            },
+            TokenInstruction::QueryFarmRewards => {
+                solana_program::msg!("Instruction: Query Farm Rewards");
+                Self::process_query_farm_rewards(accounts, program_id)
+            },
+            TokenInstruction::InitializeEmissionSchedule {
+                epoch_duration,
+                initial_emission,
+                decay_bps,
+            } => {
+                solana_program::msg!("Instruction: Initialize Emission Schedule");
+                Self::process_initialize_emission_schedule(
+                    accounts,
+                    epoch_duration,
+                    initial_emission,
+                    decay_bps,
+                    program_id,
+                )
+            },
+            TokenInstruction::AdvanceEpoch => {
+                solana_program::msg!("Instruction: Advance Epoch");
+                Self::process_advance_epoch(accounts, program_id)
+            },
+            TokenInstruction::QueryEmissionSchedule => {
+                solana_program::msg!("Instruction: Query Emission Schedule");
+                Self::process_query_emission_schedule(accounts, program_id)
+            },
+            TokenInstruction::UndelegateStake => {
+                solana_program::msg!("Instruction: Undelegate Stake");
+                Self::process_undelegate_stake(accounts, program_id)
+            },
+            TokenInstruction::RequestLiquidUnstake { receipt_amount } => {
+                solana_program::msg!("Instruction: Request Liquid Unstake");
+                Self::process_request_liquid_unstake(accounts, receipt_amount, program_id)
+            },
+            TokenInstruction::WithdrawLiquidUnbonded => {
+                solana_program::msg!("Instruction: Withdraw Liquid Unbonded");
+                Self::process_withdraw_liquid_unbonded(accounts, program_id)
+            },
+            TokenInstruction::UpdateFarm { emission_rate, end_time } => {
+                solana_program::msg!("Instruction: Update Farm");
+                Self::process_update_farm(accounts, emission_rate, end_time, program_id)
+            },
             
         }
     }
@@ -104,22 +337,19 @@ impl Processor {
     fn process_initialize_mint(
         accounts: &[AccountInfo],
         decimals: u8,
-        program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let mint_account = next_account_info(account_info_iter)?;
-        let mint_authority = next_account_info(account_info_iter)?;
-        let freeze_authority = next_account_info(account_info_iter)?;
-        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let InitializeMintAccounts {
+            mint: mint_account,
+            mint_authority,
+            freeze_authority,
+            rent: rent_info,
+        } = InitializeMintAccounts::load(accounts)?;
+        let rent = &Rent::from_account_info(rent_info)?;
     
         if !rent.is_exempt(mint_account.lamports(), mint_account.data_len()) {
             return Err(TokenError::NotRentExempt.into());
         }
     
-        if mint_account.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId.into());
-        }
-    
         let freeze_authority_option = if freeze_authority.key != &Pubkey::default() {
             Some(freeze_authority.key)
         } else {
@@ -135,29 +365,25 @@ impl Processor {
                 freeze_authority_option,
                 decimals,
             )?,
-            &[mint_account.clone(), rent.to_account_info()],
-
+            &[mint_account.clone(), rent_info.clone()],
         )
     }
 
     fn process_initialize_account(
         accounts: &[AccountInfo],
-        program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let account = next_account_info(account_info_iter)?;
-        let mint = next_account_info(account_info_iter)?;
-        let owner = next_account_info(account_info_iter)?;
-        let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+        let InitializeAccountAccounts {
+            account,
+            mint,
+            owner,
+            rent: rent_info,
+        } = InitializeAccountAccounts::load(accounts)?;
+        let rent = &Rent::from_account_info(rent_info)?;
     
         if !rent.is_exempt(account.lamports(), account.data_len()) {
             return Err(TokenError::NotRentExempt.into());
         }
     
-        if account.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId.into());
-        }
-    
         invoke(
             &spl_token::instruction::initialize_account(
                 &spl_token::id(),
@@ -165,23 +391,19 @@ impl Processor {
                 mint.key,
                 owner.key,
             )?,
-            &[account.clone(), mint.clone(), owner.clone(), rent.to_account_info()],
+            &[account.clone(), mint.clone(), owner.clone(), rent_info.clone()],
         )
     }
 
     fn process_transfer(
         accounts: &[AccountInfo],
         amount: u64,
-        program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let source = next_account_info(account_info_iter)?;
-        let destination = next_account_info(account_info_iter)?;
-        let authority = next_account_info(account_info_iter)?;
-    
-        if source.owner != program_id || destination.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId.into());
-        }
+        let TransferAccounts {
+            source,
+            destination,
+            authority,
+        } = TransferAccounts::load(accounts)?;
     
         let source_account = TokenAccount::unpack(&source.data.borrow())?;
         if source_account.amount < amount {
@@ -208,16 +430,12 @@ impl Processor {
     fn process_burn(
         accounts: &[AccountInfo],
         amount: u64,
-        program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let account = next_account_info(account_info_iter)?;
-        let mint = next_account_info(account_info_iter)?;
-        let authority = next_account_info(account_info_iter)?;
-    
-        if account.owner != program_id || mint.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId.into());
-        }
+        let BurnAccounts {
+            account,
+            mint,
+            authority,
+        } = BurnAccounts::load(accounts)?;
     
         let token_account = TokenAccount::unpack(&account.data.borrow())?;
         if token_account.amount < amount {
@@ -238,22 +456,27 @@ impl Processor {
                 amount,
             )?,
             &[account.clone(), mint.clone(), authority.clone()],
-        )
+        )?;
+
+        TokenEvent::Burned {
+            mint: *mint.key,
+            account: *account.key,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        }
+        .emit();
+        Ok(())
     }
 
     fn process_mint_to(
         accounts: &[AccountInfo],
         amount: u64,
-        program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let mint = next_account_info(account_info_iter)?;
-        let account = next_account_info(account_info_iter)?;
-        let owner = next_account_info(account_info_iter)?;
-    
-        if mint.owner != program_id || account.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId.into());
-        }
+        let MintToAccounts {
+            mint,
+            account,
+            mint_authority: owner,
+        } = MintToAccounts::load(accounts)?;
     
         let mint_data = Mint::unpack(&mint.data.borrow())?;
         if mint_data.mint_authority.is_none() || mint_data.mint_authority.unwrap() != *owner.key {
@@ -270,21 +493,26 @@ impl Processor {
                 amount,
             )?,
             &[mint.clone(), account.clone(), owner.clone()],
-        )
+        )?;
+
+        TokenEvent::Minted {
+            mint: *mint.key,
+            account: *account.key,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        }
+        .emit();
+        Ok(())
     }
 
     fn process_freeze(
         accounts: &[AccountInfo],
-        program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let account = next_account_info(account_info_iter)?;
-        let mint = next_account_info(account_info_iter)?;
-        let authority = next_account_info(account_info_iter)?;
-    
-        if account.owner != program_id || mint.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId.into());
-        }
+        let FreezeAccounts {
+            account,
+            mint,
+            freeze_authority: authority,
+        } = FreezeAccounts::load(accounts)?;
     
         let mint_data = Mint::unpack(&mint.data.borrow())?;
         if mint_data.freeze_authority.is_none() || mint_data.freeze_authority.unwrap() != *authority.key {
@@ -300,21 +528,25 @@ impl Processor {
                 &[],
             )?,
             &[account.clone(), mint.clone(), authority.clone()],
-        )
+        )?;
+
+        TokenEvent::Frozen {
+            mint: *mint.key,
+            account: *account.key,
+            timestamp: Clock::get()?.unix_timestamp,
+        }
+        .emit();
+        Ok(())
     }
 
     fn process_thaw(
         accounts: &[AccountInfo],
-        program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let account = next_account_info(account_info_iter)?;
-        let mint = next_account_info(account_info_iter)?;
-        let authority = next_account_info(account_info_iter)?;
-    
-        if account.owner != program_id || mint.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId.into());
-        }
+        let FreezeAccounts {
+            account,
+            mint,
+            freeze_authority: authority,
+        } = FreezeAccounts::load(accounts)?;
     
         let mint_data = Mint::unpack(&mint.data.borrow())?;
         if mint_data.freeze_authority.is_none() || mint_data.freeze_authority.unwrap() != *authority.key {
@@ -330,37 +562,56 @@ impl Processor {
                 &[],
             )?,
             &[account.clone(), mint.clone(), authority.clone()],
-        )
+        )?;
+
+        TokenEvent::Thawed {
+            mint: *mint.key,
+            account: *account.key,
+            timestamp: Clock::get()?.unix_timestamp,
+        }
+        .emit();
+        Ok(())
     }
 
     fn process_set_authority(
         accounts: &[AccountInfo],
         authority_type: u8,
         new_authority: Option<Pubkey>,
-        program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let account_or_mint = next_account_info(account_info_iter)?;
-        let current_authority = next_account_info(account_info_iter)?;
-    
-        if account_or_mint.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId.into());
-        }
+        let SetAuthorityAccounts {
+            account_or_mint,
+            current_authority,
+        } = SetAuthorityAccounts::load(accounts)?;
+
+        let event = TokenEvent::AuthorityChanged {
+            account: *account_or_mint.key,
+            authority_type,
+            new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        };
+
+        let authority_type = match authority_type {
+            0 => AuthorityType::MintTokens,
+            1 => AuthorityType::FreezeAccount,
+            2 => AuthorityType::AccountOwner,
+            3 => AuthorityType::CloseAccount,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        };
 
-        let authority_type = spl_token::instruction::AuthorityType::try_from(authority_type)
-        .map_err(|_| ProgramError::InvalidInstructionData)?;
-    
         invoke(
             &spl_token::instruction::set_authority(
                 &spl_token::id(),
                 account_or_mint.key,
                 new_authority.as_ref(),
-                spl_token::instruction::AuthorityType::from(authority_type)?,
+                authority_type,
                 current_authority.key,
                 &[],
             )?,
             &[account_or_mint.clone(), current_authority.clone()],
-        )
+        )?;
+
+        event.emit();
+        Ok(())
     }
 
     fn process_stake(
@@ -368,30 +619,67 @@ impl Processor {
         amount: u64,
         program_id: &Pubkey,
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let stake_account = next_account_info(account_info_iter)?;
-        let token_account = next_account_info(account_info_iter)?;
-        let authority = next_account_info(account_info_iter)?;
-        let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
-    
-        if stake_account.owner != program_id || token_account.owner != program_id {
-            return Err(ProgramError::IncorrectProgramId.into());
+        let StakeAccounts {
+            stake_account,
+            token_account,
+            vault,
+            staking_pool,
+            staking_config,
+            mint,
+            staker,
+            clock,
+            operator,
+            ..
+        } = StakeAccounts::load(program_id, accounts)?;
+        let clock = Clock::from_account_info(clock)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+        if config.paused {
+            return Err(TokenError::StakingPaused.into());
         }
     
         let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
-        let mut token_account_info = TokenAccount::unpack(&token_account.data.borrow())?;
+        if stake_info.owner != *staker.key || stake_info.mint != *mint.key {
+            return Err(TokenError::InvalidAuthority.into());
+        }
+        let token_account_info = TokenAccount::unpack(&token_account.data.borrow())?;
+        if token_account_info.owner != *staker.key {
+            return Err(TokenError::InvalidAuthority.into());
+        }
     
         if token_account_info.amount < amount {
             return Err(TokenError::InsufficientFunds.into());
         }
+        let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        Self::add_stake(&config, &mut stake_info, &mut pool, amount, clock.unix_timestamp)?;
+        let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
     
-        token_account_info.amount -= amount;
-        stake_info.amount += amount;
-        stake_info.start_time = clock.unix_timestamp;
+        invoke(
+            &spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                token_account.key,
+                mint.key,
+                vault.key,
+                staker.key,
+                &[],
+                amount,
+                decimals,
+            )?,
+            &[token_account.clone(), mint.clone(), vault.clone(), staker.clone()],
+        )?;
     
-        StakeInfo::pack(&stake_info, &mut stake_account.data.borrow_mut())?;
-        TokenAccount::pack(token_account_info, &mut token_account.data.borrow_mut())?;
+        let event = TokenEvent::Staked {
+            stake_account: *stake_account.key,
+            staker: *staker.key,
+            amount,
+            staked_amount: stake_info.amount,
+            timestamp: clock.unix_timestamp,
+        };
 
+        Self::pass_commission(&mut stake_info, operator)?;
+        StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+        StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+
+        event.emit();
         Ok(())
 }
 
@@ -401,34 +689,68 @@ fn process_unstake(
     amount: u64,
     program_id: &Pubkey,
 ) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let stake_account = next_account_info(account_info_iter)?;
-    let token_account = next_account_info(account_info_iter)?;
-    let authority = next_account_info(account_info_iter)?;
-    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
-
-    if stake_account.owner != program_id || token_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId.into());
+    let StakeAccounts {
+        stake_account,
+        token_account,
+        vault,
+        vault_bump,
+        staking_pool,
+        staking_config,
+        mint,
+        staker,
+        clock,
+        operator,
+    } = StakeAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+    if config.unbonding_period > 0 {
+        return Err(TokenError::UnbondingRequired.into());
     }
 
     let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
-    let mut token_account_info = TokenAccount::unpack(&token_account.data.borrow())?;
-
-    if stake_info.amount < amount {
-        return Err(TokenError::InsufficientFunds.into());
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
     }
-
-    // 7 days stacking
-    if clock.unix_timestamp - stake_info.start_time < 7 * 24 * 60 * 60 {
-        return Err(ProgramError::Custom(1)); 
+    let token_account_info = TokenAccount::unpack(&token_account.data.borrow())?;
+    if token_account_info.owner != *staker.key {
+        return Err(TokenError::InvalidAuthority.into());
     }
 
-    stake_info.amount -= amount;
-    token_account_info.amount += amount;
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    Self::remove_stake(&config, &mut stake_info, &mut pool, amount, clock.unix_timestamp)?;
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            vault.key,
+            mint.key,
+            token_account.key,
+            vault.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[vault.clone(), mint.clone(), token_account.clone()],
+        &[&[VAULT_SEED, mint.key.as_ref(), &[vault_bump]]],
+    )?;
+
+    let event = TokenEvent::Unstaked {
+        stake_account: *stake_account.key,
+        staker: *staker.key,
+        amount,
+        staked_amount: stake_info.amount,
+        timestamp: clock.unix_timestamp,
+    };
 
-    StakeInfo::pack(&stake_info, &mut stake_account.data.borrow_mut())?;
-    TokenAccount::pack(token_account_info, &mut token_account.data.borrow_mut())?;
+    Self::pass_commission(&mut stake_info, operator)?;
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
 
+    event.emit();
     Ok(())
 }
 
@@ -436,16 +758,13 @@ fn process_upgrade_program(
     accounts: &[AccountInfo],
     program_id: &Pubkey,
 ) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let program_account = next_account_info(account_info_iter)?;
-    let buffer_account = next_account_info(account_info_iter)?;
-    let spill_account = next_account_info(account_info_iter)?;
-    let authority = next_account_info(account_info_iter)?;
-    let rent_sysvar = next_account_info(account_info_iter)?;
-
-    if program_account.key != program_id {
-        return Err(ProgramError::IncorrectProgramId.into());
-    }
+    let UpgradeProgramAccounts {
+        program: program_account,
+        buffer: buffer_account,
+        spill: spill_account,
+        upgrade_authority: authority,
+        rent: rent_sysvar,
+    } = UpgradeProgramAccounts::load(program_id, accounts)?;
 
     invoke(
         &bpf_loader_upgradeable::upgrade(
@@ -465,26 +784,29 @@ fn process_upgrade_program(
 }
 
 fn process_initialize_token_info(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let token_info_account = next_account_info(account_info_iter)?;
-    let mint_authority = next_account_info(account_info_iter)?;
-    let mint = next_account_info(account_info_iter)?;
+    let InitializeTokenInfoAccounts {
+        token_info: token_info_account,
+        mint_authority,
+        mint,
+    } = InitializeTokenInfoAccounts::load(program_id, accounts)?;
 
-    if token_info_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId.into());
+    if !is_uninitialized(&token_info_account.data.borrow()) {
+        return Err(TokenError::AlreadyInitialized.into());
     }
 
-    if !token_info_account.data.borrow().iter().all(|&x| x == 0) {
-        return Err(TokenError::AlreadyInitialized.into());
+    let mint_data = Mint::unpack(&mint.data.borrow())?;
+    if mint_data.mint_authority != COption::Some(*mint_authority.key) {
+        return Err(TokenError::InvalidAuthority.into());
     }
 
-    let total_supply: u64 = 50_000_000_000 * 10u64.pow(9); 
+    let total_supply = TOTAL_SUPPLY;
 
     let token_info = TokenInfo {
+        header: AccountHeader::new::<TokenInfo>(),
         total_supply,
         team_allocation: total_supply / 10, 
         investors_allocation: total_supply / 5, 
-        liquidity_reserve: total_supply * 15 / 100, 
+        liquidity_reserve: total_supply / 100 * 15, 
         development_reserve: total_supply / 5, 
         community_rewards: total_supply / 4, 
         strategic_reserve: total_supply / 10, 
@@ -494,35 +816,39 @@ fn process_initialize_token_info(accounts: &[AccountInfo], program_id: &Pubkey)
 
     TokenInfo::pack(token_info, &mut token_info_account.data.borrow_mut())?;
 
-    
-    let mint_data = Mint {
-        mint_authority: COption::Some(*mint_authority.key),
-        supply: total_supply,
-        decimals: 9,
-        is_initialized: true,
-        freeze_authority: COption::None,
-    };
-    Mint::pack(mint_data, &mut mint.data.borrow_mut())?;
-
+    TokenEvent::TokenInfoInitialized {
+        token_info: *token_info_account.key,
+        mint: *mint.key,
+        mint_authority: *mint_authority.key,
+        total_supply,
+        timestamp: Clock::get()?.unix_timestamp,
+    }
+    .emit();
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_create_vesting_schedule(
     accounts: &[AccountInfo],
     beneficiary: Pubkey,
     allocation_type: AllocationType,
     amount: u64,
     start_time: i64,
+    cliff_time: i64,
     end_time: i64,
     program_id: &Pubkey,
 ) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let vesting_account = next_account_info(account_info_iter)?;
-    let token_info_account = next_account_info(account_info_iter)?;
-    let authority = next_account_info(account_info_iter)?;
+    let CreateVestingScheduleAccounts {
+        vesting_account,
+        token_info: token_info_account,
+        mint_authority: authority,
+    } = CreateVestingScheduleAccounts::load(program_id, accounts)?;
 
-    if vesting_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId.into());
+    if !is_uninitialized(&vesting_account.data.borrow()) {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+    if start_time > cliff_time || cliff_time > end_time {
+        return Err(ProgramError::InvalidArgument);
     }
 
     let mut token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
@@ -530,64 +856,67 @@ fn process_create_vesting_schedule(
         return Err(TokenError::InvalidAuthority.into());
     }
 
-    let allocation = match allocation_type {
-        AllocationType::Team => &mut token_info.team_allocation,
-        AllocationType::Investors => &mut token_info.investors_allocation,
-        AllocationType::Liquidity => &mut token_info.liquidity_reserve,
-        AllocationType::Development => &mut token_info.development_reserve,
-        AllocationType::Community => &mut token_info.community_rewards,
-        AllocationType::Strategic => &mut token_info.strategic_reserve,
-    };
+    let allocation = token_info.allocation_mut(allocation_type);
 
     if amount > *allocation {
         return Err(TokenError::InsufficientFunds.into());
     }
 
     let vesting_schedule = VestingSchedule {
+        header: AccountHeader::new::<VestingSchedule>(),
         beneficiary,
         total_amount: amount,
         released_amount: 0,
         start_time,
+        cliff_time,
         end_time,
         allocation_type,
     };
 
-    VestingSchedule::pack(&vesting_schedule, &mut vesting_account.data.borrow_mut())?;
+    VestingSchedule::pack(vesting_schedule, &mut vesting_account.data.borrow_mut())?;
 
     
     *allocation -= amount;
     TokenInfo::pack(token_info, &mut token_info_account.data.borrow_mut())?;
 
+    TokenEvent::VestingCreated {
+        vesting_account: *vesting_account.key,
+        beneficiary,
+        allocation_type,
+        amount,
+        start_time,
+        cliff_time,
+        end_time,
+        timestamp: Clock::get()?.unix_timestamp,
+    }
+    .emit();
     Ok(())
 }
 
 fn process_release_vested_tokens(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let vesting_account = next_account_info(account_info_iter)?;
-    let token_account = next_account_info(account_info_iter)?;
-    let mint = next_account_info(account_info_iter)?;
-    let authority = next_account_info(account_info_iter)?;
-    let clock = Clock::from_account_info(next_account_info(account_info_iter)?)?;
-
-    if vesting_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId.into());
-    }
+    let ReleaseVestedTokensAccounts {
+        vesting_account,
+        beneficiary_token_account,
+        source_token_account,
+        source_authority,
+        clock,
+    } = ReleaseVestedTokensAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
 
     let mut vesting_schedule = VestingSchedule::unpack(&vesting_account.data.borrow())?;
+    let beneficiary_token = TokenAccount::unpack(&beneficiary_token_account.data.borrow())?;
+    if beneficiary_token.owner != vesting_schedule.beneficiary {
+        return Err(TokenError::InvalidAuthority.into());
+    }
 
     if clock.unix_timestamp < vesting_schedule.start_time {
         return Err(TokenError::VestingNotStarted.into());
     }
+    if clock.unix_timestamp < vesting_schedule.cliff_time {
+        return Err(TokenError::VestingCliffNotReached.into());
+    }
 
-    let total_time = vesting_schedule.end_time - vesting_schedule.start_time;
-    let elapsed_time = clock.unix_timestamp - vesting_schedule.start_time;
-    let vested_amount = if elapsed_time >= total_time {
-        vesting_schedule.total_amount
-    } else {
-        vesting_schedule.total_amount * elapsed_time as u64 / total_time as u64
-    };
-
-    let releasable_amount = vested_amount.saturating_sub(vesting_schedule.released_amount);
+    let releasable_amount = vesting_schedule.releasable_amount(clock.unix_timestamp);
 
     if releasable_amount == 0 {
         return Err(TokenError::NoTokensToRelease.into());
@@ -596,21 +925,2814 @@ fn process_release_vested_tokens(accounts: &[AccountInfo], program_id: &Pubkey)
   
     let transfer_instruction = spl_token::instruction::transfer(
         &spl_token::id(),
-        mint.key,
-        token_account.key,
-        &vesting_schedule.beneficiary,
-        &[&authority.key],
+        source_token_account.key,
+        beneficiary_token_account.key,
+        source_authority.key,
+        &[],
         releasable_amount,
     )?;
 
     invoke(
         &transfer_instruction,
-        &[mint.clone(), token_account.clone(), authority.clone()],
+        &[
+            source_token_account.clone(),
+            beneficiary_token_account.clone(),
+            source_authority.clone(),
+        ],
     )?;
 
     vesting_schedule.released_amount += releasable_amount;
-    VestingSchedule::pack(&vesting_schedule, &mut vesting_account.data.borrow_mut())?;
+    let event = TokenEvent::VestingReleased {
+        vesting_account: *vesting_account.key,
+        beneficiary: vesting_schedule.beneficiary,
+        amount: releasable_amount,
+        released_amount: vesting_schedule.released_amount,
+        timestamp: clock.unix_timestamp,
+    };
+    VestingSchedule::pack(vesting_schedule, &mut vesting_account.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_migrate_account(
+    accounts: &[AccountInfo],
+    account_type: AccountType,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let MigrateAccountAccounts {
+        account,
+        token_info: token_info_account,
+        admin,
+        system_program,
+    } = MigrateAccountAccounts::load(program_id, accounts)?;
+
+    // The token info may itself still be in an old layout.
+    let (token_info, _) = migration::unpack_any_version::<TokenInfo>(&token_info_account.data.borrow())?;
+    if *admin.key != token_info.mint_authority {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+
+    let migrated = match migration::migrate(program_id, account_type, &account.data.borrow())? {
+        Some(migrated) => migrated,
+        None => return Ok(()),
+    };
+
+    let rent = Rent::get()?;
+    let top_up = rent.minimum_balance(migrated.len()).saturating_sub(account.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(admin.key, account.key, top_up),
+            &[admin.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+
+    account.realloc(migrated.len(), false)?;
+    account.data.borrow_mut().copy_from_slice(&migrated);
+
+    Ok(())
+}
+
+fn process_query_vesting(
+    accounts: &[AccountInfo],
+    program_id: &Pubkey,
+    amount_at: fn(&VestingSchedule, i64) -> u64,
+) -> ProgramResult {
+    let QueryVestingAccounts {
+        vesting_account,
+        clock,
+    } = QueryVestingAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let vesting_schedule = VestingSchedule::unpack(&vesting_account.data.borrow())?;
+    let amount = amount_at(&vesting_schedule, clock.unix_timestamp);
+    set_return_data(&amount.to_le_bytes());
+    Ok(())
+}
+
+fn process_query_stake_info(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let QueryStakeInfoAccounts { stake_account } = QueryStakeInfoAccounts::load(program_id, accounts)?;
+
+    let stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    set_return_data(&borsh::to_vec(&stake_info).map_err(|_| ProgramError::InvalidAccountData)?);
+    Ok(())
+}
+
+fn process_query_allocation_remaining(
+    accounts: &[AccountInfo],
+    allocation_type: AllocationType,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let QueryAllocationRemainingAccounts {
+        token_info: token_info_account,
+    } = QueryAllocationRemainingAccounts::load(program_id, accounts)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    set_return_data(&token_info.allocation(allocation_type).to_le_bytes());
+    Ok(())
+}
+
+fn process_initialize_vault(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let InitializeVaultAccounts {
+        vault,
+        vault_bump,
+        mint,
+        payer,
+        system_program,
+    } = InitializeVaultAccounts::load(program_id, accounts)?;
+
+    if vault.lamports() > 0 || !vault.data_is_empty() {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+
+    let seeds: &[&[u8]] = &[VAULT_SEED, mint.key.as_ref(), &[vault_bump]];
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            vault.key,
+            Rent::get()?.minimum_balance(TokenAccount::LEN),
+            TokenAccount::LEN as u64,
+            &spl_token::id(),
+        ),
+        &[payer.clone(), vault.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    // The vault is its own authority, so only this program can move tokens
+    // out of it.
+    invoke(
+        &spl_token::instruction::initialize_account3(&spl_token::id(), vault.key, mint.key, vault.key)?,
+        &[vault.clone(), mint.clone()],
+    )?;
+    Ok(())
+}
+
+fn process_initialize_stake(accounts: &[AccountInfo], lock_tier: LockTier, program_id: &Pubkey) -> ProgramResult {
+    let InitializeStakeAccounts {
+        stake_account,
+        stake_bump,
+        mint,
+        owner,
+        system_program,
+    } = InitializeStakeAccounts::load(program_id, accounts)?;
+
+    if stake_account.lamports() > 0 || !stake_account.data_is_empty() {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            owner.key,
+            stake_account.key,
+            Rent::get()?.minimum_balance(StakeInfo::LEN),
+            StakeInfo::LEN as u64,
+            program_id,
+        ),
+        &[owner.clone(), stake_account.clone(), system_program.clone()],
+        &[&[STAKE_SEED, mint.key.as_ref(), owner.key.as_ref(), &[stake_bump]]],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let stake_info = StakeInfo {
+        header: AccountHeader::new::<StakeInfo>(),
+        owner: *owner.key,
+        mint: *mint.key,
+        bump: stake_bump,
+        amount: 0,
+        weight: 0,
+        lock_tier,
+        start_time: 0,
+        unlock_time: 0,
+        created_at: now,
+        updated_at: now,
+        reward_per_token_paid: 0,
+        pending_rewards: 0,
+        unbonding_amount: 0,
+        next_unbonding_id: 0,
+        auto_compound: false,
+        operator: Pubkey::default(),
+        commission_bps: 0,
+        pending_commission: 0,
+    };
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+
+    TokenEvent::StakeInitialized {
+        stake_account: *stake_account.key,
+        owner: *owner.key,
+        mint: *mint.key,
+        lock_tier,
+        timestamp: now,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_initialize_staking_pool(accounts: &[AccountInfo], reward_rate: u64, program_id: &Pubkey) -> ProgramResult {
+    let InitializeStakingPoolAccounts {
+        staking_pool,
+        staking_pool_bump,
+        reward_vault,
+        reward_vault_bump,
+        vault: _,
+        mint,
+        token_info: token_info_account,
+        admin,
+        system_program,
+    } = InitializeStakingPoolAccounts::load(program_id, accounts)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key || token_info.mint_authority != *admin.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if staking_pool.lamports() > 0 || reward_vault.lamports() > 0 {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            staking_pool.key,
+            rent.minimum_balance(StakingPool::LEN),
+            StakingPool::LEN as u64,
+            program_id,
+        ),
+        &[admin.clone(), staking_pool.clone(), system_program.clone()],
+        &[&[STAKING_POOL_SEED, mint.key.as_ref(), &[staking_pool_bump]]],
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            reward_vault.key,
+            rent.minimum_balance(TokenAccount::LEN),
+            TokenAccount::LEN as u64,
+            &spl_token::id(),
+        ),
+        &[admin.clone(), reward_vault.clone(), system_program.clone()],
+        &[&[REWARD_VAULT_SEED, mint.key.as_ref(), &[reward_vault_bump]]],
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_account3(&spl_token::id(), reward_vault.key, mint.key, reward_vault.key)?,
+        &[reward_vault.clone(), mint.clone()],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let pool = StakingPool {
+        header: AccountHeader::new::<StakingPool>(),
+        mint: *mint.key,
+        bump: staking_pool_bump,
+        reward_vault_bump,
+        // Stakes made before the pool are counted once they are first
+        // touched, see [`Self::reweigh`].
+        total_staked: 0,
+        total_weight: 0,
+        reward_rate,
+        reward_per_token: 0,
+        undistributed_rewards: 0,
+        last_update_time: now,
+        emission_end: 0,
+    };
+    let total_staked = pool.total_staked;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
 
+    TokenEvent::StakingPoolInitialized {
+        staking_pool: *staking_pool.key,
+        mint: *mint.key,
+        reward_rate,
+        total_staked,
+        timestamp: now,
+    }
+    .emit();
     Ok(())
 }
+
+fn process_fund_rewards(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let FundRewardsAccounts {
+        staking_pool,
+        reward_vault,
+        token_info: token_info_account,
+        source_token_account,
+        mint,
+        admin,
+        clock,
+    } = FundRewardsAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let mut token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key || token_info.mint_authority != *admin.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if amount > token_info.community_rewards {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    invoke(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            source_token_account.key,
+            mint.key,
+            reward_vault.key,
+            admin.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[source_token_account.clone(), mint.clone(), reward_vault.clone(), admin.clone()],
+    )?;
+
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    pool.accrue(clock.unix_timestamp)?;
+    pool.undistributed_rewards = pool.undistributed_rewards.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+    token_info.community_rewards -= amount;
+
+    let event = TokenEvent::RewardsFunded {
+        staking_pool: *staking_pool.key,
+        amount,
+        undistributed_rewards: pool.undistributed_rewards,
+        timestamp: clock.unix_timestamp,
+    };
+
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+    TokenInfo::pack(token_info, &mut token_info_account.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_claim_rewards(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let ClaimRewardsAccounts {
+        stake_account,
+        staking_pool,
+        reward_vault,
+        reward_vault_bump,
+        destination,
+        mint,
+        staker,
+        clock,
+        operator,
+    } = ClaimRewardsAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if TokenAccount::unpack(&destination.data.borrow())?.owner != *staker.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    pool.accrue(clock.unix_timestamp)?;
+    stake_info.settle(pool.reward_per_token)?;
+
+    let amount = stake_info.pending_rewards;
+    if amount == 0 {
+        return Err(TokenError::NoRewardsToClaim.into());
+    }
+    stake_info.pending_rewards = 0;
+    stake_info.updated_at = clock.unix_timestamp;
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            reward_vault.key,
+            mint.key,
+            destination.key,
+            reward_vault.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[reward_vault.clone(), mint.clone(), destination.clone()],
+        &[&[REWARD_VAULT_SEED, mint.key.as_ref(), &[reward_vault_bump]]],
+    )?;
+
+    Self::pass_commission(&mut stake_info, operator)?;
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+
+    TokenEvent::RewardsClaimed {
+        stake_account: *stake_account.key,
+        staker: *staker.key,
+        amount,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_query_pending_rewards(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let QueryPendingRewardsAccounts {
+        stake_account,
+        staking_pool,
+        clock,
+    } = QueryPendingRewardsAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    if stake_info.mint != pool.mint {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (reward_per_token, _) = pool.reward_per_token_at(clock.unix_timestamp)?;
+    set_return_data(&stake_info.earned(reward_per_token)?.to_le_bytes());
+    Ok(())
+}
+
+fn process_initialize_staking_config(
+    accounts: &[AccountInfo],
+    params: StakingParams,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let InitializeStakingConfigAccounts {
+        staking_config,
+        staking_config_bump,
+        staking_pool,
+        mint,
+        token_info: token_info_account,
+        admin,
+        clock,
+        system_program,
+        treasury,
+    } = InitializeStakingConfigAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key || token_info.mint_authority != *admin.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if staking_config.lamports() > 0 {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+    if params.lock_duration < 0 || params.unbonding_period < 0 || params.penalty_bps as u64 > BASIS_POINTS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Self::check_treasury(&params, treasury, mint)?;
+
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            staking_config.key,
+            rent.minimum_balance(StakingConfig::LEN),
+            StakingConfig::LEN as u64,
+            program_id,
+        ),
+        &[admin.clone(), staking_config.clone(), system_program.clone()],
+        &[&[STAKING_CONFIG_SEED, mint.key.as_ref(), &[staking_config_bump]]],
+    )?;
+
+    let config = StakingConfig {
+        header: AccountHeader::new::<StakingConfig>(),
+        mint: *mint.key,
+        bump: staking_config_bump,
+        vault_bump: find_vault_address(program_id, mint.key).1,
+        params,
+    };
+    StakingConfig::pack(config, &mut staking_config.data.borrow_mut())?;
+    Self::apply_reward_rate(staking_pool, params.reward_rate, clock.unix_timestamp)?;
+
+    TokenEvent::StakingConfigUpdated {
+        staking_config: *staking_config.key,
+        params,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_update_staking_config(
+    accounts: &[AccountInfo],
+    params: StakingParams,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let UpdateStakingConfigAccounts {
+        staking_config,
+        staking_pool,
+        mint,
+        token_info: token_info_account,
+        admin,
+        clock,
+        treasury,
+    } = UpdateStakingConfigAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key || token_info.mint_authority != *admin.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if params.lock_duration < 0 || params.unbonding_period < 0 || params.penalty_bps as u64 > BASIS_POINTS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Self::check_treasury(&params, treasury, mint)?;
+
+    let mut config = StakingConfig::unpack(&staking_config.data.borrow())?;
+    config.params = params;
+    StakingConfig::pack(config, &mut staking_config.data.borrow_mut())?;
+    Self::apply_reward_rate(staking_pool, params.reward_rate, clock.unix_timestamp)?;
+
+    TokenEvent::StakingConfigUpdated {
+        staking_config: *staking_config.key,
+        params,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+    Ok(())
+}
+
+/// Checks that penalties sent to the treasury go to a token account of
+/// `mint`, passed as `treasury`.
+fn check_treasury(params: &StakingParams, treasury: Option<&AccountInfo>, mint: &AccountInfo) -> ProgramResult {
+    if params.penalty_destination != PenaltyDestination::Treasury {
+        return Ok(());
+    }
+    let treasury = treasury.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if *treasury.key != params.treasury || TokenAccount::unpack(&treasury.data.borrow())?.mint != *mint.key {
+        return Err(TokenError::InvalidPenaltyAccount.into());
+    }
+    Ok(())
+}
+
+/// Switches the pool to a new reward rate after accruing what the old rate
+/// emitted up to `now`. A pool on an emission schedule keeps the rate of
+/// its epoch.
+fn apply_reward_rate(staking_pool: &AccountInfo, reward_rate: u64, now: i64) -> ProgramResult {
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    if pool.emission_end != 0 {
+        return Ok(());
+    }
+    pool.accrue(now)?;
+    pool.reward_rate = reward_rate;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())
+}
+
+fn process_extend_lock(accounts: &[AccountInfo], lock_tier: LockTier, program_id: &Pubkey) -> ProgramResult {
+    let ExtendLockAccounts {
+        stake_account,
+        staking_pool,
+        mint,
+        staker,
+        clock,
+        operator,
+    } = ExtendLockAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if lock_tier <= stake_info.lock_tier {
+        return Err(TokenError::LockNotExtended.into());
+    }
+
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    pool.accrue(clock.unix_timestamp)?;
+    stake_info.settle(pool.reward_per_token)?;
+
+    stake_info.lock_tier = lock_tier;
+    let amount = stake_info.amount;
+    Self::reweigh(&mut stake_info, &mut pool, amount)?;
+    stake_info.unlock_time = clock.unix_timestamp + lock_tier.duration();
+    stake_info.updated_at = clock.unix_timestamp;
+
+    let event = TokenEvent::LockExtended {
+        stake_account: *stake_account.key,
+        staker: *staker.key,
+        lock_tier,
+        unlock_time: stake_info.unlock_time,
+        weight: stake_info.weight,
+        timestamp: clock.unix_timestamp,
+    };
+
+    Self::pass_commission(&mut stake_info, operator)?;
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_request_unstake(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let RequestUnstakeAccounts {
+        stake_account,
+        unbonding_entry,
+        staking_pool,
+        staking_config,
+        mint,
+        staker,
+        clock,
+        system_program,
+        operator,
+    } = RequestUnstakeAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    let id = stake_info.next_unbonding_id;
+    let (address, bump) = find_unbonding_address(program_id, stake_account.key, id);
+    if *unbonding_entry.key != address {
+        return Err(TokenError::InvalidProgramAddress.into());
+    }
+    if unbonding_entry.lamports() > 0 {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    Self::remove_stake(&config, &mut stake_info, &mut pool, amount, clock.unix_timestamp)?;
+    stake_info.unbonding_amount = stake_info
+        .unbonding_amount
+        .checked_add(amount)
+        .ok_or(TokenError::AmountOverflow)?;
+    stake_info.next_unbonding_id += 1;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            staker.key,
+            unbonding_entry.key,
+            Rent::get()?.minimum_balance(UnbondingEntry::LEN),
+            UnbondingEntry::LEN as u64,
+            program_id,
+        ),
+        &[staker.clone(), unbonding_entry.clone(), system_program.clone()],
+        &[&[UNBONDING_SEED, stake_account.key.as_ref(), &id.to_le_bytes(), &[bump]]],
+    )?;
+    let entry = UnbondingEntry {
+        header: AccountHeader::new::<UnbondingEntry>(),
+        stake_account: *stake_account.key,
+        owner: Pubkey::default(),
+        id,
+        bump,
+        amount,
+        requested_at: clock.unix_timestamp,
+        withdrawable_at: clock.unix_timestamp.saturating_add(config.unbonding_period),
+    };
+
+    let event = TokenEvent::UnstakeRequested {
+        stake_account: *stake_account.key,
+        unbonding_entry: *unbonding_entry.key,
+        staker: *staker.key,
+        amount,
+        staked_amount: stake_info.amount,
+        withdrawable_at: entry.withdrawable_at,
+        timestamp: clock.unix_timestamp,
+    };
+
+    UnbondingEntry::pack(entry, &mut unbonding_entry.data.borrow_mut())?;
+    Self::pass_commission(&mut stake_info, operator)?;
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_withdraw_unbonded(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let WithdrawUnbondedAccounts {
+        stake_account,
+        unbonding_entry,
+        token_account,
+        vault,
+        vault_bump,
+        staking_config,
+        mint,
+        staker,
+        clock,
+    } = WithdrawUnbondedAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    let entry = UnbondingEntry::unpack(&unbonding_entry.data.borrow())?;
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key || entry.stake_account != *stake_account.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if TokenAccount::unpack(&token_account.data.borrow())?.owner != *staker.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if clock.unix_timestamp < entry.withdrawable_at {
+        return Err(TokenError::UnbondingNotComplete.into());
+    }
+    stake_info.unbonding_amount = stake_info
+        .unbonding_amount
+        .checked_sub(entry.amount)
+        .ok_or(TokenError::InsufficientFunds)?;
+    stake_info.updated_at = clock.unix_timestamp;
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            vault.key,
+            mint.key,
+            token_account.key,
+            vault.key,
+            &[],
+            entry.amount,
+            decimals,
+        )?,
+        &[vault.clone(), mint.clone(), token_account.clone()],
+        &[&[VAULT_SEED, mint.key.as_ref(), &[vault_bump]]],
+    )?;
+
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    Self::close_account(unbonding_entry, staker);
+
+    TokenEvent::UnbondedWithdrawn {
+        stake_account: *stake_account.key,
+        unbonding_entry: *unbonding_entry.key,
+        staker: *staker.key,
+        amount: entry.amount,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_cancel_unbonding(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let CancelUnbondingAccounts {
+        stake_account,
+        unbonding_entry,
+        staking_pool,
+        staking_config,
+        mint,
+        staker,
+        clock,
+        operator,
+    } = CancelUnbondingAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    let entry = UnbondingEntry::unpack(&unbonding_entry.data.borrow())?;
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key || entry.stake_account != *stake_account.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    stake_info.unbonding_amount = stake_info
+        .unbonding_amount
+        .checked_sub(entry.amount)
+        .ok_or(TokenError::InsufficientFunds)?;
+    // The tokens were unlocked when they left, so they go back under the
+    // lock they had rather than a new one.
+    Self::add_stake_keeping_lock(&config, &mut stake_info, &mut pool, entry.amount, clock.unix_timestamp)?;
+
+    let event = TokenEvent::UnbondingCancelled {
+        stake_account: *stake_account.key,
+        unbonding_entry: *unbonding_entry.key,
+        staker: *staker.key,
+        amount: entry.amount,
+        staked_amount: stake_info.amount,
+        timestamp: clock.unix_timestamp,
+    };
+
+    Self::pass_commission(&mut stake_info, operator)?;
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+    Self::close_account(unbonding_entry, staker);
+
+    event.emit();
+    Ok(())
+}
+
+fn process_emergency_unstake(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let EmergencyUnstakeAccounts {
+        stake_account,
+        token_account,
+        vault,
+        vault_bump,
+        staking_pool,
+        staking_config,
+        mint,
+        penalty_account,
+        staker,
+        clock,
+        operator,
+    } = EmergencyUnstakeAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+    if *penalty_account.key != config.penalty_account(program_id, mint.key) {
+        return Err(TokenError::InvalidPenaltyAccount.into());
+    }
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    let token_account_info = TokenAccount::unpack(&token_account.data.borrow())?;
+    if token_account_info.owner != *staker.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+
+    // Unlocked tokens leave the usual way when that has an unbonding period.
+    if clock.unix_timestamp >= stake_info.lock_end(config.lock_duration) && config.unbonding_period > 0 {
+        return Err(TokenError::UnbondingRequired.into());
+    }
+    let penalty = config.early_unstake_penalty(&stake_info, amount, clock.unix_timestamp)?;
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    Self::remove_locked_stake(&config, &mut stake_info, &mut pool, amount, clock.unix_timestamp)?;
+    if config.penalty_destination == PenaltyDestination::RewardPool {
+        // The pool was accrued to now while removing the stake.
+        pool.undistributed_rewards = pool
+            .undistributed_rewards
+            .checked_add(penalty)
+            .ok_or(TokenError::AmountOverflow)?;
+    }
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    let vault_seeds: &[&[u8]] = &[VAULT_SEED, mint.key.as_ref(), &[vault_bump]];
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            vault.key,
+            mint.key,
+            token_account.key,
+            vault.key,
+            &[],
+            amount - penalty,
+            decimals,
+        )?,
+        &[vault.clone(), mint.clone(), token_account.clone()],
+        &[vault_seeds],
+    )?;
+    if penalty > 0 {
+        let instruction = match config.penalty_destination {
+            PenaltyDestination::Burn => spl_token::instruction::burn_checked(
+                &spl_token::id(),
+                vault.key,
+                mint.key,
+                vault.key,
+                &[],
+                penalty,
+                decimals,
+            )?,
+            PenaltyDestination::Treasury | PenaltyDestination::RewardPool => spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                vault.key,
+                mint.key,
+                penalty_account.key,
+                vault.key,
+                &[],
+                penalty,
+                decimals,
+            )?,
+        };
+        invoke_signed(&instruction, &[vault.clone(), mint.clone(), penalty_account.clone()], &[vault_seeds])?;
+    }
+
+    let event = TokenEvent::EmergencyUnstaked {
+        stake_account: *stake_account.key,
+        staker: *staker.key,
+        amount,
+        penalty,
+        penalty_destination: config.penalty_destination,
+        staked_amount: stake_info.amount,
+        timestamp: clock.unix_timestamp,
+    };
+
+    Self::pass_commission(&mut stake_info, operator)?;
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_initialize_liquid_pool(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let InitializeLiquidPoolAccounts {
+        liquid_pool,
+        liquid_pool_bump,
+        receipt_mint,
+        receipt_mint_bump,
+        stake_account,
+        stake_bump,
+        mint,
+        token_info: token_info_account,
+        admin,
+        system_program,
+    } = InitializeLiquidPoolAccounts::load(program_id, accounts)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key || token_info.mint_authority != *admin.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if liquid_pool.lamports() > 0 || receipt_mint.lamports() > 0 || stake_account.lamports() > 0 {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            liquid_pool.key,
+            rent.minimum_balance(LiquidPool::LEN),
+            LiquidPool::LEN as u64,
+            program_id,
+        ),
+        &[admin.clone(), liquid_pool.clone(), system_program.clone()],
+        &[&[LIQUID_POOL_SEED, mint.key.as_ref(), &[liquid_pool_bump]]],
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            receipt_mint.key,
+            rent.minimum_balance(Mint::LEN),
+            Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        &[admin.clone(), receipt_mint.clone(), system_program.clone()],
+        &[&[RECEIPT_MINT_SEED, mint.key.as_ref(), &[receipt_mint_bump]]],
+    )?;
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    invoke(
+        &spl_token::instruction::initialize_mint2(&spl_token::id(), receipt_mint.key, liquid_pool.key, None, decimals)?,
+        std::slice::from_ref(receipt_mint),
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            stake_account.key,
+            rent.minimum_balance(StakeInfo::LEN),
+            StakeInfo::LEN as u64,
+            program_id,
+        ),
+        &[admin.clone(), stake_account.clone(), system_program.clone()],
+        &[&[STAKE_SEED, mint.key.as_ref(), liquid_pool.key.as_ref(), &[stake_bump]]],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let stake_info = StakeInfo {
+        header: AccountHeader::new::<StakeInfo>(),
+        owner: *liquid_pool.key,
+        mint: *mint.key,
+        bump: stake_bump,
+        amount: 0,
+        weight: 0,
+        lock_tier: LockTier::Days30,
+        start_time: 0,
+        unlock_time: 0,
+        created_at: now,
+        updated_at: now,
+        reward_per_token_paid: 0,
+        pending_rewards: 0,
+        unbonding_amount: 0,
+        next_unbonding_id: 0,
+        // Cranks raise the exchange rate between deposits.
+        auto_compound: true,
+        operator: Pubkey::default(),
+        commission_bps: 0,
+        pending_commission: 0,
+    };
+    let pool = LiquidPool {
+        header: AccountHeader::new::<LiquidPool>(),
+        mint: *mint.key,
+        receipt_mint: *receipt_mint.key,
+        bump: liquid_pool_bump,
+        receipt_mint_bump,
+    };
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    LiquidPool::pack(pool, &mut liquid_pool.data.borrow_mut())?;
+
+    TokenEvent::LiquidPoolInitialized {
+        liquid_pool: *liquid_pool.key,
+        mint: *mint.key,
+        receipt_mint: *receipt_mint.key,
+        timestamp: now,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_liquid_stake(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let LiquidStakeAccounts {
+        liquid_pool,
+        liquid_pool_bump,
+        stake_account,
+        staking_pool,
+        staking_config,
+        vault,
+        reward_vault,
+        reward_vault_bump,
+        mint,
+        receipt_mint,
+        token_account,
+        receipt_account,
+        owner,
+        clock,
+        ..
+    } = LiquidStakeAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+    let token_account_info = TokenAccount::unpack(&token_account.data.borrow())?;
+    if token_account_info.owner != *owner.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if token_account_info.amount < amount {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    let compounded = Self::compound(&config, &mut stake_info, &mut pool, clock.unix_timestamp)?;
+    let receipt_supply = Mint::unpack(&receipt_mint.data.borrow())?.supply;
+    let receipt_amount = LiquidPool::receipt_for(amount, stake_info.amount, receipt_supply)?;
+    if receipt_amount == 0 {
+        return Err(TokenError::AmountTooSmall.into());
+    }
+    // Receipts are interchangeable, so a lock of the position would hold
+    // back every holder for each deposit.
+    Self::add_stake_keeping_lock(&config, &mut stake_info, &mut pool, amount, clock.unix_timestamp)?;
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    Self::restake_rewards(reward_vault, reward_vault_bump, vault, mint, compounded, decimals)?;
+    invoke(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            token_account.key,
+            mint.key,
+            vault.key,
+            owner.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[token_account.clone(), mint.clone(), vault.clone(), owner.clone()],
+    )?;
+    invoke_signed(
+        &spl_token::instruction::mint_to(
+            &spl_token::id(),
+            receipt_mint.key,
+            receipt_account.key,
+            liquid_pool.key,
+            &[],
+            receipt_amount,
+        )?,
+        &[receipt_mint.clone(), receipt_account.clone(), liquid_pool.clone()],
+        &[&[LIQUID_POOL_SEED, mint.key.as_ref(), &[liquid_pool_bump]]],
+    )?;
+
+    let event = TokenEvent::LiquidStaked {
+        liquid_pool: *liquid_pool.key,
+        owner: *owner.key,
+        amount,
+        receipt_amount,
+        staked_amount: stake_info.amount,
+        receipt_supply: receipt_supply + receipt_amount,
+        timestamp: clock.unix_timestamp,
+    };
+
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_liquid_unstake(accounts: &[AccountInfo], receipt_amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let LiquidStakeAccounts {
+        liquid_pool,
+        stake_account,
+        staking_pool,
+        staking_config,
+        vault,
+        vault_bump,
+        reward_vault,
+        reward_vault_bump,
+        mint,
+        receipt_mint,
+        token_account,
+        receipt_account,
+        owner,
+        clock,
+        ..
+    } = LiquidStakeAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+    if config.unbonding_period > 0 {
+        return Err(TokenError::UnbondingRequired.into());
+    }
+    if TokenAccount::unpack(&token_account.data.borrow())?.owner != *owner.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    let compounded = Self::compound(&config, &mut stake_info, &mut pool, clock.unix_timestamp)?;
+    let receipt_supply = Mint::unpack(&receipt_mint.data.borrow())?.supply;
+    let amount = LiquidPool::underlying_for(receipt_amount, stake_info.amount, receipt_supply);
+    if amount == 0 {
+        return Err(TokenError::AmountTooSmall.into());
+    }
+    Self::remove_stake(&config, &mut stake_info, &mut pool, amount, clock.unix_timestamp)?;
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    Self::restake_rewards(reward_vault, reward_vault_bump, vault, mint, compounded, decimals)?;
+    invoke(
+        &spl_token::instruction::burn(
+            &spl_token::id(),
+            receipt_account.key,
+            receipt_mint.key,
+            owner.key,
+            &[],
+            receipt_amount,
+        )?,
+        &[receipt_account.clone(), receipt_mint.clone(), owner.clone()],
+    )?;
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            vault.key,
+            mint.key,
+            token_account.key,
+            vault.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[vault.clone(), mint.clone(), token_account.clone()],
+        &[&[VAULT_SEED, mint.key.as_ref(), &[vault_bump]]],
+    )?;
+
+    let event = TokenEvent::LiquidUnstaked {
+        liquid_pool: *liquid_pool.key,
+        owner: *owner.key,
+        amount,
+        receipt_amount,
+        staked_amount: stake_info.amount,
+        receipt_supply: receipt_supply - receipt_amount,
+        timestamp: clock.unix_timestamp,
+    };
+
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_request_liquid_unstake(accounts: &[AccountInfo], receipt_amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let RequestLiquidUnstakeAccounts {
+        liquid_pool,
+        stake_account,
+        unbonding_entry,
+        staking_pool,
+        staking_config,
+        vault,
+        reward_vault,
+        reward_vault_bump,
+        mint,
+        receipt_mint,
+        receipt_account,
+        owner,
+        clock,
+        system_program,
+    } = RequestLiquidUnstakeAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    let id = stake_info.next_unbonding_id;
+    let (address, bump) = find_unbonding_address(program_id, stake_account.key, id);
+    if *unbonding_entry.key != address {
+        return Err(TokenError::InvalidProgramAddress.into());
+    }
+    if unbonding_entry.lamports() > 0 {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    let compounded = Self::compound(&config, &mut stake_info, &mut pool, clock.unix_timestamp)?;
+    let receipt_supply = Mint::unpack(&receipt_mint.data.borrow())?.supply;
+    let amount = LiquidPool::underlying_for(receipt_amount, stake_info.amount, receipt_supply);
+    if amount == 0 {
+        return Err(TokenError::AmountTooSmall.into());
+    }
+    Self::remove_stake(&config, &mut stake_info, &mut pool, amount, clock.unix_timestamp)?;
+    stake_info.unbonding_amount = stake_info
+        .unbonding_amount
+        .checked_add(amount)
+        .ok_or(TokenError::AmountOverflow)?;
+    stake_info.next_unbonding_id += 1;
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    Self::restake_rewards(reward_vault, reward_vault_bump, vault, mint, compounded, decimals)?;
+    invoke(
+        &spl_token::instruction::burn(
+            &spl_token::id(),
+            receipt_account.key,
+            receipt_mint.key,
+            owner.key,
+            &[],
+            receipt_amount,
+        )?,
+        &[receipt_account.clone(), receipt_mint.clone(), owner.clone()],
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            owner.key,
+            unbonding_entry.key,
+            Rent::get()?.minimum_balance(UnbondingEntry::LEN),
+            UnbondingEntry::LEN as u64,
+            program_id,
+        ),
+        &[owner.clone(), unbonding_entry.clone(), system_program.clone()],
+        &[&[UNBONDING_SEED, stake_account.key.as_ref(), &id.to_le_bytes(), &[bump]]],
+    )?;
+    let entry = UnbondingEntry {
+        header: AccountHeader::new::<UnbondingEntry>(),
+        stake_account: *stake_account.key,
+        owner: *owner.key,
+        id,
+        bump,
+        amount,
+        requested_at: clock.unix_timestamp,
+        withdrawable_at: clock.unix_timestamp.saturating_add(config.unbonding_period),
+    };
+
+    let event = TokenEvent::LiquidUnstakeRequested {
+        liquid_pool: *liquid_pool.key,
+        unbonding_entry: *unbonding_entry.key,
+        owner: *owner.key,
+        amount,
+        receipt_amount,
+        staked_amount: stake_info.amount,
+        receipt_supply: receipt_supply - receipt_amount,
+        withdrawable_at: entry.withdrawable_at,
+        timestamp: clock.unix_timestamp,
+    };
+
+    UnbondingEntry::pack(entry, &mut unbonding_entry.data.borrow_mut())?;
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_withdraw_liquid_unbonded(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let WithdrawLiquidUnbondedAccounts {
+        stake_account,
+        unbonding_entry,
+        token_account,
+        vault,
+        vault_bump,
+        staking_config,
+        mint,
+        owner,
+        clock,
+    } = WithdrawLiquidUnbondedAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    let entry = UnbondingEntry::unpack(&unbonding_entry.data.borrow())?;
+    if entry.stake_account != *stake_account.key || entry.owner != *owner.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if TokenAccount::unpack(&token_account.data.borrow())?.owner != *owner.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if clock.unix_timestamp < entry.withdrawable_at {
+        return Err(TokenError::UnbondingNotComplete.into());
+    }
+    stake_info.unbonding_amount = stake_info
+        .unbonding_amount
+        .checked_sub(entry.amount)
+        .ok_or(TokenError::InsufficientFunds)?;
+    stake_info.updated_at = clock.unix_timestamp;
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            vault.key,
+            mint.key,
+            token_account.key,
+            vault.key,
+            &[],
+            entry.amount,
+            decimals,
+        )?,
+        &[vault.clone(), mint.clone(), token_account.clone()],
+        &[&[VAULT_SEED, mint.key.as_ref(), &[vault_bump]]],
+    )?;
+
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    Self::close_account(unbonding_entry, owner);
+
+    TokenEvent::UnbondedWithdrawn {
+        stake_account: *stake_account.key,
+        unbonding_entry: *unbonding_entry.key,
+        staker: *owner.key,
+        amount: entry.amount,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_compound_rewards(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let CompoundRewardsAccounts {
+        stake_account,
+        staking_pool,
+        staking_config,
+        vault,
+        reward_vault,
+        reward_vault_bump,
+        mint,
+        owner,
+        clock,
+        operator,
+    } = CompoundRewardsAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+    if config.paused {
+        return Err(TokenError::StakingPaused.into());
+    }
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    if stake_info.owner != *owner.key || stake_info.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if !owner.is_signer && !stake_info.auto_compound {
+        return Err(TokenError::AutoCompoundDisabled.into());
+    }
+
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    let amount = Self::compound(&config, &mut stake_info, &mut pool, clock.unix_timestamp)?;
+    if amount == 0 {
+        return Err(TokenError::NoRewardsToClaim.into());
+    }
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    Self::restake_rewards(reward_vault, reward_vault_bump, vault, mint, amount, decimals)?;
+
+    let event = TokenEvent::RewardsCompounded {
+        stake_account: *stake_account.key,
+        owner: *owner.key,
+        amount,
+        staked_amount: stake_info.amount,
+        timestamp: clock.unix_timestamp,
+    };
+
+    Self::pass_commission(&mut stake_info, operator)?;
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_set_auto_compound(accounts: &[AccountInfo], enabled: bool, program_id: &Pubkey) -> ProgramResult {
+    let SetAutoCompoundAccounts {
+        stake_account,
+        mint,
+        staker,
+    } = SetAutoCompoundAccounts::load(program_id, accounts)?;
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    stake_info.auto_compound = enabled;
+    stake_info.updated_at = now;
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+
+    TokenEvent::AutoCompoundSet {
+        stake_account: *stake_account.key,
+        staker: *staker.key,
+        enabled,
+        timestamp: now,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_initialize_locker(accounts: &[AccountInfo], max_lock_duration: i64, program_id: &Pubkey) -> ProgramResult {
+    let InitializeLockerAccounts {
+        locker,
+        locker_bump,
+        locker_vault,
+        locker_vault_bump,
+        mint,
+        token_info: token_info_account,
+        admin,
+        system_program,
+    } = InitializeLockerAccounts::load(program_id, accounts)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key || token_info.mint_authority != *admin.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if max_lock_duration < VOTE_LOCK_WEEK
+        || max_lock_duration % VOTE_LOCK_WEEK != 0
+        || max_lock_duration > MAX_VOTE_LOCK_WEEKS as i64 * VOTE_LOCK_WEEK
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if locker.lamports() > 0 || locker_vault.lamports() > 0 {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            locker.key,
+            rent.minimum_balance(Locker::LEN),
+            Locker::LEN as u64,
+            program_id,
+        ),
+        &[admin.clone(), locker.clone(), system_program.clone()],
+        &[&[LOCKER_SEED, mint.key.as_ref(), &[locker_bump]]],
+    )?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            locker_vault.key,
+            rent.minimum_balance(TokenAccount::LEN),
+            TokenAccount::LEN as u64,
+            &spl_token::id(),
+        ),
+        &[admin.clone(), locker_vault.clone(), system_program.clone()],
+        &[&[LOCKER_VAULT_SEED, mint.key.as_ref(), &[locker_vault_bump]]],
+    )?;
+    invoke(
+        &spl_token::instruction::initialize_account3(&spl_token::id(), locker_vault.key, mint.key, locker_vault.key)?,
+        &[locker_vault.clone(), mint.clone()],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let locker_state = Locker::new(*mint.key, locker_bump, locker_vault_bump, max_lock_duration, now);
+    Locker::pack(locker_state, &mut locker.data.borrow_mut())?;
+
+    TokenEvent::LockerInitialized {
+        locker: *locker.key,
+        mint: *mint.key,
+        max_lock_duration,
+        timestamp: now,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_create_vote_lock(
+    accounts: &[AccountInfo],
+    amount: u64,
+    unlock_time: i64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let (
+        VoteLockAccounts {
+            vote_lock,
+            vote_lock_bump,
+            locker,
+            token_account,
+            locker_vault,
+            mint,
+            owner,
+            clock,
+            ..
+        },
+        system_program,
+    ) = VoteLockAccounts::load_with_system_program(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let now = clock.unix_timestamp;
+
+    if vote_lock.lamports() > 0 || !vote_lock.data_is_empty() {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut locker_state = Locker::unpack(&locker.data.borrow())?;
+    let unlock_time = locker_state.unlock_time(unlock_time, now)?;
+
+    invoke_signed(
+        &system_instruction::create_account(
+            owner.key,
+            vote_lock.key,
+            Rent::get()?.minimum_balance(VoteLock::LEN),
+            VoteLock::LEN as u64,
+            program_id,
+        ),
+        &[owner.clone(), vote_lock.clone(), system_program.clone()],
+        &[&[VOTE_LOCK_SEED, mint.key.as_ref(), owner.key.as_ref(), &[vote_lock_bump]]],
+    )?;
+
+    let lock = VoteLock {
+        header: AccountHeader::new::<VoteLock>(),
+        owner: *owner.key,
+        mint: *mint.key,
+        bump: vote_lock_bump,
+        amount,
+        unlock_time,
+        created_at: now,
+        updated_at: now,
+    };
+    let max_lock_duration = locker_state.max_lock_duration;
+    locker_state.update_lock((0, 0), (lock.slope(max_lock_duration), unlock_time), now)?;
+    locker_state.total_locked = locker_state.total_locked.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+    Self::lock_tokens(token_account, locker_vault, mint, owner, amount)?;
+
+    let event = TokenEvent::VoteLockCreated {
+        vote_lock: *vote_lock.key,
+        owner: *owner.key,
+        amount,
+        unlock_time,
+        voting_power: lock.power_at(max_lock_duration, now),
+        total_voting_power: locker_state.latest_point().power_at(now),
+        timestamp: now,
+    };
+
+    VoteLock::pack(lock, &mut vote_lock.data.borrow_mut())?;
+    Locker::pack(locker_state, &mut locker.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_increase_vote_lock_amount(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let VoteLockAccounts {
+        vote_lock,
+        locker,
+        token_account,
+        locker_vault,
+        mint,
+        owner,
+        clock,
+        ..
+    } = VoteLockAccounts::load_existing(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let now = clock.unix_timestamp;
+
+    let mut lock = VoteLock::unpack(&vote_lock.data.borrow())?;
+    if lock.owner != *owner.key || lock.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if now >= lock.unlock_time {
+        return Err(TokenError::VoteLockExpired.into());
+    }
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut locker_state = Locker::unpack(&locker.data.borrow())?;
+    let max_lock_duration = locker_state.max_lock_duration;
+    let old = (lock.slope(max_lock_duration), lock.unlock_time);
+    lock.amount = lock.amount.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+    lock.updated_at = now;
+    locker_state.update_lock(old, (lock.slope(max_lock_duration), lock.unlock_time), now)?;
+    locker_state.total_locked = locker_state.total_locked.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+    Self::lock_tokens(token_account, locker_vault, mint, owner, amount)?;
+
+    let event = TokenEvent::VoteLockIncreased {
+        vote_lock: *vote_lock.key,
+        owner: *owner.key,
+        amount,
+        locked_amount: lock.amount,
+        voting_power: lock.power_at(max_lock_duration, now),
+        total_voting_power: locker_state.latest_point().power_at(now),
+        timestamp: now,
+    };
+
+    VoteLock::pack(lock, &mut vote_lock.data.borrow_mut())?;
+    Locker::pack(locker_state, &mut locker.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_extend_vote_lock(accounts: &[AccountInfo], unlock_time: i64, program_id: &Pubkey) -> ProgramResult {
+    let ExtendVoteLockAccounts {
+        vote_lock,
+        locker,
+        mint,
+        owner,
+        clock,
+    } = ExtendVoteLockAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let now = clock.unix_timestamp;
+
+    let mut lock = VoteLock::unpack(&vote_lock.data.borrow())?;
+    if lock.owner != *owner.key || lock.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if now >= lock.unlock_time {
+        return Err(TokenError::VoteLockExpired.into());
+    }
+
+    let mut locker_state = Locker::unpack(&locker.data.borrow())?;
+    let unlock_time = locker_state.unlock_time(unlock_time, now)?;
+    if unlock_time <= lock.unlock_time {
+        return Err(TokenError::InvalidUnlockTime.into());
+    }
+    let max_lock_duration = locker_state.max_lock_duration;
+    let slope = lock.slope(max_lock_duration);
+    locker_state.update_lock((slope, lock.unlock_time), (slope, unlock_time), now)?;
+    lock.unlock_time = unlock_time;
+    lock.updated_at = now;
+
+    let event = TokenEvent::VoteLockExtended {
+        vote_lock: *vote_lock.key,
+        owner: *owner.key,
+        unlock_time,
+        voting_power: lock.power_at(max_lock_duration, now),
+        total_voting_power: locker_state.latest_point().power_at(now),
+        timestamp: now,
+    };
+
+    VoteLock::pack(lock, &mut vote_lock.data.borrow_mut())?;
+    Locker::pack(locker_state, &mut locker.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_withdraw_vote_lock(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let VoteLockAccounts {
+        vote_lock,
+        locker,
+        token_account,
+        locker_vault,
+        locker_vault_bump,
+        mint,
+        owner,
+        clock,
+        ..
+    } = VoteLockAccounts::load_existing(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let now = clock.unix_timestamp;
+
+    let lock = VoteLock::unpack(&vote_lock.data.borrow())?;
+    if lock.owner != *owner.key || lock.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if TokenAccount::unpack(&token_account.data.borrow())?.owner != *owner.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if now < lock.unlock_time {
+        return Err(TokenError::VoteLockNotExpired.into());
+    }
+
+    // The lock has no voting power left, this only checkpoints the locker.
+    let mut locker_state = Locker::unpack(&locker.data.borrow())?;
+    locker_state.update_lock((lock.slope(locker_state.max_lock_duration), lock.unlock_time), (0, 0), now)?;
+    locker_state.total_locked -= lock.amount;
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            locker_vault.key,
+            mint.key,
+            token_account.key,
+            locker_vault.key,
+            &[],
+            lock.amount,
+            decimals,
+        )?,
+        &[locker_vault.clone(), mint.clone(), token_account.clone()],
+        &[&[LOCKER_VAULT_SEED, mint.key.as_ref(), &[locker_vault_bump]]],
+    )?;
+
+    Locker::pack(locker_state, &mut locker.data.borrow_mut())?;
+    Self::close_account(vote_lock, owner);
+
+    TokenEvent::VoteLockWithdrawn {
+        vote_lock: *vote_lock.key,
+        owner: *owner.key,
+        amount: lock.amount,
+        timestamp: now,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_query_voting_power(accounts: &[AccountInfo], timestamp: i64, program_id: &Pubkey) -> ProgramResult {
+    let QueryVotingPowerAccounts { vote_lock, locker } = QueryVotingPowerAccounts::load(program_id, accounts)?;
+
+    let lock = VoteLock::unpack(&vote_lock.data.borrow())?;
+    let locker_state = Locker::unpack(&locker.data.borrow())?;
+    if lock.mint != locker_state.mint {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if timestamp < lock.updated_at {
+        return Err(TokenError::VotingPowerUnavailable.into());
+    }
+    set_return_data(&lock.power_at(locker_state.max_lock_duration, timestamp).to_le_bytes());
+    Ok(())
+}
+
+fn process_query_total_voting_power(accounts: &[AccountInfo], timestamp: i64, program_id: &Pubkey) -> ProgramResult {
+    let QueryTotalVotingPowerAccounts { locker } = QueryTotalVotingPowerAccounts::load(program_id, accounts)?;
+    let locker_state = Locker::unpack(&locker.data.borrow())?;
+    set_return_data(&locker_state.total_power_at(timestamp)?.to_le_bytes());
+    Ok(())
+}
+
+fn process_register_operator(accounts: &[AccountInfo], commission_bps: u16, program_id: &Pubkey) -> ProgramResult {
+    let RegisterOperatorAccounts {
+        operator,
+        operator_bump,
+        mint,
+        authority,
+        system_program,
+    } = RegisterOperatorAccounts::load(program_id, accounts)?;
+
+    if commission_bps as u64 > BASIS_POINTS {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if operator.lamports() > 0 || !operator.data_is_empty() {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+
+    invoke_signed(
+        &system_instruction::create_account(
+            authority.key,
+            operator.key,
+            Rent::get()?.minimum_balance(Operator::LEN),
+            Operator::LEN as u64,
+            program_id,
+        ),
+        &[authority.clone(), operator.clone(), system_program.clone()],
+        &[&[OPERATOR_SEED, mint.key.as_ref(), authority.key.as_ref(), &[operator_bump]]],
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let operator_state = Operator {
+        header: AccountHeader::new::<Operator>(),
+        mint: *mint.key,
+        authority: *authority.key,
+        bump: operator_bump,
+        reward_vault_bump: find_reward_vault_address(program_id, mint.key).1,
+        commission_bps,
+        delegations: 0,
+        pending_commission: 0,
+        collected_commission: 0,
+        created_at: now,
+    };
+    Operator::pack(operator_state, &mut operator.data.borrow_mut())?;
+
+    TokenEvent::OperatorRegistered {
+        operator: *operator.key,
+        mint: *mint.key,
+        authority: *authority.key,
+        commission_bps,
+        timestamp: now,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_delegate_stake(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let DelegateStakeAccounts {
+        stake_account,
+        staking_pool,
+        staking_config,
+        mint,
+        operator,
+        staker,
+        clock,
+        current_operator,
+    } = DelegateStakeAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    let mut operator_state = Operator::unpack(&operator.data.borrow())?;
+    if operator_state.mint != *mint.key {
+        return Err(TokenError::InvalidOperator.into());
+    }
+    if stake_info.operator == *operator.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // What was earned so far goes by the commission it was earned under.
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    pool.accrue(clock.unix_timestamp)?;
+    stake_info.settle(pool.reward_per_token)?;
+
+    let previous_operator = stake_info.operator;
+    if stake_info.is_delegated() {
+        if clock.unix_timestamp < stake_info.lock_end(config.lock_duration) {
+            return Err(TokenError::StakeLocked.into());
+        }
+        let current_operator = current_operator.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if *current_operator.key != previous_operator {
+            return Err(TokenError::InvalidOperator.into());
+        }
+        let mut current = Operator::unpack(&current_operator.data.borrow())?;
+        current.delegations -= 1;
+        current.pending_commission = current
+            .pending_commission
+            .checked_add(std::mem::take(&mut stake_info.pending_commission))
+            .ok_or(TokenError::AmountOverflow)?;
+        Operator::pack(current, &mut current_operator.data.borrow_mut())?;
+    }
+    stake_info.operator = *operator.key;
+    stake_info.commission_bps = operator_state.commission_bps;
+    stake_info.updated_at = clock.unix_timestamp;
+    operator_state.delegations += 1;
+
+    let event = TokenEvent::StakeDelegated {
+        stake_account: *stake_account.key,
+        staker: *staker.key,
+        operator: *operator.key,
+        previous_operator,
+        staked_amount: stake_info.amount,
+        timestamp: clock.unix_timestamp,
+    };
+
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+    Operator::pack(operator_state, &mut operator.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_undelegate_stake(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let UndelegateStakeAccounts {
+        stake_account,
+        staking_pool,
+        staking_config,
+        mint,
+        operator,
+        staker,
+        clock,
+    } = UndelegateStakeAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let config = StakingConfig::unpack(&staking_config.data.borrow())?.params;
+
+    let mut stake_info = StakeInfo::unpack(&stake_account.data.borrow())?;
+    if stake_info.owner != *staker.key || stake_info.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if !stake_info.is_delegated() || stake_info.operator != *operator.key {
+        return Err(TokenError::InvalidOperator.into());
+    }
+    if clock.unix_timestamp < stake_info.lock_end(config.lock_duration) {
+        return Err(TokenError::StakeLocked.into());
+    }
+
+    // What was earned so far goes by the commission it was earned under.
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    pool.accrue(clock.unix_timestamp)?;
+    stake_info.settle(pool.reward_per_token)?;
+    Self::pass_commission(&mut stake_info, Some(operator))?;
+
+    let mut operator_state = Operator::unpack(&operator.data.borrow())?;
+    operator_state.delegations -= 1;
+    stake_info.operator = Pubkey::default();
+    stake_info.commission_bps = 0;
+    stake_info.updated_at = clock.unix_timestamp;
+
+    let event = TokenEvent::StakeUndelegated {
+        stake_account: *stake_account.key,
+        staker: *staker.key,
+        operator: *operator.key,
+        staked_amount: stake_info.amount,
+        timestamp: clock.unix_timestamp,
+    };
+
+    StakeInfo::pack(stake_info, &mut stake_account.data.borrow_mut())?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+    Operator::pack(operator_state, &mut operator.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_claim_commission(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let ClaimCommissionAccounts {
+        operator,
+        reward_vault,
+        reward_vault_bump,
+        destination,
+        mint,
+        authority,
+        clock,
+    } = ClaimCommissionAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let mut operator_state = Operator::unpack(&operator.data.borrow())?;
+    if operator_state.authority != *authority.key || operator_state.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if TokenAccount::unpack(&destination.data.borrow())?.owner != *authority.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+
+    let amount = std::mem::take(&mut operator_state.pending_commission);
+    if amount == 0 {
+        return Err(TokenError::NoRewardsToClaim.into());
+    }
+    operator_state.collected_commission = operator_state
+        .collected_commission
+        .checked_add(amount)
+        .ok_or(TokenError::AmountOverflow)?;
+
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            reward_vault.key,
+            mint.key,
+            destination.key,
+            reward_vault.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[reward_vault.clone(), mint.clone(), destination.clone()],
+        &[&[REWARD_VAULT_SEED, mint.key.as_ref(), &[reward_vault_bump]]],
+    )?;
+
+    Operator::pack(operator_state, &mut operator.data.borrow_mut())?;
+
+    TokenEvent::CommissionClaimed {
+        operator: *operator.key,
+        authority: *authority.key,
+        amount,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_create_farm(
+    accounts: &[AccountInfo],
+    id: u64,
+    emission_rate: u64,
+    start_time: i64,
+    end_time: i64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let CreateFarmAccounts {
+        farm,
+        farm_vault,
+        farm_vault_bump,
+        farm_reward_vault,
+        farm_reward_vault_bump,
+        staked_mint,
+        reward_mint,
+        mint,
+        token_info: token_info_account,
+        admin,
+        system_program,
+    } = CreateFarmAccounts::load(program_id, accounts)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key || token_info.mint_authority != *admin.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    let (address, farm_bump) = find_farm_address(program_id, mint.key, id);
+    if *farm.key != address {
+        return Err(TokenError::InvalidProgramAddress.into());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if start_time >= end_time || end_time <= now {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if farm.lamports() > 0 || farm_vault.lamports() > 0 || farm_reward_vault.lamports() > 0 {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            farm.key,
+            rent.minimum_balance(Farm::LEN),
+            Farm::LEN as u64,
+            program_id,
+        ),
+        &[admin.clone(), farm.clone(), system_program.clone()],
+        &[&[FARM_SEED, mint.key.as_ref(), &id.to_le_bytes(), &[farm_bump]]],
+    )?;
+    for (vault, vault_mint, seed, bump) in [
+        (farm_vault, staked_mint, FARM_VAULT_SEED, farm_vault_bump),
+        (farm_reward_vault, reward_mint, FARM_REWARD_VAULT_SEED, farm_reward_vault_bump),
+    ] {
+        invoke_signed(
+            &system_instruction::create_account(
+                admin.key,
+                vault.key,
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            &[admin.clone(), vault.clone(), system_program.clone()],
+            &[&[seed, farm.key.as_ref(), &[bump]]],
+        )?;
+        invoke(
+            &spl_token::instruction::initialize_account3(&spl_token::id(), vault.key, vault_mint.key, vault.key)?,
+            &[vault.clone(), vault_mint.clone()],
+        )?;
+    }
+
+    let farm_state = Farm {
+        header: AccountHeader::new::<Farm>(),
+        mint: *mint.key,
+        id,
+        staked_mint: *staked_mint.key,
+        reward_mint: *reward_mint.key,
+        bump: farm_bump,
+        vault_bump: farm_vault_bump,
+        reward_vault_bump: farm_reward_vault_bump,
+        emission_rate,
+        start_time,
+        end_time,
+        total_staked: 0,
+        reward_per_token: 0,
+        undistributed_rewards: 0,
+        last_update_time: now,
+    };
+    Farm::pack(farm_state, &mut farm.data.borrow_mut())?;
+
+    TokenEvent::FarmCreated {
+        farm: *farm.key,
+        id,
+        staked_mint: *staked_mint.key,
+        reward_mint: *reward_mint.key,
+        emission_rate,
+        start_time,
+        end_time,
+        timestamp: now,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_fund_farm(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let FundFarmAccounts {
+        farm,
+        farm_reward_vault,
+        source_token_account,
+        reward_mint,
+        funder,
+        clock,
+    } = FundFarmAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let mut farm_state = Farm::unpack(&farm.data.borrow())?;
+    if farm_state.reward_mint != *reward_mint.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let decimals = Mint::unpack(&reward_mint.data.borrow())?.decimals;
+    invoke(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            source_token_account.key,
+            reward_mint.key,
+            farm_reward_vault.key,
+            funder.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[source_token_account.clone(), reward_mint.clone(), farm_reward_vault.clone(), funder.clone()],
+    )?;
+
+    farm_state.accrue(clock.unix_timestamp)?;
+    farm_state.undistributed_rewards =
+        farm_state.undistributed_rewards.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+
+    let event = TokenEvent::FarmFunded {
+        farm: *farm.key,
+        funder: *funder.key,
+        amount,
+        undistributed_rewards: farm_state.undistributed_rewards,
+        timestamp: clock.unix_timestamp,
+    };
+
+    Farm::pack(farm_state, &mut farm.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_update_farm(
+    accounts: &[AccountInfo],
+    emission_rate: u64,
+    end_time: i64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let UpdateFarmAccounts {
+        farm,
+        mint,
+        token_info: token_info_account,
+        admin,
+        clock,
+    } = UpdateFarmAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key || token_info.mint_authority != *admin.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    let mut farm_state = Farm::unpack(&farm.data.borrow())?;
+    if farm_state.mint != *mint.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if end_time <= clock.unix_timestamp || end_time <= farm_state.start_time {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Rewards so far were earned at the old rate.
+    farm_state.accrue(clock.unix_timestamp)?;
+    farm_state.emission_rate = emission_rate;
+    farm_state.end_time = end_time;
+
+    Farm::pack(farm_state, &mut farm.data.borrow_mut())?;
+
+    TokenEvent::FarmUpdated {
+        farm: *farm.key,
+        emission_rate,
+        end_time,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_farm_stake(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let (
+        FarmPositionAccounts {
+            farm_position,
+            farm_position_bump,
+            farm,
+            farm_vault,
+            token_account,
+            staked_mint,
+            owner,
+            clock,
+            ..
+        },
+        system_program,
+    ) = FarmPositionAccounts::load_with_system_program(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let now = clock.unix_timestamp;
+
+    let mut farm_state = Farm::unpack(&farm.data.borrow())?;
+    if farm_state.staked_mint != *staked_mint.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if now >= farm_state.end_time {
+        return Err(TokenError::FarmEnded.into());
+    }
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut position = if farm_position.data_is_empty() {
+        invoke_signed(
+            &system_instruction::create_account(
+                owner.key,
+                farm_position.key,
+                Rent::get()?.minimum_balance(FarmPosition::LEN),
+                FarmPosition::LEN as u64,
+                program_id,
+            ),
+            &[owner.clone(), farm_position.clone(), system_program.clone()],
+            &[&[FARM_POSITION_SEED, farm.key.as_ref(), owner.key.as_ref(), &[farm_position_bump]]],
+        )?;
+        FarmPosition {
+            header: AccountHeader::new::<FarmPosition>(),
+            farm: *farm.key,
+            owner: *owner.key,
+            bump: farm_position_bump,
+            amount: 0,
+            reward_per_token_paid: 0,
+            pending_rewards: 0,
+            created_at: now,
+            updated_at: now,
+        }
+    } else {
+        if farm_position.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        FarmPosition::unpack(&farm_position.data.borrow())?
+    };
+
+    farm_state.accrue(now)?;
+    position.settle(farm_state.reward_per_token)?;
+    position.amount = position.amount.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+    position.updated_at = now;
+    farm_state.total_staked = farm_state.total_staked.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+
+    let token_account_info = TokenAccount::unpack(&token_account.data.borrow())?;
+    if token_account_info.owner != *owner.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if token_account_info.amount < amount {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+    let decimals = Mint::unpack(&staked_mint.data.borrow())?.decimals;
+    invoke(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            token_account.key,
+            staked_mint.key,
+            farm_vault.key,
+            owner.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[token_account.clone(), staked_mint.clone(), farm_vault.clone(), owner.clone()],
+    )?;
+
+    let event = TokenEvent::FarmStaked {
+        farm_position: *farm_position.key,
+        owner: *owner.key,
+        amount,
+        staked_amount: position.amount,
+        total_staked: farm_state.total_staked,
+        timestamp: now,
+    };
+
+    FarmPosition::pack(position, &mut farm_position.data.borrow_mut())?;
+    Farm::pack(farm_state, &mut farm.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_farm_unstake(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    let FarmPositionAccounts {
+        farm_position,
+        farm,
+        farm_vault,
+        farm_vault_bump,
+        token_account,
+        staked_mint,
+        owner,
+        clock,
+        ..
+    } = FarmPositionAccounts::load_existing(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+    let now = clock.unix_timestamp;
+
+    let mut farm_state = Farm::unpack(&farm.data.borrow())?;
+    if farm_state.staked_mint != *staked_mint.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut position = FarmPosition::unpack(&farm_position.data.borrow())?;
+    if position.owner != *owner.key || TokenAccount::unpack(&token_account.data.borrow())?.owner != *owner.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if amount > position.amount {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+
+    farm_state.accrue(now)?;
+    position.settle(farm_state.reward_per_token)?;
+    position.amount -= amount;
+    position.updated_at = now;
+    farm_state.total_staked = farm_state.total_staked.checked_sub(amount).ok_or(TokenError::InsufficientFunds)?;
+
+    let decimals = Mint::unpack(&staked_mint.data.borrow())?.decimals;
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            farm_vault.key,
+            staked_mint.key,
+            token_account.key,
+            farm_vault.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[farm_vault.clone(), staked_mint.clone(), token_account.clone()],
+        &[&[FARM_VAULT_SEED, farm.key.as_ref(), &[farm_vault_bump]]],
+    )?;
+
+    let event = TokenEvent::FarmUnstaked {
+        farm_position: *farm_position.key,
+        owner: *owner.key,
+        amount,
+        staked_amount: position.amount,
+        total_staked: farm_state.total_staked,
+        timestamp: now,
+    };
+
+    FarmPosition::pack(position, &mut farm_position.data.borrow_mut())?;
+    Farm::pack(farm_state, &mut farm.data.borrow_mut())?;
+
+    event.emit();
+    Ok(())
+}
+
+fn process_claim_farm_rewards(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let ClaimFarmRewardsAccounts {
+        farm_position,
+        farm,
+        farm_reward_vault,
+        farm_reward_vault_bump,
+        destination,
+        reward_mint,
+        owner,
+        clock,
+    } = ClaimFarmRewardsAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let mut farm_state = Farm::unpack(&farm.data.borrow())?;
+    if farm_state.reward_mint != *reward_mint.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut position = FarmPosition::unpack(&farm_position.data.borrow())?;
+    if position.owner != *owner.key || TokenAccount::unpack(&destination.data.borrow())?.owner != *owner.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+
+    farm_state.accrue(clock.unix_timestamp)?;
+    position.settle(farm_state.reward_per_token)?;
+    let amount = std::mem::take(&mut position.pending_rewards);
+    if amount == 0 {
+        return Err(TokenError::NoRewardsToClaim.into());
+    }
+    position.updated_at = clock.unix_timestamp;
+
+    let decimals = Mint::unpack(&reward_mint.data.borrow())?.decimals;
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            farm_reward_vault.key,
+            reward_mint.key,
+            destination.key,
+            farm_reward_vault.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[farm_reward_vault.clone(), reward_mint.clone(), destination.clone()],
+        &[&[FARM_REWARD_VAULT_SEED, farm.key.as_ref(), &[farm_reward_vault_bump]]],
+    )?;
+
+    FarmPosition::pack(position, &mut farm_position.data.borrow_mut())?;
+    Farm::pack(farm_state, &mut farm.data.borrow_mut())?;
+
+    TokenEvent::FarmRewardsClaimed {
+        farm_position: *farm_position.key,
+        owner: *owner.key,
+        amount,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_query_farm_rewards(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let QueryFarmRewardsAccounts {
+        farm_position,
+        farm,
+        clock,
+    } = QueryFarmRewardsAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let position = FarmPosition::unpack(&farm_position.data.borrow())?;
+    let farm_state = Farm::unpack(&farm.data.borrow())?;
+    if position.farm != *farm.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let (reward_per_token, _) = farm_state.reward_per_token_at(clock.unix_timestamp)?;
+    set_return_data(&position.earned(reward_per_token)?.to_le_bytes());
+    Ok(())
+}
+
+fn process_initialize_emission_schedule(
+    accounts: &[AccountInfo],
+    epoch_duration: i64,
+    initial_emission: u64,
+    decay_bps: u16,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let InitializeEmissionScheduleAccounts {
+        emission_schedule,
+        emission_schedule_bump,
+        staking_pool,
+        mint,
+        token_info: token_info_account,
+        admin,
+        clock,
+        system_program,
+    } = InitializeEmissionScheduleAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key || token_info.mint_authority != *admin.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if emission_schedule.lamports() > 0 {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+    if epoch_duration < MIN_EPOCH_DURATION || decay_bps as u64 > BASIS_POINTS {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let rent = Rent::get()?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin.key,
+            emission_schedule.key,
+            rent.minimum_balance(EmissionSchedule::LEN),
+            EmissionSchedule::LEN as u64,
+            program_id,
+        ),
+        &[admin.clone(), emission_schedule.clone(), system_program.clone()],
+        &[&[EMISSION_SCHEDULE_SEED, mint.key.as_ref(), &[emission_schedule_bump]]],
+    )?;
+
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    pool.accrue(clock.unix_timestamp)?;
+    let epoch_emission = initial_emission.min(Self::remaining_community_rewards(&token_info, &pool)?);
+    let schedule = EmissionSchedule {
+        header: AccountHeader::new::<EmissionSchedule>(),
+        mint: *mint.key,
+        bump: emission_schedule_bump,
+        epoch_duration,
+        initial_emission,
+        decay_bps,
+        epoch: 0,
+        epoch_start: clock.unix_timestamp,
+        epoch_emission,
+    };
+    pool.reward_rate = schedule.rate(epoch_emission);
+    pool.emission_end = schedule.epoch_end()?;
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+    EmissionSchedule::pack(schedule, &mut emission_schedule.data.borrow_mut())?;
+
+    TokenEvent::EmissionScheduleInitialized {
+        emission_schedule: *emission_schedule.key,
+        epoch_duration,
+        initial_emission,
+        decay_bps,
+        epoch_emission,
+        timestamp: clock.unix_timestamp,
+    }
+    .emit();
+    Ok(())
+}
+
+fn process_advance_epoch(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let AdvanceEpochAccounts {
+        emission_schedule,
+        staking_pool,
+        token_info: token_info_account,
+        mint,
+        clock,
+    } = AdvanceEpochAccounts::load(program_id, accounts)?;
+    let clock = Clock::from_account_info(clock)?;
+
+    let token_info = TokenInfo::unpack(&token_info_account.data.borrow())?;
+    if token_info.mint != *mint.key {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let mut schedule = EmissionSchedule::unpack(&emission_schedule.data.borrow())?;
+    if clock.unix_timestamp < schedule.epoch_end()? {
+        return Err(TokenError::EpochNotEnded.into());
+    }
+
+    // Accrual stops at the end of each epoch, which the next one starts
+    // from. A late crank goes through the epochs it missed, each decaying
+    // from the last and emitting over its own window, up to
+    // MAX_EPOCHS_PER_CRANK of them; the next crank goes on from there.
+    let mut pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+    pool.accrue(clock.unix_timestamp)?;
+    let mut events = Vec::new();
+    let mut advanced = 0;
+    while advanced < MAX_EPOCHS_PER_CRANK && clock.unix_timestamp >= schedule.epoch_end()? {
+        let mut start_time = schedule.epoch_end()?;
+        let emission = schedule.next_emission(Self::remaining_community_rewards(&token_info, &pool)?);
+        // An emission that reached zero stays there, so the epochs missed
+        // after it are skipped at once.
+        let mut epochs = 1;
+        if emission == 0 {
+            let missed = (clock.unix_timestamp - start_time) / schedule.epoch_duration;
+            start_time += missed * schedule.epoch_duration;
+            epochs += missed as u64;
+        }
+        schedule.epoch += epochs;
+        schedule.epoch_start = start_time;
+        schedule.epoch_emission = emission;
+        let reward_rate = schedule.rate(emission);
+        let end_time = schedule.epoch_end()?;
+        pool.reward_rate = reward_rate;
+        pool.emission_end = end_time;
+        pool.accrue(clock.unix_timestamp)?;
+        events.push(TokenEvent::EpochAdvanced {
+            emission_schedule: *emission_schedule.key,
+            epoch: schedule.epoch,
+            start_time,
+            end_time,
+            emission,
+            reward_rate,
+            timestamp: clock.unix_timestamp,
+        });
+        advanced += 1;
+    }
+    StakingPool::pack(pool, &mut staking_pool.data.borrow_mut())?;
+    EmissionSchedule::pack(schedule, &mut emission_schedule.data.borrow_mut())?;
+
+    for event in events {
+        event.emit();
+    }
+    Ok(())
+}
+
+/// Community rewards an emission schedule can still emit: those not funded
+/// yet and those funded into the pool and not emitted yet.
+fn remaining_community_rewards(token_info: &TokenInfo, pool: &StakingPool) -> Result<u64, ProgramError> {
+    token_info
+        .community_rewards
+        .checked_add(pool.undistributed_rewards)
+        .ok_or_else(|| TokenError::AmountOverflow.into())
+}
+
+fn process_query_emission_schedule(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    let QueryEmissionScheduleAccounts { emission_schedule } =
+        QueryEmissionScheduleAccounts::load(program_id, accounts)?;
+
+    let schedule = EmissionSchedule::unpack(&emission_schedule.data.borrow())?;
+    let epoch = EpochEmission {
+        epoch: schedule.epoch,
+        start_time: schedule.epoch_start,
+        end_time: schedule.epoch_end()?,
+        emission: schedule.epoch_emission,
+    };
+    set_return_data(&borsh::to_vec(&epoch).map_err(|_| ProgramError::InvalidAccountData)?);
+    Ok(())
+}
+
+/// Moves `amount` from a token account of `owner` into the locker vault.
+fn lock_tokens<'a>(
+    token_account: &AccountInfo<'a>,
+    locker_vault: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let token_account_info = TokenAccount::unpack(&token_account.data.borrow())?;
+    if token_account_info.owner != *owner.key {
+        return Err(TokenError::InvalidAuthority.into());
+    }
+    if token_account_info.amount < amount {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+    let decimals = Mint::unpack(&mint.data.borrow())?.decimals;
+    invoke(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            token_account.key,
+            mint.key,
+            locker_vault.key,
+            owner.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[token_account.clone(), mint.clone(), locker_vault.clone(), owner.clone()],
+    )
+}
+
+/// Stakes the rewards earned by a position up to `now` into it, within the
+/// maximum total stake of `config` but without moving its lock. The caller
+/// moves the returned amount from the reward vault into the vault, see
+/// [`Self::restake_rewards`].
+fn compound(
+    config: &StakingParams,
+    stake_info: &mut StakeInfo,
+    pool: &mut StakingPool,
+    now: i64,
+) -> Result<u64, ProgramError> {
+    pool.accrue(now)?;
+    stake_info.settle(pool.reward_per_token)?;
+    let rewards = stake_info.pending_rewards;
+    if rewards == 0 {
+        return Ok(0);
+    }
+
+    let staked_amount = stake_info.amount.checked_add(rewards).ok_or(TokenError::AmountOverflow)?;
+    Self::reweigh(stake_info, pool, staked_amount)?;
+    if pool.total_staked > config.max_total_stake {
+        return Err(TokenError::MaxTotalStakeExceeded.into());
+    }
+    stake_info.amount = staked_amount;
+    stake_info.pending_rewards = 0;
+    stake_info.updated_at = now;
+    Ok(rewards)
+}
+
+/// Moves `amount` of compounded rewards from the reward vault into the
+/// vault.
+fn restake_rewards<'a>(
+    reward_vault: &AccountInfo<'a>,
+    reward_vault_bump: u8,
+    vault: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    amount: u64,
+    decimals: u8,
+) -> ProgramResult {
+    if amount == 0 {
+        return Ok(());
+    }
+    invoke_signed(
+        &spl_token::instruction::transfer_checked(
+            &spl_token::id(),
+            reward_vault.key,
+            mint.key,
+            vault.key,
+            reward_vault.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[reward_vault.clone(), mint.clone(), vault.clone()],
+        &[&[REWARD_VAULT_SEED, mint.key.as_ref(), &[reward_vault_bump]]],
+    )
+}
+
+/// Adds `amount` to a position within the limits of `config`, see
+/// [`StakeInfo::deposit`] for its lock. Rewards are settled at the old
+/// weight first.
+fn add_stake(
+    config: &StakingParams,
+    stake_info: &mut StakeInfo,
+    pool: &mut StakingPool,
+    amount: u64,
+    now: i64,
+) -> ProgramResult {
+    let staked_amount = stake_info.amount.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+    if staked_amount < config.min_stake {
+        return Err(TokenError::StakeBelowMinimum.into());
+    }
+
+    pool.accrue(now)?;
+    stake_info.settle(pool.reward_per_token)?;
+
+    Self::reweigh(stake_info, pool, staked_amount)?;
+    if pool.total_staked > config.max_total_stake {
+        return Err(TokenError::MaxTotalStakeExceeded.into());
+    }
+    stake_info.deposit(amount, now)?;
+    stake_info.updated_at = now;
+    Ok(())
+}
+
+/// Like [`Self::add_stake`], but leaves the start and unlock times of the
+/// position as they are.
+fn add_stake_keeping_lock(
+    config: &StakingParams,
+    stake_info: &mut StakeInfo,
+    pool: &mut StakingPool,
+    amount: u64,
+    now: i64,
+) -> ProgramResult {
+    let staked_amount = stake_info.amount.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+    if staked_amount < config.min_stake {
+        return Err(TokenError::StakeBelowMinimum.into());
+    }
+
+    pool.accrue(now)?;
+    stake_info.settle(pool.reward_per_token)?;
+
+    Self::reweigh(stake_info, pool, staked_amount)?;
+    if pool.total_staked > config.max_total_stake {
+        return Err(TokenError::MaxTotalStakeExceeded.into());
+    }
+    stake_info.amount = staked_amount;
+    stake_info.updated_at = now;
+    Ok(())
+}
+
+/// Takes `amount` out of an unlocked position within the limits of
+/// `config`. Rewards are settled at the old weight first.
+fn remove_stake(
+    config: &StakingParams,
+    stake_info: &mut StakeInfo,
+    pool: &mut StakingPool,
+    amount: u64,
+    now: i64,
+) -> ProgramResult {
+    if now < stake_info.lock_end(config.lock_duration) {
+        return Err(TokenError::StakeLocked.into());
+    }
+    Self::remove_locked_stake(config, stake_info, pool, amount, now)
+}
+
+/// Like [`Self::remove_stake`], whether the position is locked or not.
+fn remove_locked_stake(
+    config: &StakingParams,
+    stake_info: &mut StakeInfo,
+    pool: &mut StakingPool,
+    amount: u64,
+    now: i64,
+) -> ProgramResult {
+    if stake_info.amount < amount {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+    // A position is either closed out or kept at the minimum.
+    let remaining = stake_info.amount - amount;
+    if remaining > 0 && remaining < config.min_stake {
+        return Err(TokenError::StakeBelowMinimum.into());
+    }
+
+    pool.accrue(now)?;
+    stake_info.settle(pool.reward_per_token)?;
+
+    Self::reweigh(stake_info, pool, remaining)?;
+    stake_info.amount = remaining;
+    stake_info.updated_at = now;
+    Ok(())
+}
+
+/// Sets a position to `amount` at the weight of its lock tier, keeping
+/// `total_staked` and `total_weight` of the pool the sums over the positions
+/// it counts. A position from before the pool weighs nothing and is not
+/// counted until then, so this is also where the pool first counts it.
+/// Rewards must be settled first.
+fn reweigh(stake_info: &mut StakeInfo, pool: &mut StakingPool, amount: u64) -> ProgramResult {
+    let counted = if stake_info.weight > 0 { stake_info.amount } else { 0 };
+    let weight = stake_info.lock_tier.weight(amount)?;
+    pool.total_staked = pool
+        .total_staked
+        .checked_sub(counted)
+        .ok_or(TokenError::InsufficientFunds)?
+        .checked_add(amount)
+        .ok_or(TokenError::AmountOverflow)?;
+    pool.total_weight = pool
+        .total_weight
+        .checked_sub(stake_info.weight)
+        .ok_or(TokenError::InsufficientFunds)?
+        .checked_add(weight)
+        .ok_or(TokenError::AmountOverflow)?;
+    stake_info.weight = weight;
+    Ok(())
+}
+
+/// Passes the commission set aside in a delegated position on to its
+/// operator, which collects it from there. Rewards must be settled first.
+fn pass_commission(stake_info: &mut StakeInfo, operator: Option<&AccountInfo>) -> ProgramResult {
+    if !stake_info.is_delegated() {
+        return Ok(());
+    }
+    let operator = operator.ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if *operator.key != stake_info.operator {
+        return Err(TokenError::InvalidOperator.into());
+    }
+    let mut operator_state = Operator::unpack(&operator.data.borrow())?;
+    operator_state.pending_commission = operator_state
+        .pending_commission
+        .checked_add(std::mem::take(&mut stake_info.pending_commission))
+        .ok_or(TokenError::AmountOverflow)?;
+    Operator::pack(operator_state, &mut operator.data.borrow_mut())
+}
+
+/// Empties a program account, sending its rent to `destination`.
+fn close_account(account: &AccountInfo, destination: &AccountInfo) {
+    let lamports = account.lamports();
+    **account.lamports.borrow_mut() = 0;
+    **destination.lamports.borrow_mut() += lamports;
+    account.data.borrow_mut().fill(0);
+}
 }
@@ -1,150 +1,1361 @@
 use solana_program::program_error::ProgramError;
-
-
 use solana_program::pubkey::Pubkey;
-use solana_program::program_pack::{Pack, Sealed};
+use solana_program::program_pack::{IsInitialized, Pack, Sealed};
 
+use crate::error::TokenError;
 use crate::instruction::AllocationType;
 
-use arrayref::{array_ref, array_refs, array_mut_ref, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+
+/// Seed of the token account holding the tokens staked with a mint,
+/// derived from `[VAULT_SEED, mint]`. The vault is its own token authority.
+pub const VAULT_SEED: &[u8] = b"vault";
+
+pub fn find_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, mint.as_ref()], program_id)
+}
+
+/// Seed of the [`StakeInfo`] of an owner for a mint, derived from
+/// `[STAKE_SEED, mint, owner]`.
+pub const STAKE_SEED: &[u8] = b"stake";
+
+pub fn find_stake_address(program_id: &Pubkey, mint: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_SEED, mint.as_ref(), owner.as_ref()], program_id)
+}
+
+/// Seed of the [`StakingPool`] of a mint, derived from
+/// `[STAKING_POOL_SEED, mint]`.
+pub const STAKING_POOL_SEED: &[u8] = b"staking_pool";
+
+pub fn find_staking_pool_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKING_POOL_SEED, mint.as_ref()], program_id)
+}
+
+/// Seed of the token account holding the rewards of a mint's stakers,
+/// derived from `[REWARD_VAULT_SEED, mint]`. Like the stake vault it is its
+/// own token authority.
+pub const REWARD_VAULT_SEED: &[u8] = b"reward_vault";
+
+pub fn find_reward_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARD_VAULT_SEED, mint.as_ref()], program_id)
+}
+
+/// Seed of the [`StakingConfig`] of a mint, derived from
+/// `[STAKING_CONFIG_SEED, mint]`.
+pub const STAKING_CONFIG_SEED: &[u8] = b"staking_config";
+
+pub fn find_staking_config_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKING_CONFIG_SEED, mint.as_ref()], program_id)
+}
+
+/// Seed of an [`UnbondingEntry`] of a stake account, derived from
+/// `[UNBONDING_SEED, stake_account, id]` with `id` in little endian.
+pub const UNBONDING_SEED: &[u8] = b"unbonding";
+
+pub fn find_unbonding_address(program_id: &Pubkey, stake_account: &Pubkey, id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[UNBONDING_SEED, stake_account.as_ref(), &id.to_le_bytes()], program_id)
+}
+
+/// Seed of the [`LiquidPool`] of a mint, derived from
+/// `[LIQUID_POOL_SEED, mint]`. It owns the stake position of the pool and
+/// is the mint authority of its receipt token.
+pub const LIQUID_POOL_SEED: &[u8] = b"liquid_pool";
+
+pub fn find_liquid_pool_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LIQUID_POOL_SEED, mint.as_ref()], program_id)
+}
+
+/// Seed of the receipt token mint of a [`LiquidPool`], derived from
+/// `[RECEIPT_MINT_SEED, mint]`.
+pub const RECEIPT_MINT_SEED: &[u8] = b"receipt_mint";
+
+pub fn find_receipt_mint_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECEIPT_MINT_SEED, mint.as_ref()], program_id)
+}
+
+/// Seed of the [`Locker`] of a mint, derived from `[LOCKER_SEED, mint]`.
+pub const LOCKER_SEED: &[u8] = b"locker";
+
+pub fn find_locker_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LOCKER_SEED, mint.as_ref()], program_id)
+}
+
+/// Seed of the token account holding the tokens locked in the [`Locker`] of
+/// a mint, derived from `[LOCKER_VAULT_SEED, mint]`. Like the vault, it is
+/// its own token authority.
+pub const LOCKER_VAULT_SEED: &[u8] = b"locker_vault";
+
+pub fn find_locker_vault_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LOCKER_VAULT_SEED, mint.as_ref()], program_id)
+}
+
+/// Seed of the [`VoteLock`] of an owner, derived from `[VOTE_LOCK_SEED,
+/// mint, owner]`.
+pub const VOTE_LOCK_SEED: &[u8] = b"vote_lock";
+
+pub fn find_vote_lock_address(program_id: &Pubkey, mint: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VOTE_LOCK_SEED, mint.as_ref(), owner.as_ref()], program_id)
+}
+
+/// Seed of an [`Operator`], derived from `[OPERATOR_SEED, mint,
+/// authority]`.
+pub const OPERATOR_SEED: &[u8] = b"operator";
+
+pub fn find_operator_address(program_id: &Pubkey, mint: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[OPERATOR_SEED, mint.as_ref(), authority.as_ref()], program_id)
+}
+
+/// Seed of a [`Farm`], derived from `[FARM_SEED, mint, id]` with `id` in
+/// little endian. `mint` is the token whose admin created the farm, not
+/// the staked one.
+pub const FARM_SEED: &[u8] = b"farm";
+
+pub fn find_farm_address(program_id: &Pubkey, mint: &Pubkey, id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_SEED, mint.as_ref(), &id.to_le_bytes()], program_id)
+}
+
+/// Seed of the token account holding the staked tokens of a farm, derived
+/// from `[FARM_VAULT_SEED, farm]`. It is its own token authority.
+pub const FARM_VAULT_SEED: &[u8] = b"farm_vault";
+
+pub fn find_farm_vault_address(program_id: &Pubkey, farm: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_VAULT_SEED, farm.as_ref()], program_id)
+}
+
+/// Seed of the token account holding the rewards of a farm, derived from
+/// `[FARM_REWARD_VAULT_SEED, farm]`. It is its own token authority.
+pub const FARM_REWARD_VAULT_SEED: &[u8] = b"farm_reward_vault";
+
+pub fn find_farm_reward_vault_address(program_id: &Pubkey, farm: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_REWARD_VAULT_SEED, farm.as_ref()], program_id)
+}
+
+/// Seed of a [`FarmPosition`], derived from `[FARM_POSITION_SEED, farm,
+/// owner]`.
+pub const FARM_POSITION_SEED: &[u8] = b"farm_position";
+
+pub fn find_farm_position_address(program_id: &Pubkey, farm: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_POSITION_SEED, farm.as_ref(), owner.as_ref()], program_id)
+}
+
+/// Seed of the [`EmissionSchedule`] of a mint, derived from
+/// `[EMISSION_SCHEDULE_SEED, mint]`.
+pub const EMISSION_SCHEDULE_SEED: &[u8] = b"emission_schedule";
+
+pub fn find_emission_schedule_address(program_id: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[EMISSION_SCHEDULE_SEED, mint.as_ref()], program_id)
+}
+
+/// Scale of [`StakingPool::reward_per_token`], so that rewards smaller than
+/// the total stake still move the accumulator.
+pub const REWARD_PRECISION: u128 = 1_000_000_000_000;
+
+/// Scale of the slopes and biases of voting power, so that locks smaller
+/// than the longest lock duration still decay.
+pub const VOTING_POWER_PRECISION: u128 = 1_000_000_000_000;
+
+/// Unlock times of vote locks are rounded down to a whole week, so that the
+/// total voting power only changes slope on week boundaries.
+pub const VOTE_LOCK_WEEK: i64 = 7 * 24 * 60 * 60;
+
+/// Longest lock duration a [`Locker`] can allow, in weeks.
+pub const MAX_VOTE_LOCK_WEEKS: usize = 4 * 52;
+
+/// Number of recent [`VotingPowerPoint`]s a [`Locker`] keeps.
+pub const VOTING_POWER_HISTORY: usize = 64;
+
+/// Denominator of rates in basis points, such as
+/// [`LockTier::multiplier_bps`].
+pub const BASIS_POINTS: u64 = 10_000;
 
+/// Shortest epoch an [`EmissionSchedule`] can have, in seconds.
+pub const MIN_EPOCH_DURATION: i64 = 60 * 60;
 
+/// Most epochs one crank of an [`EmissionSchedule`] goes through, so that a
+/// crank far behind fits in a transaction and the next one picks up after it.
+pub const MAX_EPOCHS_PER_CRANK: u64 = 32;
+
+/// Lock chosen for a stake position. Longer locks weigh more in the split
+/// of the rewards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[borsh(use_discriminant = true)]
+pub enum LockTier {
+    Days30 = 0,
+    Days90 = 1,
+    Days180 = 2,
+    Days365 = 3,
+}
+
+impl LockTier {
+    /// Seconds from the last stake until the position unlocks.
+    pub fn duration(self) -> i64 {
+        let days = match self {
+            LockTier::Days30 => 30,
+            LockTier::Days90 => 90,
+            LockTier::Days180 => 180,
+            LockTier::Days365 => 365,
+        };
+        days * 24 * 60 * 60
+    }
+
+    /// Reward weight of one staked unit, in basis points.
+    pub fn multiplier_bps(self) -> u64 {
+        match self {
+            LockTier::Days30 => 10_000,
+            LockTier::Days90 => 12_500,
+            LockTier::Days180 => 15_000,
+            LockTier::Days365 => 20_000,
+        }
+    }
+
+    /// Reward weight of `amount` staked at this tier.
+    pub fn weight(self, amount: u64) -> Result<u64, ProgramError> {
+        let weight = amount as u128 * self.multiplier_bps() as u128 / BASIS_POINTS as u128;
+        u64::try_from(weight).map_err(|_| TokenError::AmountOverflow.into())
+    }
+}
+
+/// Kind of program account, stored in the first byte of every account so
+/// that one kind can never be read as another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[borsh(use_discriminant = true)]
+pub enum AccountType {
+    Uninitialized = 0,
+    TokenInfo = 1,
+    VestingSchedule = 2,
+    StakeInfo = 3,
+    StakingPool = 4,
+    StakingConfig = 5,
+    UnbondingEntry = 6,
+    LiquidPool = 7,
+    Locker = 8,
+    VoteLock = 9,
+    Operator = 10,
+    Farm = 11,
+    FarmPosition = 12,
+    EmissionSchedule = 13,
+}
+
+/// Header at the start of every program account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct AccountHeader {
+    pub account_type: AccountType,
+    pub version: u8,
+    pub is_initialized: bool,
+}
+
+impl AccountHeader {
+    pub const LEN: usize = 3;
+
+    /// Header of a freshly initialized `T`.
+    pub fn new<T: ProgramAccount>() -> Self {
+        Self {
+            account_type: T::ACCOUNT_TYPE,
+            version: T::VERSION,
+            is_initialized: true,
+        }
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = src.get(..Self::LEN).ok_or(ProgramError::InvalidAccountData)?;
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// A program account with a header.
+pub trait ProgramAccount: BorshSerialize + BorshDeserialize {
+    const ACCOUNT_TYPE: AccountType;
+    /// Current layout version.
+    const VERSION: u8;
+}
+
+/// Whether `data` has not been claimed by any account type yet.
+pub fn is_uninitialized(data: &[u8]) -> bool {
+    data.get(..AccountHeader::LEN)
+        .is_some_and(|header| header.iter().all(|&byte| byte == 0))
+}
+
+/// Decodes a `T` after checking that its header belongs to `T` in the
+/// current layout version.
+pub(crate) fn unpack_account<T: ProgramAccount>(src: &[u8]) -> Result<T, ProgramError> {
+    let header = AccountHeader::unpack(src)?;
+    if header.account_type == AccountType::Uninitialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if header.account_type != T::ACCOUNT_TYPE {
+        return Err(TokenError::InvalidAccountType.into());
+    }
+    if header.version != T::VERSION {
+        return Err(TokenError::UnsupportedAccountVersion.into());
+    }
+    T::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+pub(crate) fn pack_account<T: ProgramAccount>(account: &T, dst: &mut [u8]) {
+    account
+        .serialize(&mut &mut dst[..])
+        .expect("account LEN is too small for its layout");
+}
+
+/// Stake position of `owner` for `mint`, at the address given by
+/// [`find_stake_address`].
+///
+/// Positions migrated from before owners were recorded keep the default
+/// owner and mint, so they match no derived address and cannot be used.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub struct StakeInfo {
+    pub header: AccountHeader,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub bump: u8,
+    pub amount: u64,
+    /// `amount` scaled by the multiplier of `lock_tier`, the share of the
+    /// pool rewards the position earns.
+    pub weight: u64,
+    pub lock_tier: LockTier,
+    /// Deposit time of the staked tokens, averaged by amount, from which
+    /// the lock of the staking config runs.
+    pub start_time: i64,
+    /// Time from which the position can be unstaked, averaged by amount
+    /// like `start_time`.
+    pub unlock_time: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Pool accumulator when `pending_rewards` was last brought up to date.
+    pub reward_per_token_paid: u128,
+    /// Rewards earned and not claimed yet, as of `reward_per_token_paid`.
+    pub pending_rewards: u64,
+    /// Tokens requested out of the position and still in the vault. They
+    /// are not part of `amount` and earn nothing.
+    pub unbonding_amount: u64,
+    /// Id of the next [`UnbondingEntry`] of the position.
+    pub next_unbonding_id: u64,
+    /// Lets anyone compound the rewards of the position into it.
+    pub auto_compound: bool,
+    /// [`Operator`] the position is delegated to, or the default pubkey
+    /// for none.
+    pub operator: Pubkey,
+    /// Commission of `operator` when the position was delegated, taken out
+    /// of the rewards as they are settled.
+    pub commission_bps: u16,
+    /// Commission set aside for `operator` until it is passed on to the
+    /// operator account.
+    pub pending_commission: u64,
+}
+
+impl StakeInfo {
+    /// Rewards earned since `reward_per_token_paid`, split into the share
+    /// of the owner and the commission of the operator.
+    fn accrued(&self, reward_per_token: u128) -> Result<(u64, u64), ProgramError> {
+        let accrued = reward_per_token
+            .checked_sub(self.reward_per_token_paid)
+            .and_then(|delta| delta.checked_mul(self.weight as u128))
+            .map(|scaled| scaled / REWARD_PRECISION)
+            .ok_or(TokenError::AmountOverflow)?;
+        let accrued = u64::try_from(accrued).map_err(|_| TokenError::AmountOverflow)?;
+        let commission = (accrued as u128 * self.commission_bps as u128 / BASIS_POINTS as u128) as u64;
+        Ok((accrued - commission, commission))
+    }
+
+    /// Rewards earned by the owner and not claimed yet, given the current
+    /// pool accumulator.
+    pub fn earned(&self, reward_per_token: u128) -> Result<u64, ProgramError> {
+        let (accrued, _) = self.accrued(reward_per_token)?;
+        accrued
+            .checked_add(self.pending_rewards)
+            .ok_or_else(|| TokenError::AmountOverflow.into())
+    }
+
+    /// Moves everything earned up to `reward_per_token` into
+    /// `pending_rewards` and `pending_commission`. Must run before `weight`
+    /// or `commission_bps` change.
+    pub fn settle(&mut self, reward_per_token: u128) -> Result<(), ProgramError> {
+        let (accrued, commission) = self.accrued(reward_per_token)?;
+        self.pending_rewards = self.pending_rewards.checked_add(accrued).ok_or(TokenError::AmountOverflow)?;
+        self.pending_commission =
+            self.pending_commission.checked_add(commission).ok_or(TokenError::AmountOverflow)?;
+        self.reward_per_token_paid = reward_per_token;
+        Ok(())
+    }
+
+    pub fn is_delegated(&self) -> bool {
+        self.operator != Pubkey::default()
+    }
+
+    /// Time until which the position is locked, by its tier and by the
+    /// `lock_duration` of the staking config.
+    pub fn lock_end(&self, lock_duration: i64) -> i64 {
+        self.unlock_time.max(self.start_time.saturating_add(lock_duration))
+    }
+
+    /// Adds `amount` deposited at `now`, which unlocks after the duration of
+    /// `lock_tier`. The start and unlock times move to the average of the
+    /// tokens already staked and the new ones, weighted by amount, so a top
+    /// up only delays the position in proportion to its size. Does not
+    /// touch `weight`.
+    pub fn deposit(&mut self, amount: u64, now: i64) -> Result<(), ProgramError> {
+        let total = self.amount.checked_add(amount).ok_or(TokenError::AmountOverflow)?;
+        let average = |held: i64, added: i64| {
+            if total == 0 {
+                return added;
+            }
+            let sum = self.amount as i128 * held as i128 + amount as i128 * added as i128;
+            (sum / total as i128) as i64
+        };
+        self.start_time = average(self.start_time, now);
+        self.unlock_time = average(self.unlock_time, now + self.lock_tier.duration());
+        self.amount = total;
+        Ok(())
+    }
+}
+
+/// Reward state shared by every stake of `mint`.
+///
+/// Rewards are emitted at `reward_rate` per second out of
+/// `undistributed_rewards` and split between stakers by weight through
+/// `reward_per_token`, the rewards earned by one unit of weight since the
+/// pool was created, scaled by [`REWARD_PRECISION`]. Under an
+/// [`EmissionSchedule`] emission also stops at `emission_end`, the end of
+/// the current epoch, until the next one starts.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct StakingPool {
+    pub header: AccountHeader,
+    pub mint: Pubkey,
+    pub bump: u8,
+    pub reward_vault_bump: u8,
+    pub total_staked: u64,
+    /// Sum of the weights of all positions.
+    pub total_weight: u64,
+    /// Rewards emitted per second since `last_update_time`, following the
+    /// [`EmissionSchedule`] once there is one, or else the [`StakingConfig`].
+    pub reward_rate: u64,
+    pub reward_per_token: u128,
+    /// Funded rewards not emitted yet.
+    pub undistributed_rewards: u64,
+    pub last_update_time: i64,
+    /// End of the current epoch of the [`EmissionSchedule`], or zero
+    /// without one.
+    pub emission_end: i64,
+}
+
+impl StakingPool {
+    /// Time up to which rewards are emitted, given the current time.
+    fn emission_time(&self, now: i64) -> i64 {
+        if self.emission_end == 0 {
+            now
+        } else {
+            now.min(self.emission_end)
+        }
+    }
+
+    /// Accumulator at `now`, with emission stopping when the funded rewards
+    /// run out or the epoch ends. Returns it with the rewards emitted since
+    /// the last update.
+    pub fn reward_per_token_at(&self, now: i64) -> Result<(u128, u64), ProgramError> {
+        let elapsed = self.emission_time(now).saturating_sub(self.last_update_time);
+        if elapsed <= 0 || self.total_weight == 0 {
+            return Ok((self.reward_per_token, 0));
+        }
+        let emitted = (self.reward_rate as u128 * elapsed as u128).min(self.undistributed_rewards as u128);
+        let reward_per_token = emitted
+            .checked_mul(REWARD_PRECISION)
+            .map(|scaled| scaled / self.total_weight as u128)
+            .and_then(|delta| self.reward_per_token.checked_add(delta))
+            .ok_or(TokenError::AmountOverflow)?;
+        Ok((reward_per_token, emitted as u64))
+    }
+
+    /// Brings the accumulator up to `now`, or to the end of the epoch if it
+    /// is over, so that the next epoch emits from there. Must run before
+    /// `total_weight`, `reward_rate` or `undistributed_rewards` change.
+    pub fn accrue(&mut self, now: i64) -> Result<(), ProgramError> {
+        let (reward_per_token, emitted) = self.reward_per_token_at(now)?;
+        self.reward_per_token = reward_per_token;
+        self.undistributed_rewards -= emitted;
+        self.last_update_time = self.last_update_time.max(self.emission_time(now));
+        Ok(())
+    }
+}
+
+/// How the penalty of an early unstake is charged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[borsh(use_discriminant = true)]
+pub enum PenaltyCurve {
+    /// Locked positions cannot be unstaked early.
+    #[default]
+    Disabled = 0,
+    /// The penalty rate shrinks linearly to zero over the lock.
+    LinearDecay = 1,
+    /// The penalty rate is charged in full until the position unlocks.
+    Flat = 2,
+}
+
+/// Where the penalty of an early unstake goes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[borsh(use_discriminant = true)]
+pub enum PenaltyDestination {
+    #[default]
+    Burn = 0,
+    /// The `treasury` token account of the staking params.
+    Treasury = 1,
+    /// The reward vault, adding to the rewards still to emit.
+    RewardPool = 2,
+}
+
+/// Staking rules set by the admin.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct StakingParams {
+    /// Smallest position that can be left staked, other than none.
+    pub min_stake: u64,
+    /// Seconds after the last stake before any of it can be unstaked.
+    pub lock_duration: i64,
+    /// Rewards emitted per second, shared by all stakers. Ignored once the
+    /// pool is on an [`EmissionSchedule`].
+    pub reward_rate: u64,
+    /// Cap on the total staked with the mint.
+    pub max_total_stake: u64,
+    /// Stops staking and unstaking.
+    pub paused: bool,
+    /// Seconds between requesting an unstake and withdrawing it. With no
+    /// unbonding period, tokens are unstaked at once instead.
+    pub unbonding_period: i64,
+    pub penalty_curve: PenaltyCurve,
+    /// Share of an early unstake kept as penalty, in basis points, at the
+    /// start of the lock.
+    pub penalty_bps: u16,
+    pub penalty_destination: PenaltyDestination,
+    /// Token account receiving penalties sent to the treasury.
+    pub treasury: Pubkey,
+}
+
+impl StakingParams {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 1 + 8 + 1 + 2 + 1 + 32;
+
+    /// Penalty charged for taking `amount` out of `stake_info` at `now`.
+    /// Unlocked positions pay none.
+    pub fn early_unstake_penalty(&self, stake_info: &StakeInfo, amount: u64, now: i64) -> Result<u64, ProgramError> {
+        let lock_end = stake_info.lock_end(self.lock_duration);
+        if now >= lock_end {
+            return Ok(0);
+        }
+        let full = amount as u128 * self.penalty_bps as u128 / BASIS_POINTS as u128;
+        let penalty = match self.penalty_curve {
+            PenaltyCurve::Disabled => return Err(TokenError::StakeLocked.into()),
+            PenaltyCurve::Flat => full,
+            PenaltyCurve::LinearDecay => {
+                let remaining = (lock_end as i128 - now as i128) as u128;
+                let span = (lock_end as i128 - stake_info.start_time as i128) as u128;
+                full * remaining / span.max(remaining)
+            }
+        };
+        Ok(penalty as u64)
+    }
+
+    /// The account early unstake penalties of `mint` are paid to: the mint
+    /// itself when they are burned.
+    pub fn penalty_account(&self, program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+        match self.penalty_destination {
+            PenaltyDestination::Burn => *mint,
+            PenaltyDestination::Treasury => self.treasury,
+            PenaltyDestination::RewardPool => find_reward_vault_address(program_id, mint).0,
+        }
+    }
+}
+
+/// Staking rules of a mint, at the address given by
+/// [`find_staking_config_address`]. The staking pool runs at the configured
+/// `reward_rate`.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct StakingConfig {
+    pub header: AccountHeader,
+    pub mint: Pubkey,
+    pub bump: u8,
+    /// Bump of the vault at [`find_vault_address`], which has no state of
+    /// its own to keep it in.
+    pub vault_bump: u8,
+    pub params: StakingParams,
+}
+
+impl ProgramAccount for StakingConfig {
+    const ACCOUNT_TYPE: AccountType = AccountType::StakingConfig;
+    const VERSION: u8 = 4;
+}
+
+impl Sealed for StakingConfig {}
+
+impl IsInitialized for StakingConfig {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for StakingConfig {
+    const LEN: usize = AccountHeader::LEN + 32 + 1 + 1 + StakingParams::LEN;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+impl ProgramAccount for StakingPool {
+    const ACCOUNT_TYPE: AccountType = AccountType::StakingPool;
+    const VERSION: u8 = 3;
+}
+
+impl Sealed for StakingPool {}
+
+impl IsInitialized for StakingPool {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for StakingPool {
+    const LEN: usize = AccountHeader::LEN + 32 + 1 + 1 + 8 + 8 + 8 + 16 + 8 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+/// Epoch-based emission of the community rewards of a mint, at the address
+/// given by [`find_emission_schedule_address`].
+///
+/// Each epoch lasts `epoch_duration` seconds and emits `epoch_emission`
+/// through the [`StakingPool`] of the mint, at the per second rate that
+/// spreads it over the epoch. Every new epoch emits `decay_bps` less than
+/// the one before, capped by the community rewards left: those not funded
+/// yet in [`TokenInfo::community_rewards`] plus those funded and not emitted
+/// yet.
+///
+/// [`TokenInfo::community_rewards`]: crate::token_info::TokenInfo::community_rewards
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct EmissionSchedule {
+    pub header: AccountHeader,
+    pub mint: Pubkey,
+    pub bump: u8,
+    pub epoch_duration: i64,
+    pub initial_emission: u64,
+    /// Share of the emission dropped at every new epoch, in basis points.
+    /// 5000 halves it.
+    pub decay_bps: u16,
+    /// Number of the current epoch, from zero.
+    pub epoch: u64,
+    pub epoch_start: i64,
+    /// Rewards the current epoch emits.
+    pub epoch_emission: u64,
+}
+
+impl EmissionSchedule {
+    pub fn epoch_end(&self) -> Result<i64, ProgramError> {
+        self.epoch_start
+            .checked_add(self.epoch_duration)
+            .ok_or_else(|| TokenError::AmountOverflow.into())
+    }
+
+    /// Emission of the epoch after the current one, out of `remaining`
+    /// community rewards.
+    pub fn next_emission(&self, remaining: u64) -> u64 {
+        let decayed = self.epoch_emission as u128 * (BASIS_POINTS - self.decay_bps as u64) as u128
+            / BASIS_POINTS as u128;
+        (decayed as u64).min(remaining)
+    }
+
+    /// Per second rate emitting `emission` over an epoch, rounded down.
+    pub fn rate(&self, emission: u64) -> u64 {
+        emission / self.epoch_duration as u64
+    }
+}
+
+impl ProgramAccount for EmissionSchedule {
+    const ACCOUNT_TYPE: AccountType = AccountType::EmissionSchedule;
+    const VERSION: u8 = 1;
+}
+
+impl Sealed for EmissionSchedule {}
+
+impl IsInitialized for EmissionSchedule {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for EmissionSchedule {
+    const LEN: usize = AccountHeader::LEN + 32 + 1 + 8 + 8 + 2 + 8 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+/// Current epoch of an [`EmissionSchedule`], as returned by
+/// `QueryEmissionSchedule`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct EpochEmission {
+    pub epoch: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub emission: u64,
+}
+
+/// Tokens on their way out of a stake position, at the address given by
+/// [`find_unbonding_address`]. They can be withdrawn from `withdrawable_at`
+/// or put back into the position before.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct UnbondingEntry {
+    pub header: AccountHeader,
+    pub stake_account: Pubkey,
+    /// Receipt holder withdrawing the tokens of an entry of a
+    /// [`LiquidPool`], or the default pubkey for the owner of the position.
+    pub owner: Pubkey,
+    pub id: u64,
+    pub bump: u8,
     pub amount: u64,
+    pub requested_at: i64,
+    pub withdrawable_at: i64,
+}
+
+impl ProgramAccount for UnbondingEntry {
+    const ACCOUNT_TYPE: AccountType = AccountType::UnbondingEntry;
+    const VERSION: u8 = 2;
+}
+
+impl Sealed for UnbondingEntry {}
+
+impl IsInitialized for UnbondingEntry {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for UnbondingEntry {
+    const LEN: usize = AccountHeader::LEN + 32 + 32 + 8 + 1 + 8 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+/// Liquid staking pool of a mint, at the address given by
+/// [`find_liquid_pool_address`].
+///
+/// The pool stakes its deposits in a stake position of its own, at the
+/// shortest lock tier, and mints `receipt_mint` tokens for them. A receipt
+/// token is worth a share of the position, which grows as the rewards of
+/// the position are staked again, so the exchange rate only goes up.
+///
+/// Receipts change hands freely, so the position is never locked: a lock
+/// would be shared by every holder and pushed back by each deposit. Under
+/// an unbonding period receipts are redeemed through unbonding entries of
+/// the position, each withdrawn by the holder who requested it.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct LiquidPool {
+    pub header: AccountHeader,
+    pub mint: Pubkey,
+    pub receipt_mint: Pubkey,
+    pub bump: u8,
+    pub receipt_mint_bump: u8,
+}
+
+impl LiquidPool {
+    /// Receipt tokens minted for depositing `amount` into a pool holding
+    /// `staked` against a receipt supply of `receipt_supply`. Only the first
+    /// deposit mints one to one; receipts left without any stake behind
+    /// them have no rate to mint against.
+    pub fn receipt_for(amount: u64, staked: u64, receipt_supply: u64) -> Result<u64, ProgramError> {
+        if receipt_supply == 0 {
+            return Ok(amount);
+        }
+        if staked == 0 {
+            return Err(TokenError::EmptyLiquidPool.into());
+        }
+        let receipt = amount as u128 * receipt_supply as u128 / staked as u128;
+        u64::try_from(receipt).map_err(|_| TokenError::AmountOverflow.into())
+    }
+
+    /// Tokens redeemed for burning `receipt_amount` of `receipt_supply`
+    /// against a pool holding `staked`.
+    pub fn underlying_for(receipt_amount: u64, staked: u64, receipt_supply: u64) -> u64 {
+        if receipt_supply == 0 {
+            return 0;
+        }
+        (receipt_amount as u128 * staked as u128 / receipt_supply as u128) as u64
+    }
+}
+
+impl ProgramAccount for LiquidPool {
+    const ACCOUNT_TYPE: AccountType = AccountType::LiquidPool;
+    const VERSION: u8 = 1;
+}
+
+impl Sealed for LiquidPool {}
+
+impl IsInitialized for LiquidPool {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for LiquidPool {
+    const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 1;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+/// Total voting power of a [`Locker`] from `timestamp`, `bias` decreasing
+/// by `slope` every second. Both are scaled by [`VOTING_POWER_PRECISION`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct VotingPowerPoint {
+    pub bias: u128,
+    pub slope: u128,
+    pub timestamp: i64,
+}
+
+impl VotingPowerPoint {
+    /// Voting power at `now`, as long as the slope does not change from
+    /// `timestamp` to `now`.
+    pub fn power_at(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.timestamp).max(0) as u128;
+        (self.bias.saturating_sub(self.slope.saturating_mul(elapsed)) / VOTING_POWER_PRECISION) as u64
+    }
+
+    /// Moves the point to `now`, which must not be past a slope change.
+    fn advance(&mut self, now: i64) {
+        let elapsed = now.saturating_sub(self.timestamp).max(0) as u128;
+        self.bias = self.bias.saturating_sub(self.slope.saturating_mul(elapsed));
+        self.timestamp = now;
+    }
+}
+
+/// Operator stake positions of `mint` can be delegated to, at the address
+/// given by [`find_operator_address`]. It earns `commission_bps` of the
+/// rewards of the positions delegated to it, passed on to it as they are
+/// settled until `authority` collects it.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct Operator {
+    pub header: AccountHeader,
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub bump: u8,
+    /// Bump of the reward vault of `mint`, which commission is paid from.
+    pub reward_vault_bump: u8,
+    pub commission_bps: u16,
+    /// Number of positions delegated to the operator.
+    pub delegations: u64,
+    /// Commission passed on by delegated positions whenever they are
+    /// settled, not collected yet.
+    pub pending_commission: u64,
+    pub collected_commission: u64,
+    pub created_at: i64,
+}
+
+impl ProgramAccount for Operator {
+    const ACCOUNT_TYPE: AccountType = AccountType::Operator;
+    const VERSION: u8 = 2;
+}
+
+impl Sealed for Operator {}
+
+impl IsInitialized for Operator {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for Operator {
+    const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 1 + 2 + 8 + 8 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+/// Staking farm at the address given by [`find_farm_address`]. Holders of
+/// `staked_mint` earn `reward_mint` at `emission_rate` per second between
+/// `start_time` and `end_time`, split by stake through `reward_per_token`
+/// like in a [`StakingPool`], and only out of funded rewards.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct Farm {
+    pub header: AccountHeader,
+    /// Token whose admin created the farm.
+    pub mint: Pubkey,
+    pub id: u64,
+    pub staked_mint: Pubkey,
+    pub reward_mint: Pubkey,
+    pub bump: u8,
+    pub vault_bump: u8,
+    pub reward_vault_bump: u8,
+    pub emission_rate: u64,
     pub start_time: i64,
+    pub end_time: i64,
+    pub total_staked: u64,
+    /// Rewards earned by one staked unit since the farm was created, scaled
+    /// by [`REWARD_PRECISION`].
+    pub reward_per_token: u128,
+    /// Funded rewards not emitted yet.
+    pub undistributed_rewards: u64,
+    pub last_update_time: i64,
+}
+
+impl Farm {
+    /// Accumulator at `now`, emitting only between `start_time` and
+    /// `end_time` and out of the funded rewards. Returns it with the rewards
+    /// emitted since the last update.
+    pub fn reward_per_token_at(&self, now: i64) -> Result<(u128, u64), ProgramError> {
+        let elapsed = now.min(self.end_time).saturating_sub(self.last_update_time.max(self.start_time));
+        if elapsed <= 0 || self.total_staked == 0 {
+            return Ok((self.reward_per_token, 0));
+        }
+        let emitted = (self.emission_rate as u128 * elapsed as u128).min(self.undistributed_rewards as u128);
+        let reward_per_token = emitted
+            .checked_mul(REWARD_PRECISION)
+            .map(|scaled| scaled / self.total_staked as u128)
+            .and_then(|delta| self.reward_per_token.checked_add(delta))
+            .ok_or(TokenError::AmountOverflow)?;
+        Ok((reward_per_token, emitted as u64))
+    }
+
+    /// Brings the accumulator up to `now`. Must run before `total_staked`,
+    /// `undistributed_rewards` or the emission change.
+    pub fn accrue(&mut self, now: i64) -> Result<(), ProgramError> {
+        let (reward_per_token, emitted) = self.reward_per_token_at(now)?;
+        self.reward_per_token = reward_per_token;
+        self.undistributed_rewards -= emitted;
+        self.last_update_time = self.last_update_time.max(now);
+        Ok(())
+    }
+}
+
+impl ProgramAccount for Farm {
+    const ACCOUNT_TYPE: AccountType = AccountType::Farm;
+    const VERSION: u8 = 1;
+}
+
+impl Sealed for Farm {}
+
+impl IsInitialized for Farm {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for Farm {
+    const LEN: usize = AccountHeader::LEN + 32 + 8 + 32 + 32 + 1 + 1 + 1 + 8 + 8 + 8 + 8 + 16 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+/// Stake of `owner` in a [`Farm`], at the address given by
+/// [`find_farm_position_address`].
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct FarmPosition {
+    pub header: AccountHeader,
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    pub bump: u8,
+    pub amount: u64,
+    /// [`Farm::reward_per_token`] when the rewards were last settled.
+    pub reward_per_token_paid: u128,
+    /// Rewards settled and not claimed yet.
+    pub pending_rewards: u64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl FarmPosition {
+    /// Rewards earned and not claimed yet, given the current farm
+    /// accumulator.
+    pub fn earned(&self, reward_per_token: u128) -> Result<u64, ProgramError> {
+        reward_per_token
+            .checked_sub(self.reward_per_token_paid)
+            .and_then(|delta| delta.checked_mul(self.amount as u128))
+            .and_then(|scaled| u64::try_from(scaled / REWARD_PRECISION).ok())
+            .and_then(|accrued| accrued.checked_add(self.pending_rewards))
+            .ok_or_else(|| TokenError::AmountOverflow.into())
+    }
+
+    /// Moves everything earned up to `reward_per_token` into
+    /// `pending_rewards`. Must run before `amount` changes.
+    pub fn settle(&mut self, reward_per_token: u128) -> Result<(), ProgramError> {
+        self.pending_rewards = self.earned(reward_per_token)?;
+        self.reward_per_token_paid = reward_per_token;
+        Ok(())
+    }
 }
 
+impl ProgramAccount for FarmPosition {
+    const ACCOUNT_TYPE: AccountType = AccountType::FarmPosition;
+    const VERSION: u8 = 1;
+}
+
+impl Sealed for FarmPosition {}
+
+impl IsInitialized for FarmPosition {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for FarmPosition {
+    const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 8 + 16 + 8 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+/// Vote-escrow locker of a mint, at the address given by
+/// [`find_locker_address`].
+///
+/// Tokens locked in a [`VoteLock`] give voting power that decays linearly
+/// to zero at its unlock time, starting from the locked amount for a lock
+/// of `max_lock_duration`. The locker tracks the sum of that power as a
+/// bias and a slope, checkpointed in `history` every time they change, and
+/// the slope every lock gives up at the end of its week in
+/// `slope_changes`. Both are vectors of a fixed length, which keeps the
+/// account off the stack.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct Locker {
+    pub header: AccountHeader,
+    pub mint: Pubkey,
+    pub bump: u8,
+    pub vault_bump: u8,
+    /// Longest lock, a whole number of weeks.
+    pub max_lock_duration: i64,
+    pub total_locked: u64,
+    /// Number of points written to `history` after the first, the latest
+    /// one being at `epoch % VOTING_POWER_HISTORY`.
+    pub epoch: u64,
+    /// The last [`VOTING_POWER_HISTORY`] points.
+    pub history: Vec<VotingPowerPoint>,
+    /// Slope ending on each of the coming weeks, indexed by week number
+    /// modulo `MAX_VOTE_LOCK_WEEKS + 1`.
+    pub slope_changes: Vec<u128>,
+}
+
+impl Locker {
+    /// Empty locker created at `now`.
+    pub fn new(mint: Pubkey, bump: u8, vault_bump: u8, max_lock_duration: i64, now: i64) -> Self {
+        let mut history = vec![VotingPowerPoint::default(); VOTING_POWER_HISTORY];
+        history[0].timestamp = now;
+        Self {
+            header: AccountHeader::new::<Self>(),
+            mint,
+            bump,
+            vault_bump,
+            max_lock_duration,
+            total_locked: 0,
+            epoch: 0,
+            history,
+            slope_changes: vec![0; MAX_VOTE_LOCK_WEEKS + 1],
+        }
+    }
+
+    pub fn latest_point(&self) -> VotingPowerPoint {
+        self.history[self.epoch as usize % VOTING_POWER_HISTORY]
+    }
+
+    fn slope_change_index(week_end: i64) -> usize {
+        (week_end / VOTE_LOCK_WEEK) as usize % (MAX_VOTE_LOCK_WEEKS + 1)
+    }
+
+    fn push_point(&mut self, point: VotingPowerPoint) {
+        self.epoch += 1;
+        self.history[self.epoch as usize % VOTING_POWER_HISTORY] = point;
+    }
+
+    /// Latest point moved forward to `now`, through the slope changes on
+    /// the way. With `consume`, the slope changes are cleared and a point is
+    /// recorded at each of them, so that the history stays linear between
+    /// points.
+    fn point_at(&mut self, now: i64, consume: bool) -> VotingPowerPoint {
+        let mut point = self.latest_point();
+        let mut week_end = (point.timestamp.div_euclid(VOTE_LOCK_WEEK) + 1) * VOTE_LOCK_WEEK;
+        // Every lock ends within the slope changes ahead of the latest
+        // point, so the slope is zero once they are all passed.
+        while week_end <= now && point.slope > 0 {
+            point.advance(week_end);
+            let index = Self::slope_change_index(week_end);
+            point.slope = point.slope.saturating_sub(self.slope_changes[index]);
+            if consume && self.slope_changes[index] > 0 {
+                self.slope_changes[index] = 0;
+                self.push_point(point);
+            }
+            week_end += VOTE_LOCK_WEEK;
+        }
+        point.advance(now);
+        point
+    }
+
+    /// Checkpoints the locker at `now` with a lock going from
+    /// `(old_slope, old_end)` to `(new_slope, new_end)`. Either side may be
+    /// an expired or empty lock.
+    pub fn update_lock(&mut self, old: (u128, i64), new: (u128, i64), now: i64) -> Result<(), ProgramError> {
+        let mut point = self.point_at(now, true);
+        let (old_slope, old_end) = old;
+        if old_end > now {
+            point.bias = point.bias.saturating_sub(old_slope * (old_end - now) as u128);
+            point.slope = point.slope.saturating_sub(old_slope);
+            let index = Self::slope_change_index(old_end);
+            self.slope_changes[index] = self.slope_changes[index].saturating_sub(old_slope);
+        }
+        let (new_slope, new_end) = new;
+        if new_end > now {
+            let bias = new_slope.checked_mul((new_end - now) as u128).ok_or(TokenError::AmountOverflow)?;
+            point.bias = point.bias.checked_add(bias).ok_or(TokenError::AmountOverflow)?;
+            point.slope = point.slope.checked_add(new_slope).ok_or(TokenError::AmountOverflow)?;
+            let index = Self::slope_change_index(new_end);
+            self.slope_changes[index] =
+                self.slope_changes[index].checked_add(new_slope).ok_or(TokenError::AmountOverflow)?;
+        }
+        self.push_point(point);
+        Ok(())
+    }
+
+    /// Total voting power at `timestamp`. Timestamps before the oldest
+    /// point still kept cannot be answered.
+    pub fn total_power_at(&self, timestamp: i64) -> Result<u64, ProgramError> {
+        if timestamp >= self.latest_point().timestamp {
+            return Ok(self.clone().point_at(timestamp, false).power_at(timestamp));
+        }
+        let kept = (self.epoch as usize + 1).min(VOTING_POWER_HISTORY);
+        for age in 0..kept {
+            let point = self.history[(self.epoch as usize - age) % VOTING_POWER_HISTORY];
+            if point.timestamp <= timestamp {
+                return Ok(point.power_at(timestamp));
+            }
+        }
+        // The first point is the creation of the locker.
+        if self.epoch < VOTING_POWER_HISTORY as u64 {
+            return Ok(0);
+        }
+        Err(TokenError::VotingPowerUnavailable.into())
+    }
+
+    /// Checks that a lock may unlock at `unlock_time`, rounded down to a
+    /// week, from `now`, and returns the rounded time.
+    pub fn unlock_time(&self, unlock_time: i64, now: i64) -> Result<i64, ProgramError> {
+        let unlock_time = unlock_time.div_euclid(VOTE_LOCK_WEEK) * VOTE_LOCK_WEEK;
+        if unlock_time <= now || unlock_time > now.saturating_add(self.max_lock_duration) {
+            return Err(TokenError::InvalidUnlockTime.into());
+        }
+        Ok(unlock_time)
+    }
+}
+
+impl ProgramAccount for Locker {
+    const ACCOUNT_TYPE: AccountType = AccountType::Locker;
+    const VERSION: u8 = 1;
+}
+
+impl Sealed for Locker {}
+
+impl IsInitialized for Locker {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for Locker {
+    const LEN: usize = AccountHeader::LEN
+        + 32
+        + 1
+        + 1
+        + 8
+        + 8
+        + 8
+        + 4
+        + (16 + 16 + 8) * VOTING_POWER_HISTORY
+        + 4
+        + 16 * (MAX_VOTE_LOCK_WEEKS + 1);
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+/// Tokens of `owner` locked in the [`Locker`] of `mint` until
+/// `unlock_time`, at the address given by [`find_vote_lock_address`]. The
+/// voting power of the lock belongs to the owner and cannot be moved.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct VoteLock {
+    pub header: AccountHeader,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub bump: u8,
+    pub amount: u64,
+    /// End of the lock, on a week boundary.
+    pub unlock_time: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl VoteLock {
+    /// Voting power lost every second, scaled by
+    /// [`VOTING_POWER_PRECISION`].
+    pub fn slope(&self, max_lock_duration: i64) -> u128 {
+        self.amount as u128 * VOTING_POWER_PRECISION / max_lock_duration as u128
+    }
+
+    /// Voting power of the lock at `now`, zero once it unlocks.
+    pub fn power_at(&self, max_lock_duration: i64, now: i64) -> u64 {
+        let remaining = self.unlock_time.saturating_sub(now).max(0) as u128;
+        (self.slope(max_lock_duration) * remaining / VOTING_POWER_PRECISION) as u64
+    }
+}
+
+impl ProgramAccount for VoteLock {
+    const ACCOUNT_TYPE: AccountType = AccountType::VoteLock;
+    const VERSION: u8 = 1;
+}
+
+impl Sealed for VoteLock {}
+
+impl IsInitialized for VoteLock {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
+impl Pack for VoteLock {
+    const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 8 + 8 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
+    }
+}
+
+/// Tokens of an allocation vesting to `beneficiary`.
+///
+/// Nothing vests before `cliff_time`. At the cliff, the amount that vested
+/// linearly from `start_time` up to it unlocks at once, that is
+/// `total_amount * (cliff_time - start_time) / (end_time - start_time)`,
+/// and the rest keeps vesting linearly until `end_time`. A cliff at the
+/// start is no cliff, and a cliff at the end vests everything then.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub struct VestingSchedule {
+    pub header: AccountHeader,
     pub beneficiary: Pubkey,
     pub total_amount: u64,
     pub released_amount: u64,
     pub start_time: i64,
+    /// Between `start_time` and `end_time`.
+    pub cliff_time: i64,
     pub end_time: i64,
     pub allocation_type: AllocationType,
 }
 
+impl VestingSchedule {
+    /// Amount vested at `now`, growing linearly from `start_time` to
+    /// `end_time` once `cliff_time` is reached.
+    pub fn vested_amount(&self, now: i64) -> u64 {
+        if now < self.start_time || now < self.cliff_time {
+            return 0;
+        }
+        let total_time = self.end_time.saturating_sub(self.start_time);
+        let elapsed_time = now.saturating_sub(self.start_time);
+        if elapsed_time >= total_time {
+            return self.total_amount;
+        }
+        (self.total_amount as u128 * elapsed_time as u128 / total_time as u128) as u64
+    }
+
+    /// Amount vested at `now` and not released yet.
+    pub fn releasable_amount(&self, now: i64) -> u64 {
+        self.vested_amount(now).saturating_sub(self.released_amount)
+    }
+}
+
+impl ProgramAccount for VestingSchedule {
+    const ACCOUNT_TYPE: AccountType = AccountType::VestingSchedule;
+    const VERSION: u8 = 2;
+}
+
 impl Sealed for VestingSchedule {}
 
+impl IsInitialized for VestingSchedule {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
 impl Pack for VestingSchedule {
-    const LEN: usize = 32 + 8 + 8 + 8 + 8 + 1;
+    const LEN: usize = AccountHeader::LEN + 32 + 8 + 8 + 8 + 8 + 8 + 1;
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let mut offset = 0;
-        dst[offset..offset+32].copy_from_slice(self.beneficiary.as_ref());
-        offset += 32;
-        dst[offset..offset+8].copy_from_slice(&self.total_amount.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+8].copy_from_slice(&self.released_amount.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+8].copy_from_slice(&self.start_time.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+8].copy_from_slice(&self.end_time.to_le_bytes());
-        offset += 8;
-        dst[offset] = self.allocation_type as u8;
+        pack_account(self, dst);
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let mut offset = 0;
-        let beneficiary = Pubkey::new(&src[offset..offset+32]);
-        offset += 32;
-        let total_amount = u64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let released_amount = u64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let start_time = i64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let end_time = i64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let allocation_type = match src[offset] {
-            0 => AllocationType::Team,
-            1 => AllocationType::Investors,
-            2 => AllocationType::Liquidity,
-            3 => AllocationType::Development,
-            4 => AllocationType::Community,
-            5 => AllocationType::Strategic,
-            _ => return Err(ProgramError::InvalidAccountData),
-        };
-
-        Ok(VestingSchedule {
-            beneficiary,
-            total_amount,
-            released_amount,
-            start_time,
-            end_time,
-            allocation_type,
-        })
+        unpack_account(src)
     }
 }
 
-impl VestingSchedule {
+impl ProgramAccount for StakeInfo {
+    const ACCOUNT_TYPE: AccountType = AccountType::StakeInfo;
+    const VERSION: u8 = 7;
+}
 
-    pub fn pack(&self, output: &mut [u8]) -> Result<(), ProgramError> {
-        let output = array_mut_ref![output, 0, VestingSchedule::LEN];
-        let (
-            beneficiary,
-            total_amount,
-            released_amount,
-            start_time,
-            end_time,
-            allocation_type,
-        ) = mut_array_refs![output, 32, 8, 8, 8, 8, 1];
-
-        beneficiary.copy_from_slice(self.beneficiary.as_ref());
-        *total_amount = self.total_amount.to_le_bytes();
-        *released_amount = self.released_amount.to_le_bytes();
-        *start_time = self.start_time.to_le_bytes();
-        *end_time = self.end_time.to_le_bytes();
-        allocation_type[0] = self.allocation_type as u8;
+impl Sealed for StakeInfo {}
 
-        Ok(())
+impl IsInitialized for StakeInfo {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
     }
+}
+
+impl Pack for StakeInfo {
+    const LEN: usize = AccountHeader::LEN + 32 + 32 + 1 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 16 + 8 + 8 + 8 + 1 + 32 + 2 + 8;
 
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let input = array_ref![input, 0, VestingSchedule::LEN];
-        let (
-            beneficiary,
-            total_amount,
-            released_amount,
-            start_time,
-            end_time,
-            allocation_type,
-        ) = array_refs![input, 32, 8, 8, 8, 8, 1];
-
-        Ok(VestingSchedule {
-            beneficiary: Pubkey::new_from_array(*beneficiary),
-            total_amount: u64::from_le_bytes(*total_amount),
-            released_amount: u64::from_le_bytes(*released_amount),
-            start_time: i64::from_le_bytes(*start_time),
-            end_time: i64::from_le_bytes(*end_time),
-            allocation_type: match allocation_type[0] {
-                0 => AllocationType::Team,
-                1 => AllocationType::Investors,
-                2 => AllocationType::Liquidity,
-                3 => AllocationType::Development,
-                4 => AllocationType::Community,
-                5 => AllocationType::Strategic,
-                _ => return Err(ProgramError::InvalidAccountData),
-            },
-        })
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_account(self, dst);
     }
-}
 
-impl StakeInfo {
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let input = array_ref![input, 0, 16];
-        let (amount, start_time) = array_refs![input, 8, 8];
-        Ok(Self {
-            amount: u64::from_le_bytes(*amount),
-            start_time: i64::from_le_bytes(*start_time),
-        })
-    }
-
-    pub fn pack(&self, output: &mut [u8]) -> Result<(), ProgramError> {
-        let output = array_mut_ref![output, 0, 16];
-        let (amount_out, start_time_out) = mut_array_refs![output, 8, 8];
-        *amount_out = self.amount.to_le_bytes();
-        *start_time_out = self.start_time.to_le_bytes();
-        Ok(())
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        unpack_account(src)
     }
 }
-
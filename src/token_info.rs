@@ -1,8 +1,21 @@
 use solana_program::pubkey::Pubkey;
-use solana_program::program_pack::{Pack, Sealed};
+use solana_program::program_pack::{IsInitialized, Pack, Sealed};
 use solana_program::program_error::ProgramError;
 
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+
+use crate::instruction::AllocationType;
+use crate::state::{pack_account, unpack_account, AccountHeader, AccountType, ProgramAccount};
+
+pub const DECIMALS: u8 = 9;
+
+/// 5 billion whole tokens. Computed in a const so that a supply which does
+/// not fit in a u64 at `DECIMALS` fails to compile instead of wrapping.
+pub const TOTAL_SUPPLY: u64 = 5_000_000_000 * 10u64.pow(DECIMALS as u32);
+
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub struct TokenInfo {
+    pub header: AccountHeader,
     pub total_supply: u64,
     pub team_allocation: u64,
     pub investors_allocation: u64,
@@ -14,62 +27,52 @@ pub struct TokenInfo {
     pub mint: Pubkey,
 }
 
+impl TokenInfo {
+    /// Amount of `allocation_type` not yet committed to vesting schedules.
+    pub fn allocation(&self, allocation_type: AllocationType) -> u64 {
+        match allocation_type {
+            AllocationType::Team => self.team_allocation,
+            AllocationType::Investors => self.investors_allocation,
+            AllocationType::Liquidity => self.liquidity_reserve,
+            AllocationType::Development => self.development_reserve,
+            AllocationType::Community => self.community_rewards,
+            AllocationType::Strategic => self.strategic_reserve,
+        }
+    }
+
+    pub fn allocation_mut(&mut self, allocation_type: AllocationType) -> &mut u64 {
+        match allocation_type {
+            AllocationType::Team => &mut self.team_allocation,
+            AllocationType::Investors => &mut self.investors_allocation,
+            AllocationType::Liquidity => &mut self.liquidity_reserve,
+            AllocationType::Development => &mut self.development_reserve,
+            AllocationType::Community => &mut self.community_rewards,
+            AllocationType::Strategic => &mut self.strategic_reserve,
+        }
+    }
+}
+
+impl ProgramAccount for TokenInfo {
+    const ACCOUNT_TYPE: AccountType = AccountType::TokenInfo;
+    const VERSION: u8 = 1;
+}
+
 impl Sealed for TokenInfo {}
 
+impl IsInitialized for TokenInfo {
+    fn is_initialized(&self) -> bool {
+        self.header.is_initialized
+    }
+}
+
 impl Pack for TokenInfo {
-    const LEN: usize = 8 * 7 + 32 * 2;
+    const LEN: usize = AccountHeader::LEN + 8 * 7 + 32 * 2;
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let mut offset = 0;
-        dst[offset..offset+8].copy_from_slice(&self.total_supply.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+8].copy_from_slice(&self.team_allocation.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+8].copy_from_slice(&self.investors_allocation.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+8].copy_from_slice(&self.liquidity_reserve.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+8].copy_from_slice(&self.development_reserve.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+8].copy_from_slice(&self.community_rewards.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+8].copy_from_slice(&self.strategic_reserve.to_le_bytes());
-        offset += 8;
-        dst[offset..offset+32].copy_from_slice(self.mint_authority.as_ref());
-        offset += 32;
-        dst[offset..offset+32].copy_from_slice(self.mint.as_ref());
+        pack_account(self, dst);
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let mut offset = 0;
-        let total_supply = u64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let team_allocation = u64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let investors_allocation = u64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let liquidity_reserve = u64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let development_reserve = u64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let community_rewards = u64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let strategic_reserve = u64::from_le_bytes(src[offset..offset+8].try_into().unwrap());
-        offset += 8;
-        let mint_authority = Pubkey::from_bytes(&src[offset..offset+32]);
-        offset += 32;
-        let mint = Pubkey::from_bytes(&src[offset..offset+32]);
-
-        Ok(TokenInfo {
-            total_supply,
-            team_allocation,
-            investors_allocation,
-            liquidity_reserve,
-            development_reserve,
-            community_rewards,
-            strategic_reserve,
-            mint_authority,
-            mint,
-        })
+        unpack_account(src)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,1874 @@
+//! Account validation shared by every instruction.
+//!
+//! Each instruction has an accounts struct whose `load` takes the accounts
+//! in the order documented on [`TokenInstruction`] and checks signers,
+//! writability, owners, program addresses and sysvar identities before any
+//! handler logic runs. Handlers only deal with account data.
+//!
+//! [`TokenInstruction`]: crate::instruction::TokenInstruction
+
+use solana_program::account_info::{next_account_info, AccountInfo};
+use solana_program::program_error::ProgramError;
+use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
+use solana_program::{bpf_loader_upgradeable, system_program, sysvar};
+
+use crate::error::TokenError;
+use crate::state::{
+    EmissionSchedule, Farm, FarmPosition, LiquidPool, Locker, Operator, StakeInfo, StakingConfig, StakingPool, VoteLock,
+    EMISSION_SCHEDULE_SEED, FARM_POSITION_SEED, FARM_REWARD_VAULT_SEED, FARM_VAULT_SEED, LIQUID_POOL_SEED, LOCKER_SEED, LOCKER_VAULT_SEED,
+    OPERATOR_SEED, RECEIPT_MINT_SEED, REWARD_VAULT_SEED, STAKE_SEED, STAKING_CONFIG_SEED, STAKING_POOL_SEED, VAULT_SEED,
+    VOTE_LOCK_SEED,
+};
+
+/// Checks on a single account, chained while loading an accounts struct.
+pub trait AccountCheck: Sized {
+    fn signer(self) -> Result<Self, ProgramError>;
+    fn writable(self) -> Result<Self, ProgramError>;
+    fn owned_by(self, owner: &Pubkey) -> Result<Self, ProgramError>;
+    /// The account is the given sysvar or program.
+    fn is(self, key: &Pubkey) -> Result<Self, ProgramError>;
+    /// The account is the program address of `seeds`. Returns its bump.
+    /// Searching for the bump is costly, so this is for accounts being
+    /// created; the others are checked against their stored bump.
+    fn pda(self, seeds: &[&[u8]], program_id: &Pubkey) -> Result<(Self, u8), ProgramError>;
+    /// The account is the program address of `seeds` and `bump`.
+    fn pda_with_bump(self, seeds: &[&[u8]], bump: u8, program_id: &Pubkey) -> Result<Self, ProgramError>;
+}
+
+impl<'a, 'b> AccountCheck for &'a AccountInfo<'b> {
+    fn signer(self) -> Result<Self, ProgramError> {
+        if !self.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Ok(self)
+    }
+
+    fn writable(self) -> Result<Self, ProgramError> {
+        if !self.is_writable {
+            return Err(TokenError::AccountNotWritable.into());
+        }
+        Ok(self)
+    }
+
+    fn owned_by(self, owner: &Pubkey) -> Result<Self, ProgramError> {
+        if self.owner != owner {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        Ok(self)
+    }
+
+    fn is(self, key: &Pubkey) -> Result<Self, ProgramError> {
+        if self.key != key {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(self)
+    }
+
+    fn pda(self, seeds: &[&[u8]], program_id: &Pubkey) -> Result<(Self, u8), ProgramError> {
+        let (address, bump) = Pubkey::find_program_address(seeds, program_id);
+        if *self.key != address {
+            return Err(TokenError::InvalidProgramAddress.into());
+        }
+        Ok((self, bump))
+    }
+
+    fn pda_with_bump(self, seeds: &[&[u8]], bump: u8, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        let bump = [bump];
+        let seeds = [seeds, &[&bump]].concat();
+        match Pubkey::create_program_address(&seeds, program_id) {
+            Ok(address) if address == *self.key => Ok(self),
+            _ => Err(TokenError::InvalidProgramAddress.into()),
+        }
+    }
+}
+
+/// The operator a position is delegated to, which follows the other accounts
+/// of an instruction only for delegated positions. The processor checks it
+/// against the position.
+fn delegated_operator<'a, 'b>(
+    iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    program_id: &Pubkey,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    iter.next()
+        .map(|operator| operator.writable().and_then(|operator| operator.owned_by(program_id)))
+        .transpose()
+}
+
+/// The treasury token account of new staking params, which follows the
+/// other accounts of an instruction only when penalties go to the treasury.
+/// The processor checks it against the params.
+fn penalty_treasury<'a, 'b>(
+    iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    iter.next().map(|treasury| treasury.owned_by(&spl_token::id())).transpose()
+}
+
+pub struct InitializeMintAccounts<'a, 'b> {
+    pub mint: &'a AccountInfo<'b>,
+    pub mint_authority: &'a AccountInfo<'b>,
+    pub freeze_authority: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeMintAccounts<'a, 'b> {
+    pub fn load(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let loaded = Self {
+            mint: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            mint_authority: next_account_info(iter)?,
+            freeze_authority: next_account_info(iter)?,
+            rent: next_account_info(iter)?.is(&sysvar::rent::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct InitializeAccountAccounts<'a, 'b> {
+    pub account: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeAccountAccounts<'a, 'b> {
+    pub fn load(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let loaded = Self {
+            account: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            mint: next_account_info(iter)?.owned_by(&spl_token::id())?,
+            owner: next_account_info(iter)?,
+            rent: next_account_info(iter)?.is(&sysvar::rent::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct TransferAccounts<'a, 'b> {
+    pub source: &'a AccountInfo<'b>,
+    pub destination: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> TransferAccounts<'a, 'b> {
+    pub fn load(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let loaded = Self {
+            source: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            destination: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            authority: next_account_info(iter)?.signer()?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct BurnAccounts<'a, 'b> {
+    pub account: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> BurnAccounts<'a, 'b> {
+    pub fn load(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let loaded = Self {
+            account: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            mint: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            authority: next_account_info(iter)?.signer()?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct MintToAccounts<'a, 'b> {
+    pub mint: &'a AccountInfo<'b>,
+    pub account: &'a AccountInfo<'b>,
+    pub mint_authority: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> MintToAccounts<'a, 'b> {
+    pub fn load(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let loaded = Self {
+            mint: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            account: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            mint_authority: next_account_info(iter)?.signer()?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+/// Accounts of `Freeze` and `Thaw`.
+pub struct FreezeAccounts<'a, 'b> {
+    pub account: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub freeze_authority: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> FreezeAccounts<'a, 'b> {
+    pub fn load(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let loaded = Self {
+            account: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            mint: next_account_info(iter)?.owned_by(&spl_token::id())?,
+            freeze_authority: next_account_info(iter)?.signer()?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct SetAuthorityAccounts<'a, 'b> {
+    pub account_or_mint: &'a AccountInfo<'b>,
+    pub current_authority: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> SetAuthorityAccounts<'a, 'b> {
+    pub fn load(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let loaded = Self {
+            account_or_mint: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            current_authority: next_account_info(iter)?.signer()?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+/// Accounts of `Stake` and `Unstake`.
+pub struct StakeAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub token_account: &'a AccountInfo<'b>,
+    pub vault: &'a AccountInfo<'b>,
+    pub vault_bump: u8,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    /// Only passed for a delegated position.
+    pub operator: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> StakeAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let token_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let staker = next_account_info(iter)?.signer()?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let vault_bump = config.vault_bump;
+        let vault = vault.pda_with_bump(&[VAULT_SEED, mint.key.as_ref()], vault_bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        let clock = next_account_info(iter)?.is(&sysvar::clock::id())?;
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(Self {
+            stake_account,
+            token_account,
+            vault,
+            vault_bump,
+            staking_pool,
+            staking_config,
+            mint,
+            staker,
+            clock,
+            operator: delegated_operator(iter, program_id)?,
+        })
+    }
+}
+
+pub struct InitializeStakeAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub stake_bump: u8,
+    pub mint: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeStakeAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let owner = next_account_info(iter)?.writable()?.signer()?;
+        let (stake_account, stake_bump) =
+            stake_account.pda(&[STAKE_SEED, mint.key.as_ref(), owner.key.as_ref()], program_id)?;
+        Ok(Self {
+            stake_account,
+            stake_bump,
+            mint,
+            owner,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        })
+    }
+}
+
+pub struct InitializeStakingPoolAccounts<'a, 'b> {
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_pool_bump: u8,
+    pub reward_vault: &'a AccountInfo<'b>,
+    pub reward_vault_bump: u8,
+    pub vault: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeStakingPoolAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let staking_pool = next_account_info(iter)?.writable()?;
+        let reward_vault = next_account_info(iter)?.writable()?;
+        let vault = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let (staking_pool, staking_pool_bump) =
+            staking_pool.pda(&[STAKING_POOL_SEED, mint.key.as_ref()], program_id)?;
+        let (reward_vault, reward_vault_bump) =
+            reward_vault.pda(&[REWARD_VAULT_SEED, mint.key.as_ref()], program_id)?;
+        let (vault, _) = vault.pda(&[VAULT_SEED, mint.key.as_ref()], program_id)?;
+        let loaded = Self {
+            staking_pool,
+            staking_pool_bump,
+            reward_vault,
+            reward_vault_bump,
+            vault,
+            mint,
+            token_info: next_account_info(iter)?.owned_by(program_id)?,
+            admin: next_account_info(iter)?.writable()?.signer()?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct InitializeStakingConfigAccounts<'a, 'b> {
+    pub staking_config: &'a AccountInfo<'b>,
+    pub staking_config_bump: u8,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub treasury: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> InitializeStakingConfigAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let staking_config = next_account_info(iter)?.writable()?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let token_info = next_account_info(iter)?.owned_by(program_id)?;
+        let admin = next_account_info(iter)?.writable()?.signer()?;
+        let (staking_config, staking_config_bump) =
+            staking_config.pda(&[STAKING_CONFIG_SEED, mint.key.as_ref()], program_id)?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        Ok(Self {
+            staking_config,
+            staking_config_bump,
+            staking_pool,
+            mint,
+            token_info,
+            admin,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+            treasury: penalty_treasury(iter)?,
+        })
+    }
+}
+
+pub struct UpdateStakingConfigAccounts<'a, 'b> {
+    pub staking_config: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub treasury: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> UpdateStakingConfigAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let staking_config = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let token_info = next_account_info(iter)?.owned_by(program_id)?;
+        let admin = next_account_info(iter)?.signer()?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        Ok(Self {
+            staking_config,
+            staking_pool,
+            mint,
+            token_info,
+            admin,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+            treasury: penalty_treasury(iter)?,
+        })
+    }
+}
+
+pub struct FundRewardsAccounts<'a, 'b> {
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub reward_vault: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub source_token_account: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> FundRewardsAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let reward_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let token_info = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let source_token_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let admin = next_account_info(iter)?.signer()?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let reward_vault =
+            reward_vault.pda_with_bump(&[REWARD_VAULT_SEED, mint.key.as_ref()], pool.reward_vault_bump, program_id)?;
+        let loaded = Self {
+            staking_pool,
+            reward_vault,
+            token_info,
+            source_token_account,
+            mint,
+            admin,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct ClaimRewardsAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub reward_vault: &'a AccountInfo<'b>,
+    pub reward_vault_bump: u8,
+    pub destination: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    /// Only passed for a delegated position.
+    pub operator: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> ClaimRewardsAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let reward_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let destination = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let staker = next_account_info(iter)?.signer()?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let reward_vault_bump = pool.reward_vault_bump;
+        let reward_vault =
+            reward_vault.pda_with_bump(&[REWARD_VAULT_SEED, mint.key.as_ref()], reward_vault_bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        let clock = next_account_info(iter)?.is(&sysvar::clock::id())?;
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(Self {
+            stake_account,
+            staking_pool,
+            reward_vault,
+            reward_vault_bump,
+            destination,
+            mint,
+            staker,
+            clock,
+            operator: delegated_operator(iter, program_id)?,
+        })
+    }
+}
+
+pub struct ExtendLockAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    /// Only passed for a delegated position.
+    pub operator: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> ExtendLockAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let staker = next_account_info(iter)?.signer()?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        Ok(Self {
+            stake_account,
+            staking_pool,
+            mint,
+            staker,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+            operator: delegated_operator(iter, program_id)?,
+        })
+    }
+}
+
+pub struct RequestUnstakeAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub unbonding_entry: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    /// Only passed for a delegated position.
+    pub operator: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> RequestUnstakeAccounts<'a, 'b> {
+    /// The address of the entry depends on the stake account data, so the
+    /// processor checks it.
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let unbonding_entry = next_account_info(iter)?.writable()?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let staker = next_account_info(iter)?.writable()?.signer()?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        Ok(Self {
+            stake_account,
+            unbonding_entry,
+            staking_pool,
+            staking_config,
+            mint,
+            staker,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+            operator: delegated_operator(iter, program_id)?,
+        })
+    }
+}
+
+pub struct WithdrawUnbondedAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub unbonding_entry: &'a AccountInfo<'b>,
+    pub token_account: &'a AccountInfo<'b>,
+    pub vault: &'a AccountInfo<'b>,
+    pub vault_bump: u8,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> WithdrawUnbondedAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let unbonding_entry = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let token_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let staker = next_account_info(iter)?.writable()?.signer()?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let vault_bump = config.vault_bump;
+        let vault = vault.pda_with_bump(&[VAULT_SEED, mint.key.as_ref()], vault_bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        let loaded = Self {
+            stake_account,
+            unbonding_entry,
+            token_account,
+            vault,
+            vault_bump,
+            staking_config,
+            mint,
+            staker,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct CancelUnbondingAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub unbonding_entry: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    /// Only passed for a delegated position.
+    pub operator: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> CancelUnbondingAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let unbonding_entry = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let staker = next_account_info(iter)?.writable()?.signer()?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        Ok(Self {
+            stake_account,
+            unbonding_entry,
+            staking_pool,
+            staking_config,
+            mint,
+            staker,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+            operator: delegated_operator(iter, program_id)?,
+        })
+    }
+}
+
+pub struct EmergencyUnstakeAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub token_account: &'a AccountInfo<'b>,
+    pub vault: &'a AccountInfo<'b>,
+    pub vault_bump: u8,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub penalty_account: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    /// Only passed for a delegated position.
+    pub operator: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> EmergencyUnstakeAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let token_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let penalty_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let staker = next_account_info(iter)?.signer()?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let vault_bump = config.vault_bump;
+        let vault = vault.pda_with_bump(&[VAULT_SEED, mint.key.as_ref()], vault_bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        let clock = next_account_info(iter)?.is(&sysvar::clock::id())?;
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(Self {
+            stake_account,
+            token_account,
+            vault,
+            vault_bump,
+            staking_pool,
+            staking_config,
+            mint,
+            penalty_account,
+            staker,
+            clock,
+            operator: delegated_operator(iter, program_id)?,
+        })
+    }
+}
+
+pub struct InitializeLiquidPoolAccounts<'a, 'b> {
+    pub liquid_pool: &'a AccountInfo<'b>,
+    pub liquid_pool_bump: u8,
+    pub receipt_mint: &'a AccountInfo<'b>,
+    pub receipt_mint_bump: u8,
+    pub stake_account: &'a AccountInfo<'b>,
+    pub stake_bump: u8,
+    pub mint: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeLiquidPoolAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let liquid_pool = next_account_info(iter)?.writable()?;
+        let receipt_mint = next_account_info(iter)?.writable()?;
+        let stake_account = next_account_info(iter)?.writable()?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let (liquid_pool, liquid_pool_bump) = liquid_pool.pda(&[LIQUID_POOL_SEED, mint.key.as_ref()], program_id)?;
+        let (receipt_mint, receipt_mint_bump) =
+            receipt_mint.pda(&[RECEIPT_MINT_SEED, mint.key.as_ref()], program_id)?;
+        let (stake_account, stake_bump) =
+            stake_account.pda(&[STAKE_SEED, mint.key.as_ref(), liquid_pool.key.as_ref()], program_id)?;
+        let loaded = Self {
+            liquid_pool,
+            liquid_pool_bump,
+            receipt_mint,
+            receipt_mint_bump,
+            stake_account,
+            stake_bump,
+            mint,
+            token_info: next_account_info(iter)?.owned_by(program_id)?,
+            admin: next_account_info(iter)?.writable()?.signer()?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+/// Accounts of both [`LiquidStake`] and [`LiquidUnstake`].
+///
+/// [`LiquidStake`]: crate::instruction::TokenInstruction::LiquidStake
+/// [`LiquidUnstake`]: crate::instruction::TokenInstruction::LiquidUnstake
+pub struct LiquidStakeAccounts<'a, 'b> {
+    pub liquid_pool: &'a AccountInfo<'b>,
+    pub liquid_pool_bump: u8,
+    pub stake_account: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub vault: &'a AccountInfo<'b>,
+    pub vault_bump: u8,
+    pub reward_vault: &'a AccountInfo<'b>,
+    pub reward_vault_bump: u8,
+    pub mint: &'a AccountInfo<'b>,
+    pub receipt_mint: &'a AccountInfo<'b>,
+    pub token_account: &'a AccountInfo<'b>,
+    pub receipt_account: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> LiquidStakeAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let liquid_pool = next_account_info(iter)?.owned_by(program_id)?;
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let reward_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let receipt_mint = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let token_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let receipt_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let owner = next_account_info(iter)?.signer()?;
+        let liquid = LiquidPool::unpack(&liquid_pool.data.borrow())?;
+        let liquid_pool_bump = liquid.bump;
+        let liquid_pool =
+            liquid_pool.pda_with_bump(&[LIQUID_POOL_SEED, mint.key.as_ref()], liquid_pool_bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account = stake_account.pda_with_bump(
+            &[STAKE_SEED, mint.key.as_ref(), liquid_pool.key.as_ref()],
+            stake_bump,
+            program_id,
+        )?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let vault_bump = config.vault_bump;
+        let vault = vault.pda_with_bump(&[VAULT_SEED, mint.key.as_ref()], vault_bump, program_id)?;
+        let reward_vault_bump = pool.reward_vault_bump;
+        let reward_vault =
+            reward_vault.pda_with_bump(&[REWARD_VAULT_SEED, mint.key.as_ref()], reward_vault_bump, program_id)?;
+        let receipt_mint =
+            receipt_mint.pda_with_bump(&[RECEIPT_MINT_SEED, mint.key.as_ref()], liquid.receipt_mint_bump, program_id)?;
+        let loaded = Self {
+            liquid_pool,
+            liquid_pool_bump,
+            stake_account,
+            staking_pool,
+            staking_config,
+            vault,
+            vault_bump,
+            reward_vault,
+            reward_vault_bump,
+            mint,
+            receipt_mint,
+            token_account,
+            receipt_account,
+            owner,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct RequestLiquidUnstakeAccounts<'a, 'b> {
+    pub liquid_pool: &'a AccountInfo<'b>,
+    pub stake_account: &'a AccountInfo<'b>,
+    pub unbonding_entry: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub vault: &'a AccountInfo<'b>,
+    pub reward_vault: &'a AccountInfo<'b>,
+    pub reward_vault_bump: u8,
+    pub mint: &'a AccountInfo<'b>,
+    pub receipt_mint: &'a AccountInfo<'b>,
+    pub receipt_account: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> RequestLiquidUnstakeAccounts<'a, 'b> {
+    /// The address of the entry depends on the stake account data, so the
+    /// processor checks it.
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let liquid_pool = next_account_info(iter)?.owned_by(program_id)?;
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let unbonding_entry = next_account_info(iter)?.writable()?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let reward_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let receipt_mint = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let receipt_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let owner = next_account_info(iter)?.writable()?.signer()?;
+        let liquid = LiquidPool::unpack(&liquid_pool.data.borrow())?;
+        let liquid_pool = liquid_pool.pda_with_bump(&[LIQUID_POOL_SEED, mint.key.as_ref()], liquid.bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account = stake_account.pda_with_bump(
+            &[STAKE_SEED, mint.key.as_ref(), liquid_pool.key.as_ref()],
+            stake_bump,
+            program_id,
+        )?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let vault = vault.pda_with_bump(&[VAULT_SEED, mint.key.as_ref()], config.vault_bump, program_id)?;
+        let reward_vault_bump = pool.reward_vault_bump;
+        let reward_vault =
+            reward_vault.pda_with_bump(&[REWARD_VAULT_SEED, mint.key.as_ref()], reward_vault_bump, program_id)?;
+        let receipt_mint =
+            receipt_mint.pda_with_bump(&[RECEIPT_MINT_SEED, mint.key.as_ref()], liquid.receipt_mint_bump, program_id)?;
+        let loaded = Self {
+            liquid_pool,
+            stake_account,
+            unbonding_entry,
+            staking_pool,
+            staking_config,
+            vault,
+            reward_vault,
+            reward_vault_bump,
+            mint,
+            receipt_mint,
+            receipt_account,
+            owner,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct WithdrawLiquidUnbondedAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub unbonding_entry: &'a AccountInfo<'b>,
+    pub token_account: &'a AccountInfo<'b>,
+    pub vault: &'a AccountInfo<'b>,
+    pub vault_bump: u8,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> WithdrawLiquidUnbondedAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let liquid_pool = next_account_info(iter)?.owned_by(program_id)?;
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let unbonding_entry = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let token_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let owner = next_account_info(iter)?.writable()?.signer()?;
+        let liquid_bump = LiquidPool::unpack(&liquid_pool.data.borrow())?.bump;
+        let liquid_pool = liquid_pool.pda_with_bump(&[LIQUID_POOL_SEED, mint.key.as_ref()], liquid_bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account = stake_account.pda_with_bump(
+            &[STAKE_SEED, mint.key.as_ref(), liquid_pool.key.as_ref()],
+            stake_bump,
+            program_id,
+        )?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let vault_bump = config.vault_bump;
+        let vault = vault.pda_with_bump(&[VAULT_SEED, mint.key.as_ref()], vault_bump, program_id)?;
+        let loaded = Self {
+            stake_account,
+            unbonding_entry,
+            token_account,
+            vault,
+            vault_bump,
+            staking_config,
+            mint,
+            owner,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct CompoundRewardsAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub vault: &'a AccountInfo<'b>,
+    pub reward_vault: &'a AccountInfo<'b>,
+    pub reward_vault_bump: u8,
+    pub mint: &'a AccountInfo<'b>,
+    /// Not checked as a signer here, since cranks compound without it.
+    pub owner: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    /// Only passed for a delegated position.
+    pub operator: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> CompoundRewardsAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let reward_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let vault = vault.pda_with_bump(&[VAULT_SEED, mint.key.as_ref()], config.vault_bump, program_id)?;
+        let reward_vault_bump = pool.reward_vault_bump;
+        let reward_vault =
+            reward_vault.pda_with_bump(&[REWARD_VAULT_SEED, mint.key.as_ref()], reward_vault_bump, program_id)?;
+        let owner = next_account_info(iter)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), owner.key.as_ref()], stake_bump, program_id)?;
+        let clock = next_account_info(iter)?.is(&sysvar::clock::id())?;
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(Self {
+            stake_account,
+            staking_pool,
+            staking_config,
+            vault,
+            reward_vault,
+            reward_vault_bump,
+            mint,
+            owner,
+            clock,
+            operator: delegated_operator(iter, program_id)?,
+        })
+    }
+}
+
+pub struct SetAutoCompoundAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> SetAutoCompoundAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let staker = next_account_info(iter)?.signer()?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        Ok(Self {
+            stake_account,
+            mint,
+            staker,
+        })
+    }
+}
+
+pub struct InitializeLockerAccounts<'a, 'b> {
+    pub locker: &'a AccountInfo<'b>,
+    pub locker_bump: u8,
+    pub locker_vault: &'a AccountInfo<'b>,
+    pub locker_vault_bump: u8,
+    pub mint: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeLockerAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let locker = next_account_info(iter)?.writable()?;
+        let locker_vault = next_account_info(iter)?.writable()?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let (locker, locker_bump) = locker.pda(&[LOCKER_SEED, mint.key.as_ref()], program_id)?;
+        let (locker_vault, locker_vault_bump) = locker_vault.pda(&[LOCKER_VAULT_SEED, mint.key.as_ref()], program_id)?;
+        let loaded = Self {
+            locker,
+            locker_bump,
+            locker_vault,
+            locker_vault_bump,
+            mint,
+            token_info: next_account_info(iter)?.owned_by(program_id)?,
+            admin: next_account_info(iter)?.writable()?.signer()?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+/// Accounts of [`CreateVoteLock`], [`IncreaseVoteLockAmount`] and
+/// [`WithdrawVoteLock`]. The vote lock is only checked to belong to the
+/// program once it exists.
+///
+/// [`CreateVoteLock`]: crate::instruction::TokenInstruction::CreateVoteLock
+/// [`IncreaseVoteLockAmount`]: crate::instruction::TokenInstruction::IncreaseVoteLockAmount
+/// [`WithdrawVoteLock`]: crate::instruction::TokenInstruction::WithdrawVoteLock
+pub struct VoteLockAccounts<'a, 'b> {
+    pub vote_lock: &'a AccountInfo<'b>,
+    pub vote_lock_bump: u8,
+    pub locker: &'a AccountInfo<'b>,
+    pub token_account: &'a AccountInfo<'b>,
+    pub locker_vault: &'a AccountInfo<'b>,
+    pub locker_vault_bump: u8,
+    pub mint: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> VoteLockAccounts<'a, 'b> {
+    fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>], existing: bool) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let vote_lock = next_account_info(iter)?.writable()?;
+        let locker = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let token_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let locker_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let owner = next_account_info(iter)?.writable()?.signer()?;
+        let locker_state = Locker::unpack(&locker.data.borrow())?;
+        let locker = locker.pda_with_bump(&[LOCKER_SEED, mint.key.as_ref()], locker_state.bump, program_id)?;
+        let locker_vault_bump = locker_state.vault_bump;
+        let locker_vault =
+            locker_vault.pda_with_bump(&[LOCKER_VAULT_SEED, mint.key.as_ref()], locker_vault_bump, program_id)?;
+        let seeds: &[&[u8]] = &[VOTE_LOCK_SEED, mint.key.as_ref(), owner.key.as_ref()];
+        let (vote_lock, vote_lock_bump) = if existing {
+            let vote_lock = vote_lock.owned_by(program_id)?;
+            let bump = VoteLock::unpack(&vote_lock.data.borrow())?.bump;
+            (vote_lock.pda_with_bump(seeds, bump, program_id)?, bump)
+        } else {
+            vote_lock.pda(seeds, program_id)?
+        };
+        let loaded = Self {
+            vote_lock,
+            vote_lock_bump,
+            locker,
+            token_account,
+            locker_vault,
+            locker_vault_bump,
+            mint,
+            owner,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+
+    /// Loads the accounts of [`CreateVoteLock`], followed by the system
+    /// program.
+    ///
+    /// [`CreateVoteLock`]: crate::instruction::TokenInstruction::CreateVoteLock
+    pub fn load_with_system_program(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<(Self, &'a AccountInfo<'b>), ProgramError> {
+        let loaded = Self::load(program_id, accounts, false)?;
+        let system_program = accounts.get(8).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        Ok((loaded, system_program.is(&system_program::id())?))
+    }
+
+    /// Loads the accounts of a vote lock that already exists.
+    pub fn load_existing(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        Self::load(program_id, accounts, true)
+    }
+}
+
+pub struct ExtendVoteLockAccounts<'a, 'b> {
+    pub vote_lock: &'a AccountInfo<'b>,
+    pub locker: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> ExtendVoteLockAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let vote_lock = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let locker = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let owner = next_account_info(iter)?.signer()?;
+        let locker_bump = Locker::unpack(&locker.data.borrow())?.bump;
+        let locker = locker.pda_with_bump(&[LOCKER_SEED, mint.key.as_ref()], locker_bump, program_id)?;
+        let vote_lock_bump = VoteLock::unpack(&vote_lock.data.borrow())?.bump;
+        let vote_lock = vote_lock.pda_with_bump(
+            &[VOTE_LOCK_SEED, mint.key.as_ref(), owner.key.as_ref()],
+            vote_lock_bump,
+            program_id,
+        )?;
+        Ok(Self {
+            vote_lock,
+            locker,
+            mint,
+            owner,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        })
+    }
+}
+
+pub struct RegisterOperatorAccounts<'a, 'b> {
+    pub operator: &'a AccountInfo<'b>,
+    pub operator_bump: u8,
+    pub mint: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> RegisterOperatorAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let operator = next_account_info(iter)?.writable()?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let authority = next_account_info(iter)?.writable()?.signer()?;
+        let (operator, operator_bump) =
+            operator.pda(&[OPERATOR_SEED, mint.key.as_ref(), authority.key.as_ref()], program_id)?;
+        Ok(Self {
+            operator,
+            operator_bump,
+            mint,
+            authority,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        })
+    }
+}
+
+pub struct DelegateStakeAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub operator: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    /// Only passed for a delegated position.
+    pub current_operator: Option<&'a AccountInfo<'b>>,
+}
+
+impl<'a, 'b> DelegateStakeAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let operator = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staker = next_account_info(iter)?.signer()?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        let clock = next_account_info(iter)?.is(&sysvar::clock::id())?;
+        let current_operator = delegated_operator(iter, program_id)?;
+        Ok(Self {
+            stake_account,
+            staking_pool,
+            staking_config,
+            mint,
+            operator,
+            staker,
+            clock,
+            current_operator,
+        })
+    }
+}
+
+pub struct UndelegateStakeAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub staking_config: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub operator: &'a AccountInfo<'b>,
+    pub staker: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> UndelegateStakeAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let stake_account = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_config = next_account_info(iter)?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let operator = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staker = next_account_info(iter)?.signer()?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        let config = StakingConfig::unpack(&staking_config.data.borrow())?;
+        let staking_config =
+            staking_config.pda_with_bump(&[STAKING_CONFIG_SEED, mint.key.as_ref()], config.bump, program_id)?;
+        let stake_bump = StakeInfo::unpack(&stake_account.data.borrow())?.bump;
+        let stake_account =
+            stake_account.pda_with_bump(&[STAKE_SEED, mint.key.as_ref(), staker.key.as_ref()], stake_bump, program_id)?;
+        Ok(Self {
+            stake_account,
+            staking_pool,
+            staking_config,
+            mint,
+            operator,
+            staker,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        })
+    }
+}
+
+pub struct ClaimCommissionAccounts<'a, 'b> {
+    pub operator: &'a AccountInfo<'b>,
+    pub reward_vault: &'a AccountInfo<'b>,
+    pub reward_vault_bump: u8,
+    pub destination: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub authority: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> ClaimCommissionAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let operator = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let reward_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let destination = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let authority = next_account_info(iter)?.signer()?;
+        let operator_state = Operator::unpack(&operator.data.borrow())?;
+        let reward_vault_bump = operator_state.reward_vault_bump;
+        let reward_vault =
+            reward_vault.pda_with_bump(&[REWARD_VAULT_SEED, mint.key.as_ref()], reward_vault_bump, program_id)?;
+        let operator = operator.pda_with_bump(
+            &[OPERATOR_SEED, mint.key.as_ref(), authority.key.as_ref()],
+            operator_state.bump,
+            program_id,
+        )?;
+        let clock = next_account_info(iter)?.is(&sysvar::clock::id())?;
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(Self {
+            operator,
+            reward_vault,
+            reward_vault_bump,
+            destination,
+            mint,
+            authority,
+            clock,
+        })
+    }
+}
+
+pub struct CreateFarmAccounts<'a, 'b> {
+    /// Checked against the address of the farm id by the handler.
+    pub farm: &'a AccountInfo<'b>,
+    pub farm_vault: &'a AccountInfo<'b>,
+    pub farm_vault_bump: u8,
+    pub farm_reward_vault: &'a AccountInfo<'b>,
+    pub farm_reward_vault_bump: u8,
+    pub staked_mint: &'a AccountInfo<'b>,
+    pub reward_mint: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> CreateFarmAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let farm = next_account_info(iter)?.writable()?;
+        let (farm_vault, farm_vault_bump) =
+            next_account_info(iter)?.writable()?.pda(&[FARM_VAULT_SEED, farm.key.as_ref()], program_id)?;
+        let (farm_reward_vault, farm_reward_vault_bump) = next_account_info(iter)?
+            .writable()?
+            .pda(&[FARM_REWARD_VAULT_SEED, farm.key.as_ref()], program_id)?;
+        let loaded = Self {
+            farm,
+            farm_vault,
+            farm_vault_bump,
+            farm_reward_vault,
+            farm_reward_vault_bump,
+            staked_mint: next_account_info(iter)?.owned_by(&spl_token::id())?,
+            reward_mint: next_account_info(iter)?.owned_by(&spl_token::id())?,
+            mint: next_account_info(iter)?.owned_by(&spl_token::id())?,
+            token_info: next_account_info(iter)?.owned_by(program_id)?,
+            admin: next_account_info(iter)?.writable()?.signer()?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct FundFarmAccounts<'a, 'b> {
+    pub farm: &'a AccountInfo<'b>,
+    pub farm_reward_vault: &'a AccountInfo<'b>,
+    pub source_token_account: &'a AccountInfo<'b>,
+    pub reward_mint: &'a AccountInfo<'b>,
+    pub funder: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> FundFarmAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let farm = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let farm_reward_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let source_token_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let reward_mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let funder = next_account_info(iter)?.signer()?;
+        let farm_reward_vault_bump = Farm::unpack(&farm.data.borrow())?.reward_vault_bump;
+        let farm_reward_vault = farm_reward_vault.pda_with_bump(
+            &[FARM_REWARD_VAULT_SEED, farm.key.as_ref()],
+            farm_reward_vault_bump,
+            program_id,
+        )?;
+        let loaded = Self {
+            farm,
+            farm_reward_vault,
+            source_token_account,
+            reward_mint,
+            funder,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct UpdateFarmAccounts<'a, 'b> {
+    pub farm: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> UpdateFarmAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            farm: next_account_info(iter)?.writable()?.owned_by(program_id)?,
+            mint: next_account_info(iter)?.owned_by(&spl_token::id())?,
+            token_info: next_account_info(iter)?.owned_by(program_id)?,
+            admin: next_account_info(iter)?.signer()?,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        })
+    }
+}
+
+pub struct FarmPositionAccounts<'a, 'b> {
+    pub farm_position: &'a AccountInfo<'b>,
+    pub farm_position_bump: u8,
+    pub farm: &'a AccountInfo<'b>,
+    pub farm_vault: &'a AccountInfo<'b>,
+    pub farm_vault_bump: u8,
+    pub token_account: &'a AccountInfo<'b>,
+    pub staked_mint: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> FarmPositionAccounts<'a, 'b> {
+    fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>], existing: bool) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let farm_position = next_account_info(iter)?.writable()?;
+        let farm = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let farm_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let token_account = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let staked_mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let owner = next_account_info(iter)?.signer()?;
+        let farm_vault_bump = Farm::unpack(&farm.data.borrow())?.vault_bump;
+        let farm_vault = farm_vault.pda_with_bump(&[FARM_VAULT_SEED, farm.key.as_ref()], farm_vault_bump, program_id)?;
+        let seeds: &[&[u8]] = &[FARM_POSITION_SEED, farm.key.as_ref(), owner.key.as_ref()];
+        let (farm_position, farm_position_bump) = if existing {
+            let farm_position = farm_position.owned_by(program_id)?;
+            let bump = FarmPosition::unpack(&farm_position.data.borrow())?.bump;
+            (farm_position.pda_with_bump(seeds, bump, program_id)?, bump)
+        } else {
+            farm_position.pda(seeds, program_id)?
+        };
+        let loaded = Self {
+            farm_position,
+            farm_position_bump,
+            farm,
+            farm_vault,
+            farm_vault_bump,
+            token_account,
+            staked_mint,
+            owner,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+
+    /// Loads the accounts of [`FarmStake`], where the owner pays for a new
+    /// position, followed by the system program.
+    ///
+    /// [`FarmStake`]: crate::instruction::TokenInstruction::FarmStake
+    pub fn load_with_system_program(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<(Self, &'a AccountInfo<'b>), ProgramError> {
+        let loaded = Self::load(program_id, accounts, false)?;
+        loaded.owner.writable()?;
+        let system_program = accounts.get(8).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        Ok((loaded, system_program.is(&system_program::id())?))
+    }
+
+    /// Loads the accounts of a farm position that already exists.
+    pub fn load_existing(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        Self::load(program_id, accounts, true)
+    }
+}
+
+pub struct ClaimFarmRewardsAccounts<'a, 'b> {
+    pub farm_position: &'a AccountInfo<'b>,
+    pub farm: &'a AccountInfo<'b>,
+    pub farm_reward_vault: &'a AccountInfo<'b>,
+    pub farm_reward_vault_bump: u8,
+    pub destination: &'a AccountInfo<'b>,
+    pub reward_mint: &'a AccountInfo<'b>,
+    pub owner: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> ClaimFarmRewardsAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let farm_position = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let farm = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let farm_reward_vault = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let destination = next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?;
+        let reward_mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let owner = next_account_info(iter)?.signer()?;
+        let farm_reward_vault_bump = Farm::unpack(&farm.data.borrow())?.reward_vault_bump;
+        let farm_reward_vault = farm_reward_vault.pda_with_bump(
+            &[FARM_REWARD_VAULT_SEED, farm.key.as_ref()],
+            farm_reward_vault_bump,
+            program_id,
+        )?;
+        let farm_position_bump = FarmPosition::unpack(&farm_position.data.borrow())?.bump;
+        let farm_position = farm_position.pda_with_bump(
+            &[FARM_POSITION_SEED, farm.key.as_ref(), owner.key.as_ref()],
+            farm_position_bump,
+            program_id,
+        )?;
+        let loaded = Self {
+            farm_position,
+            farm,
+            farm_reward_vault,
+            farm_reward_vault_bump,
+            destination,
+            reward_mint,
+            owner,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct InitializeVaultAccounts<'a, 'b> {
+    pub vault: &'a AccountInfo<'b>,
+    pub vault_bump: u8,
+    pub mint: &'a AccountInfo<'b>,
+    pub payer: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeVaultAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let vault = next_account_info(iter)?.writable()?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let (vault, vault_bump) = vault.pda(&[VAULT_SEED, mint.key.as_ref()], program_id)?;
+        let loaded = Self {
+            vault,
+            vault_bump,
+            mint,
+            payer: next_account_info(iter)?.writable()?.signer()?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct UpgradeProgramAccounts<'a, 'b> {
+    pub program: &'a AccountInfo<'b>,
+    pub buffer: &'a AccountInfo<'b>,
+    pub spill: &'a AccountInfo<'b>,
+    pub upgrade_authority: &'a AccountInfo<'b>,
+    pub rent: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> UpgradeProgramAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let loaded = Self {
+            program: next_account_info(iter)?.writable()?.is(program_id)?,
+            buffer: next_account_info(iter)?.writable()?,
+            spill: next_account_info(iter)?.writable()?,
+            upgrade_authority: next_account_info(iter)?.signer()?,
+            rent: next_account_info(iter)?.is(&sysvar::rent::id())?,
+        };
+        next_account_info(iter)?.is(&bpf_loader_upgradeable::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct InitializeTokenInfoAccounts<'a, 'b> {
+    pub token_info: &'a AccountInfo<'b>,
+    pub mint_authority: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeTokenInfoAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            token_info: next_account_info(iter)?.writable()?.owned_by(program_id)?,
+            mint_authority: next_account_info(iter)?.signer()?,
+            mint: next_account_info(iter)?.owned_by(&spl_token::id())?,
+        })
+    }
+}
+
+pub struct CreateVestingScheduleAccounts<'a, 'b> {
+    pub vesting_account: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub mint_authority: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> CreateVestingScheduleAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            vesting_account: next_account_info(iter)?.writable()?.owned_by(program_id)?,
+            token_info: next_account_info(iter)?.writable()?.owned_by(program_id)?,
+            mint_authority: next_account_info(iter)?.signer()?,
+        })
+    }
+}
+
+pub struct ReleaseVestedTokensAccounts<'a, 'b> {
+    pub vesting_account: &'a AccountInfo<'b>,
+    pub beneficiary_token_account: &'a AccountInfo<'b>,
+    pub source_token_account: &'a AccountInfo<'b>,
+    pub source_authority: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> ReleaseVestedTokensAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let loaded = Self {
+            vesting_account: next_account_info(iter)?.writable()?.owned_by(program_id)?,
+            beneficiary_token_account: next_account_info(iter)?
+                .writable()?
+                .owned_by(&spl_token::id())?,
+            source_token_account: next_account_info(iter)?.writable()?.owned_by(&spl_token::id())?,
+            source_authority: next_account_info(iter)?.signer()?,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        };
+        next_account_info(iter)?.is(&spl_token::id())?;
+        Ok(loaded)
+    }
+}
+
+pub struct MigrateAccountAccounts<'a, 'b> {
+    pub account: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> MigrateAccountAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            account: next_account_info(iter)?.writable()?.owned_by(program_id)?,
+            token_info: next_account_info(iter)?.owned_by(program_id)?,
+            admin: next_account_info(iter)?.writable()?.signer()?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        })
+    }
+}
+
+/// Accounts of `QueryVestedAmount` and `QueryReleasableAmount`.
+pub struct QueryVestingAccounts<'a, 'b> {
+    pub vesting_account: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> QueryVestingAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            vesting_account: next_account_info(iter)?.owned_by(program_id)?,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        })
+    }
+}
+
+pub struct QueryStakeInfoAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> QueryStakeInfoAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            stake_account: next_account_info(iter)?.owned_by(program_id)?,
+        })
+    }
+}
+
+pub struct QueryPendingRewardsAccounts<'a, 'b> {
+    pub stake_account: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> QueryPendingRewardsAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            stake_account: next_account_info(iter)?.owned_by(program_id)?,
+            staking_pool: next_account_info(iter)?.owned_by(program_id)?,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        })
+    }
+}
+
+pub struct QueryVotingPowerAccounts<'a, 'b> {
+    pub vote_lock: &'a AccountInfo<'b>,
+    pub locker: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> QueryVotingPowerAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            vote_lock: next_account_info(iter)?.owned_by(program_id)?,
+            locker: next_account_info(iter)?.owned_by(program_id)?,
+        })
+    }
+}
+
+pub struct QueryTotalVotingPowerAccounts<'a, 'b> {
+    pub locker: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> QueryTotalVotingPowerAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            locker: next_account_info(iter)?.owned_by(program_id)?,
+        })
+    }
+}
+
+pub struct QueryFarmRewardsAccounts<'a, 'b> {
+    pub farm_position: &'a AccountInfo<'b>,
+    pub farm: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> QueryFarmRewardsAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            farm_position: next_account_info(iter)?.owned_by(program_id)?,
+            farm: next_account_info(iter)?.owned_by(program_id)?,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        })
+    }
+}
+
+pub struct QueryAllocationRemainingAccounts<'a, 'b> {
+    pub token_info: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> QueryAllocationRemainingAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            token_info: next_account_info(iter)?.owned_by(program_id)?,
+        })
+    }
+}
+
+pub struct InitializeEmissionScheduleAccounts<'a, 'b> {
+    pub emission_schedule: &'a AccountInfo<'b>,
+    pub emission_schedule_bump: u8,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub admin: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> InitializeEmissionScheduleAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let emission_schedule = next_account_info(iter)?.writable()?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let token_info = next_account_info(iter)?.owned_by(program_id)?;
+        let admin = next_account_info(iter)?.writable()?.signer()?;
+        let (emission_schedule, emission_schedule_bump) =
+            emission_schedule.pda(&[EMISSION_SCHEDULE_SEED, mint.key.as_ref()], program_id)?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        Ok(Self {
+            emission_schedule,
+            emission_schedule_bump,
+            staking_pool,
+            mint,
+            token_info,
+            admin,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+            system_program: next_account_info(iter)?.is(&system_program::id())?,
+        })
+    }
+}
+
+pub struct AdvanceEpochAccounts<'a, 'b> {
+    pub emission_schedule: &'a AccountInfo<'b>,
+    pub staking_pool: &'a AccountInfo<'b>,
+    pub token_info: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    pub clock: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> AdvanceEpochAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let emission_schedule = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let staking_pool = next_account_info(iter)?.writable()?.owned_by(program_id)?;
+        let token_info = next_account_info(iter)?.owned_by(program_id)?;
+        let mint = next_account_info(iter)?.owned_by(&spl_token::id())?;
+        let schedule_bump = EmissionSchedule::unpack(&emission_schedule.data.borrow())?.bump;
+        let emission_schedule =
+            emission_schedule.pda_with_bump(&[EMISSION_SCHEDULE_SEED, mint.key.as_ref()], schedule_bump, program_id)?;
+        let pool = StakingPool::unpack(&staking_pool.data.borrow())?;
+        let staking_pool = staking_pool.pda_with_bump(&[STAKING_POOL_SEED, mint.key.as_ref()], pool.bump, program_id)?;
+        Ok(Self {
+            emission_schedule,
+            staking_pool,
+            token_info,
+            mint,
+            clock: next_account_info(iter)?.is(&sysvar::clock::id())?,
+        })
+    }
+}
+
+pub struct QueryEmissionScheduleAccounts<'a, 'b> {
+    pub emission_schedule: &'a AccountInfo<'b>,
+}
+
+impl<'a, 'b> QueryEmissionScheduleAccounts<'a, 'b> {
+    pub fn load(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        Ok(Self {
+            emission_schedule: next_account_info(iter)?.owned_by(program_id)?,
+        })
+    }
+}
@@ -1,7 +1,32 @@
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use solana_program::account_info::AccountInfo;
     use solana_program::clock::Epoch;
+    use solana_program::entrypoint::ProgramResult;
+    use solana_program::instruction::Instruction;
+    use solana_program::program_error::ProgramError;
+    use solana_program::program_pack::Pack;
+    use solana_program::pubkey::Pubkey;
+    use solana_program::rent::Rent;
+    use solana_program::sysvar::Sysvar;
+    use solana_token::instruction::{self, AllocationType, TokenInstruction};
+    use solana_token::processor::Processor;
+    use solana_token::state::{
+        find_farm_address, find_farm_position_address, find_farm_reward_vault_address, find_farm_vault_address,
+        find_liquid_pool_address, find_locker_address, find_locker_vault_address, find_operator_address,
+        find_receipt_mint_address, find_reward_vault_address, find_stake_address, find_staking_config_address,
+        find_staking_pool_address, find_unbonding_address, find_vault_address, find_vote_lock_address, AccountType,
+        LockTier, PenaltyCurve, PenaltyDestination, StakingParams,
+    };
+    use spl_token::state::Mint;
+
+    fn process_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        instruction_data: &[u8],
+    ) -> ProgramResult {
+        Processor::process(program_id, accounts, instruction_data)
+    }
 
     // Fonction utilitaire pour créer un AccountInfo pour les tests
     fn create_account_info<'a>(
@@ -32,19 +57,1290 @@ mod tests {
         let mut mint_lamports = 0;
         let mint_authority_key = Pubkey::new_unique();
         let freeze_authority_key = Pubkey::new_unique();
-        let mut rent_sysvar = vec![0; Rent::default().try_to_vec().unwrap().len()];
+        let rent_key = solana_program::sysvar::rent::id();
+        let system_program_id = solana_program::system_program::id();
+        let spl_token_id = spl_token::id();
+        let mut rent_sysvar = vec![0; Rent::size_of()];
+        let (mut authority_lamports, mut freeze_lamports, mut rent_lamports, mut token_program_lamports) = (0, 0, 0, 0);
 
         let accounts = vec![
-            create_account_info(&mint_key, false, true, &mut mint_lamports, &mut mint_account, &program_id),
-            create_account_info(&mint_authority_key, true, false, &mut 0, &mut [], &program_id),
-            create_account_info(&freeze_authority_key, false, false, &mut 0, &mut [], &program_id),
-            create_account_info(&solana_program::sysvar::rent::id(), false, false, &mut 0, &mut rent_sysvar, &solana_program::system_program::id()),
+            create_account_info(&mint_key, false, true, &mut mint_lamports, &mut mint_account, &spl_token_id),
+            create_account_info(&mint_authority_key, true, false, &mut authority_lamports, &mut [], &program_id),
+            create_account_info(&freeze_authority_key, false, false, &mut freeze_lamports, &mut [], &program_id),
+            create_account_info(&rent_key, false, false, &mut rent_lamports, &mut rent_sysvar, &system_program_id),
+            create_account_info(&spl_token_id, false, false, &mut token_program_lamports, &mut [], &system_program_id),
         ];
 
         let instruction_data = [0, 9]; // InitializeMint with 9 decimals
 
         let result = process_instruction(&program_id, &accounts, &instruction_data);
         assert!(result.is_ok());
+
+        // spl-token rejects mints it does not own, so the program does too.
+        let (mut other_lamports, mut other_mint) = (0, vec![0; Mint::LEN]);
+        let mut accounts = accounts;
+        accounts[0] = create_account_info(&mint_key, false, true, &mut other_lamports, &mut other_mint, &program_id);
+        let result = process_instruction(&program_id, &accounts, &instruction_data);
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+    }
+
+    /// Runs `ix` as built, except that `unsigned` does not sign. Accounts
+    /// get the owner listed in `owners`, or the system program.
+    fn process_without_signature(
+        program_id: &Pubkey,
+        ix: &Instruction,
+        owners: &[(Pubkey, Pubkey)],
+        unsigned: &Pubkey,
+    ) -> ProgramResult {
+        let account_owners: Vec<Pubkey> = ix
+            .accounts
+            .iter()
+            .map(|meta| {
+                owners
+                    .iter()
+                    .find(|(key, _)| *key == meta.pubkey)
+                    .map_or(solana_program::system_program::id(), |(_, owner)| *owner)
+            })
+            .collect();
+        let mut lamports = vec![0; ix.accounts.len()];
+        let mut data = vec![Vec::new(); ix.accounts.len()];
+        let accounts: Vec<AccountInfo> = ix
+            .accounts
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .zip(account_owners.iter())
+            .map(|(((meta, lamports), data), owner)| {
+                let is_signer = meta.is_signer && meta.pubkey != *unsigned;
+                create_account_info(&meta.pubkey, is_signer, meta.is_writable, lamports, data, owner)
+            })
+            .collect();
+        process_instruction(program_id, &accounts, &ix.data)
+    }
+
+    #[test]
+    fn test_missing_signatures_are_rejected() {
+        let program_id = Pubkey::new_unique();
+        let spl_token_id = spl_token::id();
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let authority = Pubkey::new_unique();
+        let (vault, _) = find_vault_address(&program_id, &c);
+        let (stake, _) = find_stake_address(&program_id, &c, &authority);
+        let (pool, _) = find_staking_pool_address(&program_id, &c);
+        let (reward_vault, _) = find_reward_vault_address(&program_id, &c);
+        let (config, _) = find_staking_config_address(&program_id, &c);
+        let (unbonding, _) = find_unbonding_address(&program_id, &stake, 0);
+        let stake_owners = vec![
+            (stake, program_id),
+            (b, spl_token_id),
+            (vault, spl_token_id),
+            (pool, program_id),
+            (config, program_id),
+            (c, spl_token_id),
+        ];
+
+        let (liquid_pool, _) = find_liquid_pool_address(&program_id, &c);
+        let (liquid_stake, _) = find_stake_address(&program_id, &c, &liquid_pool);
+        let liquid_owners = vec![
+            (liquid_pool, program_id),
+            (liquid_stake, program_id),
+            (pool, program_id),
+            (config, program_id),
+            (vault, spl_token_id),
+            (reward_vault, spl_token_id),
+            (c, spl_token_id),
+            (find_receipt_mint_address(&program_id, &c).0, spl_token_id),
+            (a, spl_token_id),
+            (b, spl_token_id),
+        ];
+
+        let (locker, _) = find_locker_address(&program_id, &c);
+        let (vote_lock, _) = find_vote_lock_address(&program_id, &c, &authority);
+        let vote_lock_owners = vec![
+            (vote_lock, program_id),
+            (locker, program_id),
+            (a, spl_token_id),
+            (find_locker_vault_address(&program_id, &c).0, spl_token_id),
+            (c, spl_token_id),
+        ];
+
+        let (farm, _) = find_farm_address(&program_id, &c, 0);
+        let (farm_position, _) = find_farm_position_address(&program_id, &farm, &authority);
+        let (farm_reward_vault, _) = find_farm_reward_vault_address(&program_id, &farm);
+        let farm_owners = vec![
+            (farm_position, program_id),
+            (farm, program_id),
+            (find_farm_vault_address(&program_id, &farm).0, spl_token_id),
+            (a, spl_token_id),
+            (b, spl_token_id),
+        ];
+
+        let cases = vec![
+            (instruction::transfer(&program_id, &a, &b, &authority, 1), vec![(a, spl_token_id), (b, spl_token_id)]),
+            (instruction::burn(&program_id, &a, &b, &authority, 1), vec![(a, spl_token_id), (b, spl_token_id)]),
+            (instruction::mint_to(&program_id, &a, &b, &authority, 1), vec![(a, spl_token_id), (b, spl_token_id)]),
+            (instruction::freeze(&program_id, &a, &b, &authority), vec![(a, spl_token_id), (b, spl_token_id)]),
+            (instruction::thaw(&program_id, &a, &b, &authority), vec![(a, spl_token_id), (b, spl_token_id)]),
+            (instruction::set_authority(&program_id, &a, None, 0, &authority), vec![(a, spl_token_id)]),
+            (instruction::stake(&program_id, &b, &c, &authority, 1, None), stake_owners.clone()),
+            (instruction::unstake(&program_id, &b, &c, &authority, 1, None), stake_owners.clone()),
+            (instruction::upgrade_program(&program_id, &a, &b, &authority), vec![]),
+            (instruction::initialize_token_info(&program_id, &a, &authority, &b), vec![(a, program_id), (b, spl_token_id)]),
+            (
+                instruction::create_vesting_schedule(&program_id, &a, &b, &authority, &c, AllocationType::Team, 1, 0, 0, 1),
+                vec![(a, program_id), (b, program_id)],
+            ),
+            (
+                instruction::release_vested_tokens(&program_id, &a, &b, &c, &authority),
+                vec![(a, program_id), (b, spl_token_id), (c, spl_token_id)],
+            ),
+            (
+                instruction::migrate_account(&program_id, &a, &b, &authority, AccountType::StakeInfo),
+                vec![(a, program_id), (b, program_id)],
+            ),
+            (instruction::initialize_vault(&program_id, &c, &authority), vec![(c, spl_token_id)]),
+            (instruction::initialize_stake(&program_id, &c, &authority, LockTier::Days30), vec![(c, spl_token_id)]),
+            (
+                instruction::initialize_staking_pool(&program_id, &c, &a, &authority, 1),
+                vec![(vault, spl_token_id), (c, spl_token_id), (a, program_id)],
+            ),
+            (
+                instruction::fund_rewards(&program_id, &c, &a, &b, &authority, 1),
+                vec![(pool, program_id), (reward_vault, spl_token_id), (a, program_id), (b, spl_token_id), (c, spl_token_id)],
+            ),
+            (
+                instruction::claim_rewards(&program_id, &b, &c, &authority, None),
+                vec![(stake, program_id), (pool, program_id), (reward_vault, spl_token_id), (b, spl_token_id), (c, spl_token_id)],
+            ),
+            (
+                instruction::initialize_staking_config(&program_id, &c, &a, &authority, StakingParams::default()),
+                vec![(pool, program_id), (c, spl_token_id), (a, program_id)],
+            ),
+            (
+                instruction::update_staking_config(&program_id, &c, &a, &authority, StakingParams::default()),
+                vec![(config, program_id), (pool, program_id), (c, spl_token_id), (a, program_id)],
+            ),
+            (
+                instruction::extend_lock(&program_id, &c, &authority, LockTier::Days90, None),
+                vec![(stake, program_id), (pool, program_id), (c, spl_token_id)],
+            ),
+            (
+                instruction::request_unstake(&program_id, &c, &authority, 0, 1, None),
+                vec![(stake, program_id), (pool, program_id), (config, program_id), (c, spl_token_id)],
+            ),
+            (
+                instruction::withdraw_unbonded(&program_id, &b, &c, &authority, 0),
+                vec![
+                    (stake, program_id),
+                    (unbonding, program_id),
+                    (b, spl_token_id),
+                    (vault, spl_token_id),
+                    (config, program_id),
+                    (c, spl_token_id),
+                ],
+            ),
+            (
+                instruction::cancel_unbonding(&program_id, &c, &authority, 0, None),
+                vec![(stake, program_id), (unbonding, program_id), (pool, program_id), (config, program_id), (c, spl_token_id)],
+            ),
+            (
+                instruction::emergency_unstake(&program_id, &b, &c, &reward_vault, &authority, 1, None),
+                [stake_owners.clone(), vec![(reward_vault, spl_token_id)]].concat(),
+            ),
+            (instruction::initialize_liquid_pool(&program_id, &c, &a, &authority), vec![(c, spl_token_id), (a, program_id)]),
+            (instruction::liquid_stake(&program_id, &a, &b, &c, &authority, 1), liquid_owners.clone()),
+            (instruction::liquid_unstake(&program_id, &a, &b, &c, &authority, 1), liquid_owners.clone()),
+            (instruction::request_liquid_unstake(&program_id, &b, &c, &authority, 0, 1), liquid_owners.clone()),
+            (
+                instruction::withdraw_liquid_unbonded(&program_id, &a, &c, &authority, 0),
+                vec![
+                    (liquid_pool, program_id),
+                    (liquid_stake, program_id),
+                    (find_unbonding_address(&program_id, &liquid_stake, 0).0, program_id),
+                    (a, spl_token_id),
+                    (vault, spl_token_id),
+                    (config, program_id),
+                    (c, spl_token_id),
+                ],
+            ),
+            (
+                instruction::set_auto_compound(&program_id, &c, &authority, true),
+                vec![(stake, program_id), (c, spl_token_id)],
+            ),
+            (
+                instruction::initialize_locker(&program_id, &c, &a, &authority, 604_800),
+                vec![(c, spl_token_id), (a, program_id)],
+            ),
+            (instruction::create_vote_lock(&program_id, &a, &c, &authority, 1, 0), vote_lock_owners.clone()),
+            (instruction::increase_vote_lock_amount(&program_id, &a, &c, &authority, 1), vote_lock_owners.clone()),
+            (
+                instruction::extend_vote_lock(&program_id, &c, &authority, 0),
+                vec![(vote_lock, program_id), (locker, program_id), (c, spl_token_id)],
+            ),
+            (instruction::withdraw_vote_lock(&program_id, &a, &c, &authority), vote_lock_owners.clone()),
+            (instruction::register_operator(&program_id, &c, &authority, 500), vec![(c, spl_token_id)]),
+            (
+                instruction::delegate_stake(&program_id, &c, &authority, &a, None),
+                vec![(stake, program_id), (pool, program_id), (config, program_id), (c, spl_token_id), (a, program_id)],
+            ),
+            (
+                instruction::claim_commission(&program_id, &a, &c, &authority),
+                vec![
+                    (find_operator_address(&program_id, &c, &authority).0, program_id),
+                    (reward_vault, spl_token_id),
+                    (a, spl_token_id),
+                    (c, spl_token_id),
+                ],
+            ),
+            (
+                instruction::create_farm(&program_id, &c, &a, &b, &b, &authority, 0, 1, 0, 1),
+                vec![(b, spl_token_id), (c, spl_token_id), (a, program_id)],
+            ),
+            (
+                instruction::fund_farm(&program_id, &farm, &a, &b, &authority, 1),
+                vec![(farm, program_id), (farm_reward_vault, spl_token_id), (a, spl_token_id), (b, spl_token_id)],
+            ),
+            (
+                instruction::update_farm(&program_id, &farm, &c, &a, &authority, 1, 2),
+                vec![(farm, program_id), (c, spl_token_id), (a, program_id)],
+            ),
+            (instruction::farm_stake(&program_id, &farm, &a, &b, &authority, 1), farm_owners.clone()),
+            (instruction::farm_unstake(&program_id, &farm, &a, &b, &authority, 1), farm_owners.clone()),
+            (
+                instruction::claim_farm_rewards(&program_id, &farm, &a, &b, &authority),
+                vec![
+                    (farm_position, program_id),
+                    (farm, program_id),
+                    (farm_reward_vault, spl_token_id),
+                    (a, spl_token_id),
+                    (b, spl_token_id),
+                ],
+            ),
+            (
+                instruction::initialize_emission_schedule(&program_id, &c, &a, &authority, 1, 1, 0),
+                vec![(pool, program_id), (c, spl_token_id), (a, program_id)],
+            ),
+            (
+                instruction::undelegate_stake(&program_id, &c, &authority, &a),
+                vec![(stake, program_id), (pool, program_id), (config, program_id), (c, spl_token_id), (a, program_id)],
+            ),
+        ];
+
+        for (ix, owners) in cases {
+            let signers: Vec<_> = ix.accounts.iter().filter(|meta| meta.is_signer).map(|meta| meta.pubkey).collect();
+            assert!(!signers.is_empty());
+            for signer in signers {
+                assert_eq!(
+                    process_without_signature(&program_id, &ix, &owners, &signer),
+                    Err(ProgramError::MissingRequiredSignature),
+                    "instruction {}",
+                    ix.data[0]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_account_validation() {
+        use solana_token::error::TokenError;
+        use solana_token::validation::AccountCheck;
+
+        let program_id = Pubkey::new_unique();
+        let (seed_key, bump) = Pubkey::find_program_address(&[b"seed"], &program_id);
+        let mut lamports = 0;
+        let mut data = [];
+        let account = create_account_info(&seed_key, false, false, &mut lamports, &mut data, &program_id);
+
+        assert!(account.owned_by(&program_id).is_ok());
+        assert_eq!(account.owned_by(&spl_token::id()).err(), Some(ProgramError::IncorrectProgramId));
+        assert_eq!(account.writable().err(), Some(TokenError::AccountNotWritable.into()));
+        assert_eq!(account.signer().err(), Some(ProgramError::MissingRequiredSignature));
+        assert_eq!(account.is(&solana_program::sysvar::clock::id()).err(), Some(ProgramError::InvalidArgument));
+        assert_eq!(account.pda(&[b"seed"], &program_id).unwrap().1, bump);
+        assert_eq!(
+            account.pda(&[b"other"], &program_id).err(),
+            Some(TokenError::InvalidProgramAddress.into())
+        );
+        assert!(account.pda_with_bump(&[b"seed"], bump, &program_id).is_ok());
+        assert_eq!(
+            account.pda_with_bump(&[b"seed"], bump.wrapping_sub(1), &program_id).err(),
+            Some(TokenError::InvalidProgramAddress.into())
+        );
+    }
+
+    #[test]
+    fn test_instruction_round_trip() {
+        let instructions = vec![
+            TokenInstruction::InitializeMint { decimals: 9 },
+            TokenInstruction::InitializeAccount,
+            TokenInstruction::Transfer { amount: 1 },
+            TokenInstruction::Burn { amount: 2 },
+            TokenInstruction::MintTo { amount: 3 },
+            TokenInstruction::Freeze,
+            TokenInstruction::Thaw,
+            TokenInstruction::SetAuthority { authority_type: 1, new_authority: None },
+            TokenInstruction::SetAuthority { authority_type: 0, new_authority: Some(Pubkey::new_unique()) },
+            TokenInstruction::Stake { amount: 4 },
+            TokenInstruction::Unstake { amount: 5 },
+            TokenInstruction::UpgradeProgram,
+            TokenInstruction::InitializeTokenInfo,
+            TokenInstruction::CreateVestingSchedule {
+                beneficiary: Pubkey::new_unique(),
+                allocation_type: AllocationType::Investors,
+                amount: 1_000,
+                start_time: -1,
+                cliff_time: 0,
+                end_time: i64::MAX,
+            },
+            TokenInstruction::ReleaseVestedTokens,
+            TokenInstruction::MigrateAccount { account_type: AccountType::StakeInfo },
+            TokenInstruction::QueryVestedAmount,
+            TokenInstruction::QueryReleasableAmount,
+            TokenInstruction::QueryStakeInfo,
+            TokenInstruction::QueryAllocationRemaining { allocation_type: AllocationType::Community },
+            TokenInstruction::InitializeVault,
+            TokenInstruction::InitializeStake { lock_tier: LockTier::Days180 },
+            TokenInstruction::InitializeStakingPool { reward_rate: 6 },
+            TokenInstruction::FundRewards { amount: 7 },
+            TokenInstruction::ClaimRewards,
+            TokenInstruction::QueryPendingRewards,
+            TokenInstruction::InitializeStakingConfig {
+                params: StakingParams {
+                    min_stake: 8,
+                    lock_duration: 9,
+                    reward_rate: 10,
+                    max_total_stake: 11,
+                    paused: true,
+                    unbonding_period: 12,
+                    penalty_curve: PenaltyCurve::LinearDecay,
+                    penalty_bps: 13,
+                    penalty_destination: PenaltyDestination::RewardPool,
+                    treasury: Pubkey::new_unique(),
+                },
+            },
+            TokenInstruction::UpdateStakingConfig { params: StakingParams::default() },
+            TokenInstruction::ExtendLock { lock_tier: LockTier::Days365 },
+            TokenInstruction::RequestUnstake { amount: 12 },
+            TokenInstruction::WithdrawUnbonded,
+            TokenInstruction::CancelUnbonding,
+            TokenInstruction::EmergencyUnstake { amount: 13 },
+            TokenInstruction::InitializeLiquidPool,
+            TokenInstruction::LiquidStake { amount: 14 },
+            TokenInstruction::LiquidUnstake { receipt_amount: 15 },
+            TokenInstruction::CompoundRewards,
+            TokenInstruction::SetAutoCompound { enabled: true },
+            TokenInstruction::InitializeLocker { max_lock_duration: 16 },
+            TokenInstruction::CreateVoteLock { amount: 17, unlock_time: 18 },
+            TokenInstruction::IncreaseVoteLockAmount { amount: 19 },
+            TokenInstruction::ExtendVoteLock { unlock_time: 20 },
+            TokenInstruction::WithdrawVoteLock,
+            TokenInstruction::QueryVotingPower { timestamp: 21 },
+            TokenInstruction::QueryTotalVotingPower { timestamp: 22 },
+            TokenInstruction::RegisterOperator { commission_bps: 23 },
+            TokenInstruction::DelegateStake,
+            TokenInstruction::ClaimCommission,
+            TokenInstruction::CreateFarm {
+                id: 24,
+                emission_rate: 25,
+                start_time: 26,
+                end_time: 27,
+            },
+            TokenInstruction::FundFarm { amount: 28 },
+            TokenInstruction::FarmStake { amount: 29 },
+            TokenInstruction::FarmUnstake { amount: 30 },
+            TokenInstruction::ClaimFarmRewards,
+            TokenInstruction::QueryFarmRewards,
+            TokenInstruction::InitializeEmissionSchedule {
+                epoch_duration: 31,
+                initial_emission: 32,
+                decay_bps: 33,
+            },
+            TokenInstruction::AdvanceEpoch,
+            TokenInstruction::QueryEmissionSchedule,
+            TokenInstruction::UndelegateStake,
+            TokenInstruction::RequestLiquidUnstake { receipt_amount: 34 },
+            TokenInstruction::WithdrawLiquidUnbonded,
+            TokenInstruction::UpdateFarm {
+                emission_rate: 35,
+                end_time: 36,
+            },
+        ];
+
+        for instruction in instructions {
+            let packed = instruction.pack();
+            assert_eq!(TokenInstruction::unpack(&packed).unwrap(), instruction);
+
+            let mut too_long = packed.clone();
+            too_long.push(0);
+            assert!(TokenInstruction::unpack(&too_long).is_err());
+            if packed.len() > 1 {
+                assert!(TokenInstruction::unpack(&packed[..packed.len() - 1]).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_authority_wire_format() {
+        let new_authority = Pubkey::new_unique();
+        let mut with_authority = vec![7, 2];
+        with_authority.extend_from_slice(new_authority.as_ref());
+        let cases = [
+            (vec![7, 1], TokenInstruction::SetAuthority { authority_type: 1, new_authority: None }),
+            (
+                with_authority,
+                TokenInstruction::SetAuthority { authority_type: 2, new_authority: Some(new_authority) },
+            ),
+        ];
+        for (bytes, instruction) in cases {
+            assert_eq!(TokenInstruction::unpack(&bytes).unwrap(), instruction);
+            assert_eq!(instruction.pack(), bytes);
+        }
+
+        // A Borsh option tag is not part of the format.
+        assert!(TokenInstruction::unpack(&[7, 1, 0]).is_err());
+        assert!(TokenInstruction::unpack(&[&[7, 1, 1][..], new_authority.as_ref()].concat()).is_err());
+    }
+
+    #[test]
+    fn test_create_vesting_schedule_rejects_bad_payloads() {
+        let instruction = TokenInstruction::CreateVestingSchedule {
+            beneficiary: Pubkey::new_unique(),
+            allocation_type: AllocationType::Team,
+            amount: 10,
+            start_time: 0,
+            cliff_time: 50,
+            end_time: 100,
+        };
+        let mut packed = instruction.pack();
+        assert_eq!(packed.len(), 1 + 65);
+
+        packed[33] = 6; // unknown allocation type
+        assert!(TokenInstruction::unpack(&packed).is_err());
+        assert!(TokenInstruction::unpack(&[14]).is_err());
+        assert!(TokenInstruction::unpack(&[]).is_err());
+    }
+
+    #[test]
+    fn test_instruction_builders() {
+        let program_id = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let staker = Pubkey::new_unique();
+
+        let mint = Pubkey::new_unique();
+
+        let ix = instruction::stake(&program_id, &token_account, &mint, &staker, 42, None);
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(TokenInstruction::unpack(&ix.data).unwrap(), TokenInstruction::Stake { amount: 42 });
+        let signers: Vec<_> = ix.accounts.iter().filter(|meta| meta.is_signer).map(|meta| meta.pubkey).collect();
+        assert_eq!(signers, vec![staker]);
+        assert!(ix.accounts[0].is_writable && ix.accounts[1].is_writable && ix.accounts[2].is_writable);
+        assert_eq!(ix.accounts[0].pubkey, find_stake_address(&program_id, &mint, &staker).0);
+        assert_eq!(ix.accounts[2].pubkey, find_vault_address(&program_id, &mint).0);
+        assert_eq!(ix.accounts[4].pubkey, find_staking_config_address(&program_id, &mint).0);
+        assert_eq!(ix.accounts[7].pubkey, solana_program::sysvar::clock::id());
+
+        let beneficiary = Pubkey::new_unique();
+        let ix = instruction::create_vesting_schedule(
+            &program_id,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &staker,
+            &beneficiary,
+            AllocationType::Team,
+            1_000,
+            10,
+            15,
+            20,
+        );
+        assert_eq!(
+            TokenInstruction::unpack(&ix.data).unwrap(),
+            TokenInstruction::CreateVestingSchedule {
+                beneficiary,
+                allocation_type: AllocationType::Team,
+                amount: 1_000,
+                start_time: 10,
+                cliff_time: 15,
+                end_time: 20,
+            }
+        );
+        assert!(ix.accounts[2].is_signer);
+    }
+
+    #[test]
+    fn test_state_len_matches_borsh_layout() {
+        use solana_program::borsh1::get_packed_len;
+        use solana_token::state::{
+            EmissionSchedule, Farm, FarmPosition, Operator, StakeInfo, StakingConfig, StakingPool, UnbondingEntry,
+            VestingSchedule, VoteLock,
+        };
+        use solana_token::token_info::TokenInfo;
+
+        assert_eq!(get_packed_len::<TokenInfo>(), TokenInfo::LEN);
+        assert_eq!(get_packed_len::<VestingSchedule>(), VestingSchedule::LEN);
+        assert_eq!(get_packed_len::<StakeInfo>(), StakeInfo::LEN);
+        assert_eq!(get_packed_len::<StakingPool>(), StakingPool::LEN);
+        assert_eq!(get_packed_len::<StakingConfig>(), StakingConfig::LEN);
+        assert_eq!(get_packed_len::<UnbondingEntry>(), UnbondingEntry::LEN);
+        assert_eq!(get_packed_len::<VoteLock>(), VoteLock::LEN);
+        assert_eq!(get_packed_len::<Operator>(), Operator::LEN);
+        assert_eq!(get_packed_len::<Farm>(), Farm::LEN);
+        assert_eq!(get_packed_len::<FarmPosition>(), FarmPosition::LEN);
+        assert_eq!(get_packed_len::<EmissionSchedule>(), EmissionSchedule::LEN);
+    }
+
+    #[test]
+    fn test_account_header_rejects_other_types() {
+        use solana_token::error::TokenError;
+        use solana_token::state::{AccountHeader, StakeInfo, VestingSchedule};
+
+        let vesting = VestingSchedule {
+            header: AccountHeader::new::<VestingSchedule>(),
+            beneficiary: Pubkey::new_unique(),
+            total_amount: 100,
+            released_amount: 0,
+            start_time: 0,
+            cliff_time: 0,
+            end_time: 10,
+            allocation_type: AllocationType::Team,
+        };
+        let mut data = vec![0; VestingSchedule::LEN];
+        VestingSchedule::pack(vesting.clone(), &mut data).unwrap();
+        assert_eq!(AccountHeader::unpack(&data).unwrap().account_type, AccountType::VestingSchedule);
+        assert_eq!(VestingSchedule::unpack(&data).unwrap(), vesting);
+        let mut as_stake = data.clone();
+        as_stake.resize(StakeInfo::LEN, 0);
+        assert_eq!(StakeInfo::unpack(&as_stake), Err(TokenError::InvalidAccountType.into()));
+
+        data[1] = 0; // unknown layout version
+        assert_eq!(VestingSchedule::unpack(&data), Err(TokenError::UnsupportedAccountVersion.into()));
+        assert_eq!(
+            VestingSchedule::unpack(&[0; VestingSchedule::LEN]),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn test_locker_voting_power() {
+        use solana_token::error::TokenError;
+        use solana_token::state::{Locker, VOTE_LOCK_WEEK as WEEK};
+
+        // Amounts are multiples of 189 so that their slopes over four weeks
+        // are exact.
+        let mut locker = Locker::new(Pubkey::new_unique(), 0, 0, 4 * WEEK, 0);
+        assert_eq!(borsh::to_vec(&locker).unwrap().len(), Locker::LEN);
+        let slope = |amount: u128| amount * 1_000_000_000_000 / (4 * WEEK) as u128;
+
+        let end = locker.unlock_time(14 * WEEK + 5, 10 * WEEK).unwrap();
+        assert_eq!(end, 14 * WEEK);
+        locker.update_lock((0, 0), (slope(7_560), end), 10 * WEEK).unwrap();
+        locker.update_lock((0, 0), (slope(3_780), 13 * WEEK), 11 * WEEK).unwrap();
+        assert_eq!(locker.total_power_at(10 * WEEK).unwrap(), 7_560);
+        assert_eq!(locker.total_power_at(12 * WEEK).unwrap(), 3_780 + 945);
+        assert_eq!(locker.total_power_at(12 * WEEK + WEEK / 2).unwrap(), 2_835 + 472);
+        assert_eq!(locker.total_power_at(13 * WEEK).unwrap(), 1_890);
+        assert_eq!(locker.total_power_at(14 * WEEK).unwrap(), 0);
+        assert_eq!(locker.total_power_at(5 * WEEK).unwrap(), 0);
+
+        // Checkpointing past the ends of the locks keeps the same history.
+        locker.update_lock((0, 0), (0, 0), 20 * WEEK).unwrap();
+        assert_eq!(locker.latest_point().slope, 0);
+        assert_eq!(locker.total_power_at(12 * WEEK + WEEK / 2).unwrap(), 2_835 + 472);
+        assert_eq!(locker.total_power_at(13 * WEEK).unwrap(), 1_890);
+
+        assert_eq!(locker.unlock_time(20 * WEEK + 5, 20 * WEEK), Err(TokenError::InvalidUnlockTime.into()));
+        assert_eq!(locker.unlock_time(25 * WEEK, 20 * WEEK), Err(TokenError::InvalidUnlockTime.into()));
+
+        for week in 21..100 {
+            locker.update_lock((0, 0), (0, 0), week * WEEK).unwrap();
+        }
+        assert_eq!(locker.total_power_at(13 * WEEK), Err(TokenError::VotingPowerUnavailable.into()));
+    }
+
+    #[test]
+    fn test_vested_amount() {
+        use solana_token::state::{AccountHeader, VestingSchedule};
+
+        let mut vesting = VestingSchedule {
+            header: AccountHeader::new::<VestingSchedule>(),
+            beneficiary: Pubkey::new_unique(),
+            total_amount: u64::MAX,
+            released_amount: 0,
+            start_time: 100,
+            cliff_time: 100,
+            end_time: 200,
+            allocation_type: AllocationType::Team,
+        };
+        assert_eq!(vesting.vested_amount(99), 0);
+        assert_eq!(vesting.vested_amount(100), 0);
+        assert_eq!(vesting.vested_amount(150), u64::MAX / 2);
+        assert_eq!(vesting.vested_amount(200), u64::MAX);
+        assert_eq!(vesting.vested_amount(i64::MAX), u64::MAX);
+
+        vesting.released_amount = u64::MAX / 4;
+        assert_eq!(vesting.releasable_amount(150), u64::MAX / 2 - u64::MAX / 4);
+        assert_eq!(vesting.releasable_amount(110), 0);
+
+        // Nothing vests before the cliff, where what vested since the start
+        // unlocks at once.
+        vesting.released_amount = 0;
+        vesting.cliff_time = 125;
+        assert_eq!(vesting.vested_amount(124), 0);
+        assert_eq!(vesting.vested_amount(125), u64::MAX / 4);
+        assert_eq!(vesting.vested_amount(150), u64::MAX / 2);
+        vesting.cliff_time = vesting.end_time;
+        assert_eq!(vesting.vested_amount(199), 0);
+        assert_eq!(vesting.vested_amount(200), u64::MAX);
+
+        // A schedule that ends when it starts vests all at once.
+        vesting.end_time = vesting.start_time;
+        vesting.cliff_time = vesting.start_time;
+        assert_eq!(vesting.vested_amount(100), u64::MAX);
+    }
+
+    #[test]
+    fn test_reward_accumulator() {
+        use solana_token::state::{AccountHeader, StakeInfo, StakingPool};
+        use solana_token::token_info::TOTAL_SUPPLY;
+
+        let stake = |amount| StakeInfo {
+            header: AccountHeader::new::<StakeInfo>(),
+            owner: Pubkey::new_unique(),
+            mint: Pubkey::default(),
+            bump: 0,
+            amount,
+            weight: amount,
+            lock_tier: LockTier::Days30,
+            start_time: 0,
+            unlock_time: 0,
+            created_at: 0,
+            updated_at: 0,
+            reward_per_token_paid: 0,
+            pending_rewards: 0,
+            unbonding_amount: 0,
+            next_unbonding_id: 0,
+            auto_compound: false,
+            operator: Pubkey::default(),
+            commission_bps: 0,
+            pending_commission: 0,
+        };
+        let mut pool = StakingPool {
+            header: AccountHeader::new::<StakingPool>(),
+            mint: Pubkey::default(),
+            bump: 0,
+            reward_vault_bump: 0,
+            total_staked: 0,
+            total_weight: 0,
+            reward_rate: TOTAL_SUPPLY / 10,
+            reward_per_token: 0,
+            undistributed_rewards: TOTAL_SUPPLY / 2,
+            last_update_time: 0,
+            emission_end: 0,
+        };
+
+        // Nothing is emitted while nothing is staked.
+        pool.accrue(100).unwrap();
+        assert_eq!((pool.reward_per_token, pool.undistributed_rewards), (0, TOTAL_SUPPLY / 2));
+
+        // Nearly the whole supply staked, at 9 decimals, next to a single
+        // base unit.
+        let mut whale = stake(TOTAL_SUPPLY - TOTAL_SUPPLY / 2 - 1);
+        let mut minnow = stake(1);
+        pool.total_weight = whale.weight + minnow.weight;
+        pool.accrue(101).unwrap();
+        assert_eq!(pool.undistributed_rewards, TOTAL_SUPPLY / 2 - TOTAL_SUPPLY / 10);
+        let (whale_earned, minnow_earned) = (whale.earned(pool.reward_per_token).unwrap(), minnow.earned(pool.reward_per_token).unwrap());
+        assert!(whale_earned + minnow_earned <= TOTAL_SUPPLY / 10);
+        assert!(TOTAL_SUPPLY / 10 - (whale_earned + minnow_earned) <= 1);
+        assert_eq!(minnow_earned, 0);
+
+        // Settling keeps what was earned while the balance changes.
+        whale.settle(pool.reward_per_token).unwrap();
+        minnow.settle(pool.reward_per_token).unwrap();
+        pool.total_weight -= whale.weight;
+        whale.weight = 0;
+
+        // Emission stops when the funded rewards run out, and all of it goes
+        // to the only staker left.
+        pool.accrue(1_000).unwrap();
+        assert_eq!(pool.undistributed_rewards, 0);
+        assert_eq!(whale.earned(pool.reward_per_token).unwrap(), whale_earned);
+        assert_eq!(minnow.earned(pool.reward_per_token).unwrap(), TOTAL_SUPPLY / 2 - TOTAL_SUPPLY / 10);
+
+        // Time going backwards emits nothing.
+        let before = pool.clone();
+        pool.accrue(500).unwrap();
+        assert_eq!(pool, before);
     }
 
+    #[test]
+    fn test_stake_deposit_averages_times() {
+        use solana_token::error::TokenError;
+        use solana_token::state::{AccountHeader, StakeInfo};
+
+        let mut stake = StakeInfo {
+            header: AccountHeader::new::<StakeInfo>(),
+            owner: Pubkey::new_unique(),
+            mint: Pubkey::default(),
+            bump: 0,
+            amount: 0,
+            weight: 0,
+            lock_tier: LockTier::Days90,
+            start_time: 0,
+            unlock_time: 0,
+            created_at: 0,
+            updated_at: 0,
+            reward_per_token_paid: 0,
+            pending_rewards: 0,
+            unbonding_amount: 0,
+            next_unbonding_id: 0,
+            auto_compound: false,
+            operator: Pubkey::default(),
+            commission_bps: 0,
+            pending_commission: 0,
+        };
+        let lock = LockTier::Days90.duration();
+
+        // An empty position takes the times of its first deposit.
+        stake.deposit(300, 1_000).unwrap();
+        assert_eq!((stake.amount, stake.start_time, stake.unlock_time), (300, 1_000, 1_000 + lock));
+
+        // A quarter of the tokens 400 seconds younger move it by 100.
+        stake.deposit(100, 1_400).unwrap();
+        assert_eq!((stake.amount, stake.start_time, stake.unlock_time), (400, 1_100, 1_100 + lock));
+
+        // The largest positions and times do not overflow.
+        stake.amount = u64::MAX - 1;
+        stake.deposit(1, i64::MAX - lock).unwrap();
+        assert!(stake.start_time >= 1_100 && stake.unlock_time > stake.start_time);
+        assert_eq!(stake.deposit(1, 0), Err(TokenError::AmountOverflow.into()));
+    }
+
+    #[test]
+    fn test_liquid_pool_exchange_rate() {
+        use solana_token::error::TokenError;
+        use solana_token::state::LiquidPool;
+
+        // The first deposit mints one to one, later ones at the rate.
+        assert_eq!(LiquidPool::receipt_for(100, 0, 0), Ok(100));
+        assert_eq!(LiquidPool::receipt_for(100, 50, 0), Ok(100));
+        assert_eq!(LiquidPool::receipt_for(100, 400, 200), Ok(50));
+        assert_eq!(LiquidPool::underlying_for(50, 400, 200), 100);
+
+        // Receipts with no stake behind them cannot set a rate.
+        assert_eq!(LiquidPool::receipt_for(100, 0, 200), Err(TokenError::EmptyLiquidPool.into()));
+        assert_eq!(LiquidPool::underlying_for(200, 0, 200), 0);
+    }
+
+    #[test]
+    fn test_migrate_historical_layouts() {
+        use solana_token::error::TokenError;
+        use solana_token::migration::{self, v0, v1, v2, v3, v4, v5, v6};
+        use solana_token::state::{
+            find_reward_vault_address, find_vault_address, AccountHeader, Operator, StakeInfo, StakingConfig, StakingPool,
+            UnbondingEntry, VestingSchedule,
+        };
+        use solana_token::token_info::TokenInfo;
+
+        let program_id = Pubkey::new_unique();
+        let mint_authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let beneficiary = Pubkey::new_unique();
+
+        // Version 0 fixtures, written field by field as the original program did.
+        let mut token_info_v0 = Vec::new();
+        for value in [5_000u64, 500, 1_000, 750, 1_000, 1_250, 500] {
+            token_info_v0.extend_from_slice(&value.to_le_bytes());
+        }
+        token_info_v0.extend_from_slice(mint_authority.as_ref());
+        token_info_v0.extend_from_slice(mint.as_ref());
+        assert_eq!(token_info_v0.len(), v0::TokenInfo::LEN);
+
+        let mut vesting_v0 = Vec::new();
+        vesting_v0.extend_from_slice(beneficiary.as_ref());
+        vesting_v0.extend_from_slice(&1_000u64.to_le_bytes());
+        vesting_v0.extend_from_slice(&250u64.to_le_bytes());
+        vesting_v0.extend_from_slice(&10i64.to_le_bytes());
+        vesting_v0.extend_from_slice(&110i64.to_le_bytes());
+        vesting_v0.push(AllocationType::Development as u8);
+        assert_eq!(vesting_v0.len(), v0::VestingSchedule::LEN);
+
+        let mut stake_v0 = Vec::new();
+        stake_v0.extend_from_slice(&42u64.to_le_bytes());
+        stake_v0.extend_from_slice(&(-7i64).to_le_bytes());
+        assert_eq!(stake_v0.len(), v0::StakeInfo::LEN);
+
+        let migrated = migration::migrate(&program_id, AccountType::TokenInfo, &token_info_v0).unwrap().unwrap();
+        let token_info = TokenInfo::unpack(&migrated).unwrap();
+        assert_eq!(token_info.header.version, 1);
+        assert_eq!(token_info.total_supply, 5_000);
+        assert_eq!(token_info.community_rewards, 1_250);
+        assert_eq!(token_info.strategic_reserve, 500);
+        assert_eq!(token_info.mint_authority, mint_authority);
+        assert_eq!(token_info.mint, mint);
+        assert_eq!(migration::migrate(&program_id, AccountType::TokenInfo, &migrated), Ok(None));
+
+        let vesting_v1 = migration::migrate(&program_id, AccountType::VestingSchedule, &vesting_v0).unwrap().unwrap();
+        let vesting = VestingSchedule::unpack(&vesting_v1).unwrap();
+        assert_eq!(vesting.beneficiary, beneficiary);
+        assert_eq!((vesting.total_amount, vesting.released_amount), (1_000, 250));
+        assert_eq!((vesting.start_time, vesting.cliff_time, vesting.end_time), (10, 10, 110));
+        assert_eq!(vesting.allocation_type, AllocationType::Development);
+
+        // Schedules from before cliffs vest from their start.
+        let vesting_v1 = borsh::to_vec(&v1::VestingSchedule {
+            header: AccountHeader { account_type: AccountType::VestingSchedule, version: 1, is_initialized: true },
+            beneficiary,
+            total_amount: 1_000,
+            released_amount: 250,
+            start_time: 10,
+            end_time: 110,
+            allocation_type: AllocationType::Team,
+        })
+        .unwrap();
+        assert_eq!(vesting_v1.len(), v1::VestingSchedule::LEN);
+        let migrated = migration::migrate(&program_id, AccountType::VestingSchedule, &vesting_v1).unwrap().unwrap();
+        let vesting = VestingSchedule::unpack(&migrated).unwrap();
+        assert_eq!((vesting.start_time, vesting.cliff_time, vesting.end_time), (10, 10, 110));
+        assert_eq!((vesting.released_amount, vesting.allocation_type), (250, AllocationType::Team));
+        assert_eq!(vesting.vested_amount(60), 500);
+        assert_eq!(migration::migrate(&program_id, AccountType::VestingSchedule, &migrated), Ok(None));
+
+        let migrated = migration::migrate(&program_id, AccountType::StakeInfo, &stake_v0).unwrap().unwrap();
+        let stake = StakeInfo::unpack(&migrated).unwrap();
+        assert_eq!((stake.amount, stake.weight, stake.start_time), (42, 0, -7));
+        assert_eq!((stake.owner, stake.mint), (Pubkey::default(), Pubkey::default()));
+
+        // Version 1 stakes had a header but no owner or mint.
+        let mut stake_v1 = vec![AccountType::StakeInfo as u8, 1, 1];
+        stake_v1.extend_from_slice(&stake_v0);
+        assert_eq!(stake_v1.len(), v1::StakeInfo::LEN);
+        let from_v1 = migration::migrate(&program_id, AccountType::StakeInfo, &stake_v1).unwrap().unwrap();
+        assert_eq!(from_v1, migrated);
+        assert_eq!(migration::migrate(&program_id, AccountType::StakeInfo, &from_v1), Ok(None));
+
+        // Version 2 stakes predate the pool, and are only weighed and earn
+        // once first touched.
+        let owner = Pubkey::new_unique();
+        let stake_v2 = borsh::to_vec(&v2::StakeInfo {
+            header: AccountHeader { account_type: AccountType::StakeInfo, version: 2, is_initialized: true },
+            owner,
+            mint,
+            bump: 254,
+            amount: 42,
+            start_time: 5,
+            created_at: 1,
+            updated_at: 5,
+        })
+        .unwrap();
+        assert_eq!(stake_v2.len(), v2::StakeInfo::LEN);
+        let stake = StakeInfo::unpack(&migration::migrate(&program_id, AccountType::StakeInfo, &stake_v2).unwrap().unwrap()).unwrap();
+        assert_eq!((stake.owner, stake.mint, stake.bump, stake.amount), (owner, mint, 254, 42));
+        assert_eq!((stake.weight, stake.reward_per_token_paid, stake.pending_rewards), (0, 0, 0));
+
+        // Version 3 stakes keep their rewards and weigh their amount, as
+        // they did in version 1 pools.
+        let stake_v3 = borsh::to_vec(&v3::StakeInfo {
+            header: AccountHeader { account_type: AccountType::StakeInfo, version: 3, is_initialized: true },
+            owner,
+            mint,
+            bump: 254,
+            amount: 42,
+            start_time: 5,
+            created_at: 1,
+            updated_at: 5,
+            reward_per_token_paid: 9,
+            pending_rewards: 3,
+        })
+        .unwrap();
+        assert_eq!(stake_v3.len(), v3::StakeInfo::LEN);
+        let stake = StakeInfo::unpack(&migration::migrate(&program_id, AccountType::StakeInfo, &stake_v3).unwrap().unwrap()).unwrap();
+        assert_eq!((stake.amount, stake.weight, stake.lock_tier), (42, 42, LockTier::Days30));
+        assert_eq!((stake.start_time, stake.unlock_time), (5, 5));
+        assert_eq!((stake.reward_per_token_paid, stake.pending_rewards), (9, 3));
+
+        let stake_v4 = borsh::to_vec(&v4::StakeInfo {
+            header: AccountHeader { account_type: AccountType::StakeInfo, version: 4, is_initialized: true },
+            owner,
+            mint,
+            bump: 254,
+            amount: 42,
+            weight: 84,
+            lock_tier: LockTier::Days365,
+            start_time: 5,
+            unlock_time: 500,
+            created_at: 1,
+            updated_at: 5,
+            reward_per_token_paid: 9,
+            pending_rewards: 3,
+        })
+        .unwrap();
+        assert_eq!(stake_v4.len(), v4::StakeInfo::LEN);
+        let stake = StakeInfo::unpack(&migration::migrate(&program_id, AccountType::StakeInfo, &stake_v4).unwrap().unwrap()).unwrap();
+        assert_eq!((stake.weight, stake.lock_tier, stake.unlock_time), (84, LockTier::Days365, 500));
+        assert_eq!((stake.unbonding_amount, stake.next_unbonding_id), (0, 0));
+
+        // Positions from before auto-compounding have to opt in.
+        let stake_v5 = borsh::to_vec(&v5::StakeInfo {
+            header: AccountHeader { account_type: AccountType::StakeInfo, version: 5, is_initialized: true },
+            owner,
+            mint,
+            bump: 254,
+            amount: 42,
+            weight: 84,
+            lock_tier: LockTier::Days365,
+            start_time: 5,
+            unlock_time: 500,
+            created_at: 1,
+            updated_at: 5,
+            reward_per_token_paid: 9,
+            pending_rewards: 3,
+            unbonding_amount: 7,
+            next_unbonding_id: 2,
+        })
+        .unwrap();
+        assert_eq!(stake_v5.len(), v5::StakeInfo::LEN);
+        let stake = StakeInfo::unpack(&migration::migrate(&program_id, AccountType::StakeInfo, &stake_v5).unwrap().unwrap()).unwrap();
+        assert_eq!((stake.unbonding_amount, stake.next_unbonding_id, stake.auto_compound), (7, 2, false));
+
+        // Positions from before delegation are not delegated.
+        let stake_v6 = borsh::to_vec(&v6::StakeInfo {
+            header: AccountHeader { account_type: AccountType::StakeInfo, version: 6, is_initialized: true },
+            owner,
+            mint,
+            bump: 254,
+            amount: 42,
+            weight: 84,
+            lock_tier: LockTier::Days365,
+            start_time: 5,
+            unlock_time: 500,
+            created_at: 1,
+            updated_at: 5,
+            reward_per_token_paid: 9,
+            pending_rewards: 3,
+            unbonding_amount: 7,
+            next_unbonding_id: 2,
+            auto_compound: true,
+        })
+        .unwrap();
+        assert_eq!(stake_v6.len(), v6::StakeInfo::LEN);
+        let stake = StakeInfo::unpack(&migration::migrate(&program_id, AccountType::StakeInfo, &stake_v6).unwrap().unwrap()).unwrap();
+        assert!(stake.auto_compound && !stake.is_delegated());
+        assert_eq!((stake.pending_rewards, stake.pending_commission), (3, 0));
+
+        let pool_v1 = borsh::to_vec(&v1::StakingPool {
+            header: AccountHeader { account_type: AccountType::StakingPool, version: 1, is_initialized: true },
+            mint,
+            bump: 253,
+            reward_vault_bump: 252,
+            total_staked: 42,
+            reward_rate: 10,
+            reward_per_token: 9,
+            undistributed_rewards: 100,
+            last_update_time: 5,
+        })
+        .unwrap();
+        assert_eq!(pool_v1.len(), v1::StakingPool::LEN);
+        let migrated = migration::migrate(&program_id, AccountType::StakingPool, &pool_v1).unwrap().unwrap();
+        let pool = StakingPool::unpack(&migrated).unwrap();
+        assert_eq!((pool.total_staked, pool.total_weight, pool.reward_per_token), (42, 42, 9));
+        assert_eq!(pool.emission_end, 0);
+        assert_eq!(migration::migrate(&program_id, AccountType::StakingPool, &migrated), Ok(None));
+
+        // Pools from before emission schedules emit without epochs.
+        let pool_v2 = borsh::to_vec(&v2::StakingPool {
+            header: AccountHeader { account_type: AccountType::StakingPool, version: 2, is_initialized: true },
+            mint,
+            bump: 253,
+            reward_vault_bump: 252,
+            total_staked: 42,
+            total_weight: 84,
+            reward_rate: 10,
+            reward_per_token: 9,
+            undistributed_rewards: 100,
+            last_update_time: 5,
+        })
+        .unwrap();
+        assert_eq!(pool_v2.len(), v2::StakingPool::LEN);
+        let pool = StakingPool::unpack(&migration::migrate(&program_id, AccountType::StakingPool, &pool_v2).unwrap().unwrap()).unwrap();
+        assert_eq!((pool.total_weight, pool.reward_rate, pool.last_update_time, pool.emission_end), (84, 10, 5, 0));
+
+        // Configs from before unbonding keep unstaking immediate, and
+        // configs from before penalties keep locked positions locked.
+        let config_v1 = borsh::to_vec(&v1::StakingConfig {
+            header: AccountHeader { account_type: AccountType::StakingConfig, version: 1, is_initialized: true },
+            mint,
+            bump: 251,
+            params: v1::StakingParams {
+                min_stake: 1,
+                lock_duration: 2,
+                reward_rate: 3,
+                max_total_stake: 4,
+                paused: true,
+            },
+        })
+        .unwrap();
+        assert_eq!(config_v1.len(), v1::StakingConfig::LEN);
+        let migrated = migration::migrate(&program_id, AccountType::StakingConfig, &config_v1).unwrap().unwrap();
+        let config = StakingConfig::unpack(&migrated).unwrap();
+        assert_eq!(
+            config.params,
+            StakingParams {
+                min_stake: 1,
+                lock_duration: 2,
+                reward_rate: 3,
+                max_total_stake: 4,
+                paused: true,
+                unbonding_period: 0,
+                penalty_curve: PenaltyCurve::Disabled,
+                penalty_bps: 0,
+                penalty_destination: PenaltyDestination::Burn,
+                treasury: Pubkey::default(),
+            }
+        );
+        assert_eq!(migration::migrate(&program_id, AccountType::StakingConfig, &migrated), Ok(None));
+        let config_v2 = borsh::to_vec(&v2::StakingConfig {
+            header: AccountHeader { account_type: AccountType::StakingConfig, version: 2, is_initialized: true },
+            mint,
+            bump: 251,
+            params: v2::StakingParams {
+                min_stake: 1,
+                lock_duration: 2,
+                reward_rate: 3,
+                max_total_stake: 4,
+                paused: false,
+                unbonding_period: 5,
+            },
+        })
+        .unwrap();
+        assert_eq!(config_v2.len(), v2::StakingConfig::LEN);
+        let migrated = migration::migrate(&program_id, AccountType::StakingConfig, &config_v2).unwrap().unwrap();
+        let config = StakingConfig::unpack(&migrated).unwrap();
+        assert_eq!((config.mint, config.bump), (mint, 251));
+        assert_eq!((config.params.unbonding_period, config.params.penalty_curve), (5, PenaltyCurve::Disabled));
+
+        // Configs and operators gain the bumps of the vaults they stand for.
+        let params = StakingParams { treasury: Pubkey::new_unique(), ..StakingParams::default() };
+        let config_v3 = borsh::to_vec(&v3::StakingConfig {
+            header: AccountHeader { account_type: AccountType::StakingConfig, version: 3, is_initialized: true },
+            mint,
+            bump: 251,
+            params,
+        })
+        .unwrap();
+        assert_eq!(config_v3.len(), v3::StakingConfig::LEN);
+        let migrated = migration::migrate(&program_id, AccountType::StakingConfig, &config_v3).unwrap().unwrap();
+        let config = StakingConfig::unpack(&migrated).unwrap();
+        assert_eq!((config.bump, config.params), (251, params));
+        assert_eq!(config.vault_bump, find_vault_address(&program_id, &mint).1);
+        assert_eq!(migration::migrate(&program_id, AccountType::StakingConfig, &migrated), Ok(None));
+
+        let authority = Pubkey::new_unique();
+        let operator_v1 = borsh::to_vec(&v1::Operator {
+            header: AccountHeader { account_type: AccountType::Operator, version: 1, is_initialized: true },
+            mint,
+            authority,
+            bump: 250,
+            commission_bps: 500,
+            delegations: 2,
+            pending_commission: 30,
+            collected_commission: 40,
+            created_at: 50,
+        })
+        .unwrap();
+        assert_eq!(operator_v1.len(), v1::Operator::LEN);
+        let migrated = migration::migrate(&program_id, AccountType::Operator, &operator_v1).unwrap().unwrap();
+        let operator = Operator::unpack(&migrated).unwrap();
+        assert_eq!((operator.authority, operator.bump, operator.delegations), (authority, 250, 2));
+        assert_eq!((operator.pending_commission, operator.collected_commission), (30, 40));
+        assert_eq!(operator.reward_vault_bump, find_reward_vault_address(&program_id, &mint).1);
+        assert_eq!(migration::migrate(&program_id, AccountType::Operator, &migrated), Ok(None));
+
+        // Entries from before liquid unbonding go to the owner of their
+        // position.
+        let stake_account = Pubkey::new_unique();
+        let entry_v1 = borsh::to_vec(&v1::UnbondingEntry {
+            header: AccountHeader { account_type: AccountType::UnbondingEntry, version: 1, is_initialized: true },
+            stake_account,
+            id: 3,
+            bump: 249,
+            amount: 60,
+            requested_at: 70,
+            withdrawable_at: 80,
+        })
+        .unwrap();
+        assert_eq!(entry_v1.len(), v1::UnbondingEntry::LEN);
+        let migrated = migration::migrate(&program_id, AccountType::UnbondingEntry, &entry_v1).unwrap().unwrap();
+        let entry = UnbondingEntry::unpack(&migrated).unwrap();
+        assert_eq!((entry.stake_account, entry.owner, entry.id, entry.amount), (stake_account, Pubkey::default(), 3, 60));
+        assert_eq!((entry.requested_at, entry.withdrawable_at), (70, 80));
+        assert_eq!(migration::migrate(&program_id, AccountType::UnbondingEntry, &migrated), Ok(None));
+
+        // A layout is only accepted for the type it was written for.
+        assert_eq!(
+            migration::migrate(&program_id, AccountType::VestingSchedule, &stake_v0),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            migration::migrate(&program_id, AccountType::StakeInfo, &vesting_v1),
+            Err(TokenError::InvalidAccountType.into())
+        );
+        assert!(migration::migrate(&program_id, AccountType::StakeInfo, &[0; v0::StakeInfo::LEN]).is_err());
+    }
+
+    #[test]
+    fn test_parse_event_logs() {
+        use base64::Engine;
+        use solana_token::event::{self, EventError, TokenEvent, EVENT_VERSION};
+
+        let program_id = Pubkey::new_unique();
+        let other_program = Pubkey::new_unique();
+        let staked = TokenEvent::Staked {
+            stake_account: Pubkey::new_unique(),
+            staker: Pubkey::new_unique(),
+            amount: 10,
+            staked_amount: 30,
+            timestamp: 1_700_000_000,
+        };
+        let frozen = TokenEvent::Frozen {
+            mint: Pubkey::new_unique(),
+            account: Pubkey::new_unique(),
+            timestamp: 1_700_000_001,
+        };
+        let encode = |event: &TokenEvent| base64::engine::general_purpose::STANDARD.encode(event.pack());
+
+        let logs = vec![
+            format!("Program {} invoke [1]", program_id),
+            "Program log: Instruction: Stake".to_string(),
+            format!("Program data: {}", encode(&staked)),
+            format!("Program {} invoke [2]", other_program),
+            format!("Program data: {}", encode(&frozen)),
+            format!("Program {} success", other_program),
+            format!("Program data: {}", encode(&frozen)),
+            format!("Program {} consumed 5000 of 200000 compute units", program_id),
+            format!("Program {} success", program_id),
+            format!("Program data: {}", encode(&staked)),
+        ];
+        assert_eq!(event::parse_logs(&program_id, &logs), Ok(vec![staked.clone(), frozen]));
+
+        let mut data = staked.pack();
+        assert_eq!(data[0], EVENT_VERSION);
+        data[0] = EVENT_VERSION + 1;
+        assert_eq!(TokenEvent::unpack(&data), Err(EventError::UnsupportedVersion(EVENT_VERSION + 1)));
+        let logs = [format!("Program {} invoke [1]", program_id), "Program data: !!".to_string()];
+        assert_eq!(event::parse_logs(&program_id, &logs), Err(EventError::InvalidBase64));
+    }
+
+    #[cfg(feature = "idl")]
+    #[test]
+    fn test_idl_matches_instruction_builders() {
+        use solana_token::idl;
+
+        let key = Pubkey::new_unique();
+        let builders = vec![
+            instruction::initialize_mint(&key, &key, &key, None, 9),
+            instruction::initialize_account(&key, &key, &key, &key),
+            instruction::transfer(&key, &key, &key, &key, 1),
+            instruction::burn(&key, &key, &key, &key, 1),
+            instruction::mint_to(&key, &key, &key, &key, 1),
+            instruction::freeze(&key, &key, &key, &key),
+            instruction::thaw(&key, &key, &key, &key),
+            instruction::set_authority(&key, &key, None, 0, &key),
+            instruction::stake(&key, &key, &key, &key, 1, None),
+            instruction::unstake(&key, &key, &key, &key, 1, None),
+            instruction::upgrade_program(&key, &key, &key, &key),
+            instruction::initialize_token_info(&key, &key, &key, &key),
+            instruction::create_vesting_schedule(&key, &key, &key, &key, &key, AllocationType::Team, 1, 0, 0, 1),
+            instruction::release_vested_tokens(&key, &key, &key, &key, &key),
+            instruction::migrate_account(&key, &key, &key, &key, AccountType::TokenInfo),
+            instruction::query_vested_amount(&key, &key),
+            instruction::query_releasable_amount(&key, &key),
+            instruction::query_stake_info(&key, &key),
+            instruction::query_allocation_remaining(&key, &key, AllocationType::Team),
+            instruction::initialize_vault(&key, &key, &key),
+            instruction::initialize_stake(&key, &key, &key, LockTier::Days30),
+            instruction::initialize_staking_pool(&key, &key, &key, &key, 1),
+            instruction::fund_rewards(&key, &key, &key, &key, &key, 1),
+            instruction::claim_rewards(&key, &key, &key, &key, None),
+            instruction::query_pending_rewards(&key, &key, &key),
+            instruction::initialize_staking_config(&key, &key, &key, &key, StakingParams::default()),
+            instruction::update_staking_config(&key, &key, &key, &key, StakingParams::default()),
+            instruction::extend_lock(&key, &key, &key, LockTier::Days90, None),
+            instruction::request_unstake(&key, &key, &key, 0, 1, None),
+            instruction::withdraw_unbonded(&key, &key, &key, &key, 0),
+            instruction::cancel_unbonding(&key, &key, &key, 0, None),
+            instruction::emergency_unstake(&key, &key, &key, &key, &key, 1, None),
+            instruction::initialize_liquid_pool(&key, &key, &key, &key),
+            instruction::liquid_stake(&key, &key, &key, &key, &key, 1),
+            instruction::liquid_unstake(&key, &key, &key, &key, &key, 1),
+            instruction::compound_rewards(&key, &key, &key, false, None),
+            instruction::set_auto_compound(&key, &key, &key, true),
+            instruction::initialize_locker(&key, &key, &key, &key, 1),
+            instruction::create_vote_lock(&key, &key, &key, &key, 1, 1),
+            instruction::increase_vote_lock_amount(&key, &key, &key, &key, 1),
+            instruction::extend_vote_lock(&key, &key, &key, 1),
+            instruction::withdraw_vote_lock(&key, &key, &key, &key),
+            instruction::query_voting_power(&key, &key, &key, 1),
+            instruction::query_total_voting_power(&key, &key, 1),
+            instruction::register_operator(&key, &key, &key, 1),
+            instruction::delegate_stake(&key, &key, &key, &key, None),
+            instruction::claim_commission(&key, &key, &key, &key),
+            instruction::create_farm(&key, &key, &key, &key, &key, &key, 1, 1, 1, 2),
+            instruction::fund_farm(&key, &key, &key, &key, &key, 1),
+            instruction::farm_stake(&key, &key, &key, &key, &key, 1),
+            instruction::farm_unstake(&key, &key, &key, &key, &key, 1),
+            instruction::claim_farm_rewards(&key, &key, &key, &key, &key),
+            instruction::query_farm_rewards(&key, &key, &key),
+            instruction::initialize_emission_schedule(&key, &key, &key, &key, 1, 1, 0),
+            instruction::advance_epoch(&key, &key, &key),
+            instruction::query_emission_schedule(&key, &key),
+            instruction::undelegate_stake(&key, &key, &key, &key),
+            instruction::request_liquid_unstake(&key, &key, &key, &key, 0, 1),
+            instruction::withdraw_liquid_unbonded(&key, &key, &key, &key, 0),
+            instruction::update_farm(&key, &key, &key, &key, &key, 1, 2),
+        ];
+
+        let generated = idl::generate();
+        let instructions = generated["instructions"].as_array().unwrap();
+        assert_eq!(instructions.len(), builders.len());
+        for (tag, (ix, spec)) in builders.iter().zip(idl::INSTRUCTION_ACCOUNTS).enumerate() {
+            assert_eq!(ix.data[0] as usize, tag);
+            assert_eq!(instructions[tag]["discriminant"]["value"], tag);
+            let flags: Vec<_> = ix.accounts.iter().map(|meta| (meta.is_writable, meta.is_signer)).collect();
+            let expected: Vec<_> = spec.iter().map(|(_, writable, signer)| (*writable, *signer)).collect();
+            assert_eq!(flags, expected, "account flags of instruction {}", tag);
+        }
+
+        // Optional accounts follow the required ones.
+        let to_treasury = StakingParams {
+            penalty_destination: PenaltyDestination::Treasury,
+            ..StakingParams::default()
+        };
+        let with_optional = [
+            instruction::stake(&key, &key, &key, &key, 1, Some(&key)),
+            instruction::unstake(&key, &key, &key, &key, 1, Some(&key)),
+            instruction::claim_rewards(&key, &key, &key, &key, Some(&key)),
+            instruction::initialize_staking_config(&key, &key, &key, &key, to_treasury),
+            instruction::update_staking_config(&key, &key, &key, &key, to_treasury),
+            instruction::extend_lock(&key, &key, &key, LockTier::Days90, Some(&key)),
+            instruction::request_unstake(&key, &key, &key, 0, 1, Some(&key)),
+            instruction::cancel_unbonding(&key, &key, &key, 0, Some(&key)),
+            instruction::emergency_unstake(&key, &key, &key, &key, &key, 1, Some(&key)),
+            instruction::compound_rewards(&key, &key, &key, false, Some(&key)),
+            instruction::delegate_stake(&key, &key, &key, &key, Some(&key)),
+        ];
+        assert_eq!(with_optional.len(), idl::OPTIONAL_ACCOUNTS.len());
+        for ix in with_optional {
+            let tag = ix.data[0] as usize;
+            let required = idl::INSTRUCTION_ACCOUNTS[tag].len();
+            let (_, (_, writable, signer)) =
+                idl::OPTIONAL_ACCOUNTS.iter().find(|(optional_tag, _)| *optional_tag as usize == tag).unwrap();
+            assert_eq!(ix.accounts.len(), required + 1);
+            assert_eq!((ix.accounts[required].is_writable, ix.accounts[required].is_signer), (*writable, *signer));
+            let accounts = instructions[tag]["accounts"].as_array().unwrap();
+            assert_eq!(accounts.len(), required + 1);
+            assert_eq!(accounts[required]["isOptional"], true);
+        }
+        assert_eq!(generated["accounts"].as_array().unwrap().len(), 13);
+        assert_eq!(generated["errors"][0]["name"], "InvalidInstruction");
+    }
 }